@@ -0,0 +1,290 @@
+//! Authentication and authorization of the HTTP requests.
+//!
+//! The credentials file has one rule per line, empty lines and lines starting with `#` are ignored:
+//! ```text
+//! user <name> <argon2 hash of the password> <read|write> [<graph>...]
+//! token <name> <argon2 hash of the token> <read|write> [<graph>...]
+//! anonymous <read|write> [<graph>...]
+//! ```
+//! The hashes are PHC strings like `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`, see [`hash_secret`].
+//! Users authenticate with HTTP Basic, tokens with `Authorization: Bearer <name>:<token>`.
+//! The optional graphs, IRIs or `default` for the default graph, restrict the graphs the rule allows to write to.
+//! Without an `anonymous` rule, requests without credentials are rejected.
+
+use anyhow::{anyhow, bail, Context};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use oxhttp::model::{HeaderName, Request};
+use oxigraph::model::{GraphName, NamedNode};
+use rand::random;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Role granted by a rule of the credentials file
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Role {
+    /// Only the queries and the other read routes
+    Read,
+    /// The updates and the Graph Store Protocol writes too
+    Write,
+}
+
+/// What an authenticated request is allowed to do
+#[derive(Clone)]
+pub struct Permission {
+    role: Role,
+    /// The graphs that might be written, all of them if `None`
+    graphs: Option<Vec<GraphName>>,
+}
+
+impl Permission {
+    pub fn can_write_some_graph(&self) -> bool {
+        self.role == Role::Write
+    }
+
+    /// If it allows to write to a graph, `None` meaning the full dataset
+    pub fn can_write(&self, graph: Option<&GraphName>) -> bool {
+        self.role == Role::Write
+            && match (&self.graphs, graph) {
+                (None, _) => true,
+                (Some(graphs), Some(graph)) => graphs.contains(graph),
+                (Some(_), None) => false,
+            }
+    }
+}
+
+/// The content of the credentials file
+#[derive(Clone)]
+pub struct Credentials {
+    /// Password hashes and permissions by user name
+    users: HashMap<String, (String, Permission)>,
+    /// Token hashes and permissions by token name
+    tokens: HashMap<String, (String, Permission)>,
+    anonymous: Option<Permission>,
+}
+
+impl Credentials {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        Self::parse(
+            &fs::read_to_string(path).with_context(|| {
+                format!("Failed to read the credentials file {}", path.display())
+            })?,
+        )
+        .with_context(|| format!("Invalid credentials file {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let mut credentials = Self {
+            users: HashMap::new(),
+            tokens: HashMap::new(),
+            anonymous: None,
+        };
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            credentials
+                .add_rule(line)
+                .with_context(|| format!("Error on line {}", line_number + 1))?;
+        }
+        Ok(credentials)
+    }
+
+    fn add_rule(&mut self, line: &str) -> anyhow::Result<()> {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("user") => {
+                let name = fields.next().context("The user name is missing")?;
+                let hash = parse_hash(fields.next())?;
+                self.users
+                    .insert(name.into(), (hash, parse_permission(fields)?));
+            }
+            Some("token") => {
+                let name = fields.next().context("The token name is missing")?;
+                let hash = parse_hash(fields.next())?;
+                self.tokens
+                    .insert(name.into(), (hash, parse_permission(fields)?));
+            }
+            Some("anonymous") => self.anonymous = Some(parse_permission(fields)?),
+            Some(kind) => bail!("Unknown rule kind '{kind}', expecting user, token or anonymous"),
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// Returns the permission of the request or `None` if its credentials are missing or wrong
+    pub fn authenticate(&self, request: &Request) -> Option<&Permission> {
+        let Some(authorization) = request.header(&HeaderName::AUTHORIZATION) else {
+            return self.anonymous.as_ref();
+        };
+        let authorization = authorization.to_str().ok()?;
+        let (scheme, value) = authorization.trim().split_once(' ')?;
+        let value = value.trim();
+        let (secrets, name, secret) = if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(decode_base64(value)?).ok()?;
+            let (name, password) = decoded.split_once(':')?;
+            (&self.users, name.to_owned(), password.to_owned())
+        } else if scheme.eq_ignore_ascii_case("bearer") {
+            let (name, token) = value.split_once(':')?;
+            (&self.tokens, name.to_owned(), token.to_owned())
+        } else {
+            return None;
+        };
+        let Some((hash, permission)) = secrets.get(&name) else {
+            // As slow as a wrong secret so that the existing names are not disclosed
+            verify_secret(&secret, UNKNOWN_NAME_HASH);
+            return None;
+        };
+        verify_secret(&secret, hash).then_some(permission)
+    }
+}
+
+/// The hash verified when the name is unknown, no secret is known to match it
+const UNKNOWN_NAME_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$Rk7Gn2mVJ8tQ0bXcLwYpZA$2bM9m2Xb3Cq1Zk6Wc1yJ2kXoKx8C9rWQ0mY1t5o2fWk";
+
+/// Hashes a password or a token for the credentials file with Argon2id and a random salt
+pub fn hash_secret(secret: &str) -> anyhow::Result<String> {
+    let salt = SaltString::encode_b64(&random::<[u8; 16]>()).map_err(|e| anyhow!("{e}"))?;
+    Ok(Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .map_err(|e| anyhow!("{e}"))?
+        .to_string())
+}
+
+/// Checks `secret` against its hash, the comparison of the hashes is in constant time
+fn verify_secret(secret: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(secret.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+fn parse_hash(value: Option<&str>) -> anyhow::Result<String> {
+    let value = value.context("The argon2 hash is missing")?;
+    let hash = PasswordHash::new(value)
+        .map_err(|e| anyhow!("'{value}' is not an argon2 hash in the PHC format: {e}"))?;
+    if !hash.algorithm.as_str().starts_with("argon2") {
+        bail!("'{value}' is not an argon2 hash");
+    }
+    Ok(value.into())
+}
+
+fn parse_permission<'a>(mut fields: impl Iterator<Item = &'a str>) -> anyhow::Result<Permission> {
+    let role = match fields.next() {
+        Some("read") => Role::Read,
+        Some("write") => Role::Write,
+        Some(role) => bail!("Unknown role '{role}', expecting read or write"),
+        None => bail!("The role is missing"),
+    };
+    let graphs = fields
+        .map(|graph| {
+            Ok(if graph == "default" {
+                GraphName::DefaultGraph
+            } else {
+                NamedNode::new(graph.trim_start_matches('<').trim_end_matches('>'))?.into()
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if role == Role::Read && !graphs.is_empty() {
+        bail!("Graphs might only be given to the write role");
+    }
+    Ok(Permission {
+        role,
+        graphs: (!graphs.is_empty()).then_some(graphs),
+    })
+}
+
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim_end_matches('=');
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    for byte in input.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = ((buffer << 6) | u32::from(value)) & 0xFFFF;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push(u8::try_from((buffer >> bits) & 0xFF).ok()?);
+        }
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::panic_in_result_fn)]
+
+    use super::*;
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("YWxpY2U6c2VjcmV0").unwrap(), b"alice:secret");
+        assert_eq!(decode_base64("YQ==").unwrap(), b"a");
+        assert!(decode_base64("YQ?=").is_none());
+    }
+
+    #[test]
+    fn test_authenticate() -> anyhow::Result<()> {
+        let credentials = Credentials::parse(&format!(
+            "user alice {} write <http://example.com/g>\ntoken ci {} read\n",
+            hash_secret("secret")?,
+            hash_secret("t0ken")?
+        ))?;
+        let authenticate = |authorization: &str| -> anyhow::Result<Option<Role>> {
+            let request =
+                Request::builder(oxhttp::model::Method::GET, "http://localhost/".parse()?)
+                    .with_header(HeaderName::AUTHORIZATION, authorization)?
+                    .build();
+            Ok(credentials
+                .authenticate(&request)
+                .map(|permission| permission.role))
+        };
+        // alice:secret, alice:wrong and bob:secret
+        assert!(authenticate("Basic YWxpY2U6c2VjcmV0")? == Some(Role::Write));
+        assert!(authenticate("Basic YWxpY2U6d3Jvbmc=")?.is_none());
+        assert!(authenticate("Basic Ym9iOnNlY3JldA==")?.is_none());
+        assert!(authenticate("Bearer ci:t0ken")? == Some(Role::Read));
+        assert!(authenticate("Bearer ci:secret")?.is_none());
+        assert!(authenticate("Bearer t0ken")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_secret() -> anyhow::Result<()> {
+        let hash = hash_secret("secret")?;
+        assert!(hash.starts_with("$argon2id$"));
+        assert_ne!(hash, hash_secret("secret")?, "The salt should be random");
+        assert!(verify_secret("secret", &hash));
+        assert!(!verify_secret("Secret", &hash));
+        assert!(!verify_secret("secret", UNKNOWN_NAME_HASH));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Credentials::parse("user alice foo write").is_err());
+        assert!(Credentials::parse(
+            "user alice 2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b write"
+        )
+        .is_err());
+        assert!(
+            Credentials::parse("token $argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHQ$aGFzaA read")
+                .is_err()
+        );
+        assert!(Credentials::parse("anonymous admin").is_err());
+        assert!(Credentials::parse("anonymous read <http://example.com/g>").is_err());
+        assert!(Credentials::parse("group foo").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Configuration file of an HTTP server serving several datasets.
+//!
+//! Each dataset is served below `/<name>`, e.g. `/<name>/query`, `/<name>/update` and `/<name>/store`:
+//! ```toml
+//! [datasets.hprc]
+//! location = "hprc.gbz"
+//! base-iri = "https://example.org/hprc"
+//! read-only = true
+//! cors = true
+//! query-timeout = 30
+//!
+//! [datasets.hprc.prefixes]
+//! vg = "http://biohackathon.org/resource/vg#"
+//! ```
+//! Relative paths are resolved against the directory of the configuration file.
+//!
+//! The IRIs of the nodes, paths and steps of a dataset are minted below the path `/<name>`
+//! so that they are described by the server, the path of a `base-iri` must be `/<name>`.
+
+use anyhow::{bail, ensure, Context};
+use clap::ValueEnum;
+use oxigraph::store::RdfProfile;
+use oxiri::Iri;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The content of the configuration file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The datasets by name
+    pub datasets: BTreeMap<String, DatasetConfig>,
+}
+
+/// A dataset served below `/<name>`
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DatasetConfig {
+    /// The graph file, read like the `--location` of `serve`
+    pub location: PathBuf,
+    /// GAF file of read alignments to expose next to the graph
+    pub alignments: Option<PathBuf>,
+    /// Skip the invalid records of the GFA file instead of failing
+    #[serde(default)]
+    pub lenient: bool,
+    /// Conventions of the IRIs of the graph
+    #[serde(default)]
+    pub rdf_profile: RdfProfileName,
+    /// The IRI under which the nodes, paths and steps are named, with the path `/<name>`.
+    ///
+    /// By default the host of the IRIs of the profile followed by `/<name>`.
+    pub base_iri: Option<String>,
+    /// Rejects the updates and the Graph Store Protocol writes
+    #[serde(default)]
+    pub read_only: bool,
+    /// Prefixes usable in the queries and updates without declaring them.
+    ///
+    /// They are declared before the text of the requests, see [`DatasetConfig::prologue`].
+    #[serde(default)]
+    pub prefixes: BTreeMap<String, String>,
+    /// Allows cross-origin requests
+    #[serde(default)]
+    pub cors: bool,
+    /// Maximal duration of the evaluation of a SPARQL query, in seconds
+    pub query_timeout: Option<f64>,
+    /// Maximal size of the SPARQL query and update request bodies, in bytes
+    pub max_body_size: Option<u64>,
+}
+
+/// The [`RdfProfile`] of a graph, as named on the command line and in the configuration file
+#[derive(Clone, Copy, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RdfProfileName {
+    /// IRIs under https://example.org
+    #[default]
+    Oxiqle,
+    /// The same IRIs as the materialised RDF of vg
+    Vg,
+}
+
+impl From<RdfProfileName> for RdfProfile {
+    fn from(name: RdfProfileName) -> Self {
+        match name {
+            RdfProfileName::Oxiqle => Self::Oxiqle,
+            RdfProfileName::Vg => Self::Vg,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let mut config = Self::parse(&fs::read_to_string(path).with_context(|| {
+            format!("Failed to read the configuration file {}", path.display())
+        })?)
+        .with_context(|| format!("Invalid configuration file {}", path.display()))?;
+        if let Some(directory) = path.parent() {
+            for dataset in config.datasets.values_mut() {
+                dataset.location = directory.join(&dataset.location);
+                dataset.alignments = dataset
+                    .alignments
+                    .as_ref()
+                    .map(|alignments| directory.join(alignments));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        ensure!(!config.datasets.is_empty(), "No dataset is configured");
+        for (name, dataset) in &config.datasets {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                bail!("The dataset name '{name}' should only contain ASCII letters, digits, '-', '_' and '.'");
+            }
+            ensure!(
+                dataset.location != Path::new("-"),
+                "The dataset {name} can not be read from stdin"
+            );
+            if let Some(base_iri) = &dataset.base_iri {
+                let base_iri = Iri::parse(base_iri.as_str())
+                    .with_context(|| format!("Invalid base IRI of the dataset {name}"))?;
+                ensure!(
+                    base_iri.path().trim_end_matches('/') == format!("/{name}"),
+                    "The path of the base IRI of the dataset {name} should be /{name} for its resources to be served"
+                );
+            }
+            for (prefix, iri) in &dataset.prefixes {
+                Iri::parse(iri.as_str()).with_context(|| {
+                    format!("Invalid IRI of the prefix {prefix} of the dataset {name}")
+                })?;
+            }
+            if let Some(query_timeout) = dataset.query_timeout {
+                ensure!(
+                    query_timeout.is_finite() && query_timeout > 0.,
+                    "The query timeout of the dataset {name} should be a positive number of seconds"
+                );
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl DatasetConfig {
+    /// The declarations of the prefixes to add before the queries and the updates.
+    ///
+    /// The text of the requests is shifted by it: the positions of the syntax errors
+    /// and the queries written to the slow query log include these declarations.
+    pub fn prologue(&self) -> String {
+        let mut prologue = String::new();
+        for (prefix, iri) in &self.prefixes {
+            write!(prologue, "PREFIX {prefix}: <{iri}> ").unwrap();
+        }
+        prologue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::panic_in_result_fn)]
+
+    use super::*;
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        let config = Config::parse(
+            "[datasets.hprc]\n\
+             location = \"hprc.gfa\"\n\
+             read-only = true\n\
+             query-timeout = 30\n\
+             [datasets.hprc.prefixes]\n\
+             vg = \"http://biohackathon.org/resource/vg#\"\n\
+             [datasets.t]\n\
+             location = \"t.gfa\"\n",
+        )?;
+        assert_eq!(config.datasets.len(), 2);
+        let hprc = &config.datasets["hprc"];
+        assert!(hprc.read_only);
+        assert_eq!(hprc.query_timeout, Some(30.));
+        assert_eq!(hprc.prefixes["vg"], "http://biohackathon.org/resource/vg#");
+        assert_eq!(
+            hprc.prologue(),
+            "PREFIX vg: <http://biohackathon.org/resource/vg#> "
+        );
+        assert!(!config.datasets["t"].read_only);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Config::parse("").is_err());
+        assert!(Config::parse("[datasets.t]\nlocation = \"t.gfa\"\nfoo = 1\n").is_err());
+        assert!(Config::parse("[datasets.\"a/b\"]\nlocation = \"t.gfa\"\n").is_err());
+        assert!(Config::parse("[datasets.t]\nlocation = \"-\"\n").is_err());
+        assert!(
+            Config::parse("[datasets.t]\nlocation = \"t.gfa\"\nrdf-profile = \"foo\"\n").is_err()
+        );
+        assert!(Config::parse("[datasets.t]\nlocation = \"t.gfa\"\nbase-iri = \"foo\"\n").is_err());
+        assert!(Config::parse(
+            "[datasets.t]\nlocation = \"t.gfa\"\nbase-iri = \"https://example.org/u\"\n"
+        )
+        .is_err());
+        assert!(Config::parse(
+            "[datasets.t]\nlocation = \"t.gfa\"\nbase-iri = \"https://example.org/t/\"\n"
+        )
+        .is_ok());
+    }
+}
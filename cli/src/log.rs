@@ -0,0 +1,289 @@
+//! Request and slow query logs of the HTTP server, written on stderr.
+
+use oxhttp::model::{Body, Request, Status};
+use oxigraph::sparql::QueryExplanation;
+use std::fmt::Write;
+use std::io::{self, Read};
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Format of the request log
+#[derive(Clone, Copy)]
+pub enum RequestLogFormat {
+    /// One JSON object per line
+    Json,
+    /// The [Common Log Format](https://httpd.apache.org/docs/current/logs.html#common) followed by the route and the duration in seconds
+    Common,
+}
+
+impl RequestLogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "common" => Some(Self::Common),
+            _ => None,
+        }
+    }
+}
+
+/// Destination of the log lines
+#[derive(Clone, Default)]
+pub enum LogOutput {
+    #[default]
+    Stderr,
+    /// Keeps the lines in memory to check them
+    #[cfg(test)]
+    Memory(Arc<Mutex<Vec<String>>>),
+}
+
+impl LogOutput {
+    fn write_line(&self, line: String) {
+        match self {
+            Self::Stderr => eprintln!("{line}"),
+            #[cfg(test)]
+            Self::Memory(lines) => lines.lock().unwrap().push(line),
+        }
+    }
+}
+
+/// A handled request, logged once its response has been sent
+pub struct RequestLogEntry {
+    format: RequestLogFormat,
+    output: LogOutput,
+    method: String,
+    path: String,
+    query: Option<String>,
+    route: &'static str,
+    status: Status,
+    start: Instant,
+}
+
+impl RequestLogEntry {
+    pub fn new(
+        format: RequestLogFormat,
+        output: LogOutput,
+        request: &Request,
+        route: &'static str,
+        status: Status,
+        start: Instant,
+    ) -> Self {
+        Self {
+            format,
+            output,
+            method: request.method().to_string(),
+            path: request.url().path().into(),
+            query: request.url().query().map(Into::into),
+            route,
+            status,
+            start,
+        }
+    }
+
+    /// Writes the line with the size of the response body, the duration is the one since the start of the request
+    pub fn write(&self, size: u64) {
+        let status = u16::from(self.status);
+        let duration = self.start.elapsed().as_secs_f64();
+        let (year, month, day, hour, minute, second) = utc_date_time(SystemTime::now());
+        let route = self.route;
+        self.output.write_line(match self.format {
+            RequestLogFormat::Json => format!(
+                "{{\"time\":\"{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z\",\"method\":{},\"path\":{},\"route\":{},\"status\":{status},\"duration\":{duration},\"size\":{size}}}",
+                json_string(&self.method),
+                json_string(&self.path),
+                json_string(route),
+            ),
+            RequestLogFormat::Common => {
+                let target = if let Some(query) = &self.query {
+                    format!("{}?{query}", self.path)
+                } else {
+                    self.path.clone()
+                };
+                format!(
+                    "- - - [{day:02}/{}/{year:04}:{hour:02}:{minute:02}:{second:02} +0000] \"{} {target} HTTP/1.1\" {status} {size} \"{route}\" {duration}",
+                    MONTHS[usize::try_from(month - 1).unwrap_or_default()],
+                    self.method,
+                )
+            }
+        });
+    }
+}
+
+/// Streamed response body that writes the request log line once it has been sent, or dropped
+pub struct LoggedBody {
+    body: Body,
+    entry: RequestLogEntry,
+    size: u64,
+}
+
+impl LoggedBody {
+    pub fn new(body: Body, entry: RequestLogEntry) -> Self {
+        Self {
+            body,
+            entry,
+            size: 0,
+        }
+    }
+}
+
+impl Read for LoggedBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.body.read(buf)?;
+        self.size += u64::try_from(len).unwrap_or(u64::MAX);
+        Ok(len)
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        self.entry.write(self.size);
+    }
+}
+
+/// Threshold above which the queries are logged
+#[derive(Clone, Copy)]
+pub struct SlowQueryLog {
+    pub threshold: Duration,
+    /// Evaluates the queries with statistics to log their explanation
+    pub explain: bool,
+}
+
+/// A query that is logged if its evaluation is slower than the threshold
+pub struct SlowQuery {
+    log: SlowQueryLog,
+    output: LogOutput,
+    query: String,
+    default_graph_uris: Vec<String>,
+    named_graph_uris: Vec<String>,
+    union_default_graph: bool,
+    explanation: Option<QueryExplanation>,
+}
+
+impl SlowQuery {
+    pub fn new(
+        log: SlowQueryLog,
+        output: LogOutput,
+        query: &str,
+        default_graph_uris: &[String],
+        named_graph_uris: &[String],
+        union_default_graph: bool,
+    ) -> Self {
+        Self {
+            log,
+            output,
+            query: query.into(),
+            default_graph_uris: default_graph_uris.to_vec(),
+            named_graph_uris: named_graph_uris.to_vec(),
+            union_default_graph,
+            explanation: None,
+        }
+    }
+
+    pub fn explain(&self) -> bool {
+        self.log.explain
+    }
+
+    pub fn set_explanation(&mut self, explanation: QueryExplanation) {
+        self.explanation = Some(explanation);
+    }
+
+    /// Writes the query as a JSON line if the evaluation took longer than the threshold
+    pub fn report(&self, duration: Duration) {
+        if duration < self.log.threshold {
+            return;
+        }
+        let (year, month, day, hour, minute, second) = utc_date_time(SystemTime::now());
+        let mut line = String::new();
+        write!(
+            line,
+            "{{\"time\":\"{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z\",\"slow_query\":{},\"duration\":{},\"default-graph-uri\":[{}],\"named-graph-uri\":[{}],\"union-default-graph\":{}",
+            json_string(&self.query),
+            duration.as_secs_f64(),
+            self.default_graph_uris.iter().map(|uri| json_string(uri)).collect::<Vec<_>>().join(","),
+            self.named_graph_uris.iter().map(|uri| json_string(uri)).collect::<Vec<_>>().join(","),
+            self.union_default_graph,
+        )
+        .unwrap();
+        if let Some(explanation) = &self.explanation {
+            let mut json = Vec::new();
+            if explanation.write_in_json(&mut json).is_ok() {
+                write!(line, ",\"explanation\":{}", String::from_utf8_lossy(&json)).unwrap();
+            }
+        }
+        line.push('}');
+        self.output.write_line(line);
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => write!(output, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Year, month, day, hour, minute and second of a time in UTC
+fn utc_date_time(time: SystemTime) -> (u64, u64, u64, u64, u64, u64) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+    // Conversion from days to civil dates by Howard Hinnant
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utc_date_time() {
+        assert_eq!(utc_date_time(UNIX_EPOCH), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(
+            utc_date_time(UNIX_EPOCH + Duration::from_secs(1_709_251_199)),
+            (2024, 2, 29, 23, 59, 59)
+        );
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+}
@@ -1,42 +1,72 @@
 #![allow(clippy::print_stderr, clippy::cast_precision_loss, clippy::use_debug)]
+mod auth;
+mod config;
+mod log;
+mod metrics;
+mod reload;
+mod tls;
+
+use crate::auth::{hash_secret, Credentials, Permission};
+use crate::config::{Config, DatasetConfig, RdfProfileName};
+use crate::log::{
+    LogOutput, LoggedBody, RequestLogEntry, RequestLogFormat, SlowQuery, SlowQueryLog,
+};
+use crate::metrics::{MeteredServiceHandler, Metrics, QueryTimer};
+use crate::reload::{ReloadableStore, StoreLoader};
+use crate::tls::TlsServer;
 use anyhow::{bail, ensure, Context};
 use clap::{Parser, Subcommand, ValueHint};
 use flate2::read::MultiGzDecoder;
 use oxhttp::model::{Body, HeaderName, HeaderValue, Method, Request, Response, Status};
 use oxhttp::Server;
 use oxigraph::io::{RdfFormat, RdfParser, RdfSerializer};
+use oxigraph::model::vocab::{rdf, xsd};
 use oxigraph::model::{
-    GraphName, GraphNameRef, IriParseError, NamedNode, NamedNodeRef, NamedOrBlankNode,
+    BlankNode, GraphName, GraphNameRef, IriParseError, Literal, NamedNode, NamedNodeRef,
+    NamedOrBlankNode, Subject, Term, Triple,
 };
 use oxigraph::sparql::results::{QueryResultsFormat, QueryResultsSerializer};
-use oxigraph::sparql::{Query, QueryOptions, QueryResults, Update};
-use oxigraph::store::{BulkLoader, LoaderError, Store};
+use oxigraph::sparql::{
+    EvaluationError, Query, QueryOptions, QueryResults, Update, EXTENSION_FUNCTIONS,
+};
+use oxigraph::store::{BulkLoader, LoaderError, QuadIter, RdfProfile, Store, ValidationError};
 use oxiri::Iri;
 use rand::random;
 use rayon_core::ThreadPoolBuilder;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::{max, min};
+use std::collections::{BTreeMap, VecDeque};
 #[cfg(target_os = "linux")]
 use std::env;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{self, stdin, stdout, BufWriter, Read, Write};
-use std::net::ToSocketAddrs;
+use std::iter::Peekable;
+use std::net::{TcpListener, ToSocketAddrs};
 #[cfg(target_os = "linux")]
 use std::os::unix::net::UnixDatagram;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
-use std::thread::available_parallelism;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, available_parallelism};
 use std::time::{Duration, Instant};
-use std::{fmt, fs, str};
+use std::{fmt, fs, mem, str};
 use url::form_urlencoded;
 
 const MAX_SPARQL_BODY_SIZE: u64 = 0x0010_0000;
 const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+const SERVER_NAME: &str = concat!("Oxigraph/", env!("CARGO_PKG_VERSION"));
 const HTML_ROOT_PAGE: &str = include_str!("../templates/query.html");
 const LOGO: &str = include_str!("../logo.svg");
+const SD: &str = "http://www.w3.org/ns/sparql-service-description#";
+const VOID: &str = "http://rdfs.org/ns/void#";
+const HYDRA: &str = "http://www.w3.org/ns/hydra/core#";
+const VG: &str = "http://biohackathon.org/resource/vg#";
+const FRAGMENT_PAGE_SIZE: usize = 100;
+const MAX_FRAGMENT_CURSORS: usize = 64;
+const FRAGMENT_CURSOR_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
 
 #[derive(Parser)]
 #[command(about, version, name = "oxigraph")]
@@ -46,21 +76,92 @@ struct Args {
     command: Command,
 }
 
+/// Options shared by the commands starting an HTTP server
+#[derive(clap::Args)]
+struct ServerArgs {
+    /// Allows cross-origin requests
+    #[arg(long)]
+    cors: bool,
+    /// Maximal duration of the evaluation of a SPARQL query, in seconds.
+    ///
+    /// Requests might ask for a shorter one with the "timeout" parameter.
+    /// The results streamed when the timeout is reached are truncated and end with the error message.
+    #[arg(long, value_parser = parse_timeout)]
+    query_timeout: Option<Duration>,
+    /// Exposes Prometheus metrics on the /metrics route.
+    #[arg(long)]
+    metrics: bool,
+    /// Logs each request on stderr.
+    ///
+    /// "json" writes JSON lines, "common" the Common Log Format followed by the route and the duration.
+    #[arg(long, value_parser = request_log_format_from_name)]
+    request_log: Option<RequestLogFormat>,
+    /// Logs on stderr the SPARQL queries slower than this duration, in seconds.
+    ///
+    /// The full query text and its dataset parameters are written as JSON lines.
+    #[arg(long, value_parser = parse_timeout)]
+    slow_query_threshold: Option<Duration>,
+    /// Adds the explanation of the evaluation to the slow query log.
+    ///
+    /// The queries are then evaluated with statistics, which is slightly slower.
+    #[arg(long, requires = "slow_query_threshold")]
+    slow_query_explain: bool,
+    /// File of the users and bearer tokens allowed to use the server, with their roles.
+    ///
+    /// Each line is "user <name> <password hash> <read|write> [graphs]",
+    /// "token <name> <token hash> <read|write> [graphs]" or "anonymous <read|write> [graphs]".
+    /// The hashes are the argon2 ones printed by the hash-secret command.
+    /// The graphs, IRIs or "default", restrict the graphs the write role might change.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    auth_file: Option<PathBuf>,
+    /// PEM file of the TLS certificate chain, the server then only accepts HTTPS connections.
+    ///
+    /// On Unix, the certificate and its key are reloaded on SIGHUP.
+    #[arg(long, requires = "tls_key", value_hint = ValueHint::FilePath)]
+    tls_cert: Option<PathBuf>,
+    /// PEM file of the private key of the TLS certificate.
+    #[arg(long, requires = "tls_cert", value_hint = ValueHint::FilePath)]
+    tls_key: Option<PathBuf>,
+    /// Enables the POST /reload route that builds the graph again from its files and swaps it in.
+    ///
+    /// The running queries finish on the previous graph. With a credentials file, it requires
+    /// the write role on all graphs. The updates done on the previous graph are lost.
+    #[arg(long)]
+    reload_route: bool,
+}
+
 #[derive(Subcommand)]
 enum Command {
     /// Start Oxigraph HTTP server in read-write mode.
     Serve {
+        /// TOML file of the datasets to serve, each of them below /<name>.
+        ///
+        /// Each dataset has a "location" and might set "alignments", "lenient", "rdf-profile",
+        /// "base-iri", "read-only", "prefixes", "cors", "query-timeout" and "max-body-size".
+        #[arg(long, conflicts_with_all = ["location", "alignments", "lenient", "rdf_profile"], value_hint = ValueHint::FilePath)]
+        config: Option<PathBuf>,
         /// Directory in which the data should be persisted.
         ///
         /// If not present. An in-memory storage will be used.
+        ///
+        /// The GFA file might be compressed with gzip or zstd, "-" reads it from stdin.
+        /// Files ending with ".gbz" are read as vg GBZ graphs.
         #[arg(short, long, value_hint = ValueHint::DirPath)]
         location: Option<PathBuf>,
         /// Host and port to listen to.
         #[arg(short, long, default_value = "localhost:7878", value_hint = ValueHint::Hostname)]
         bind: String,
-        /// Allows cross-origin requests
+        /// GAF file of read alignments to expose next to the graph.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        alignments: Option<PathBuf>,
+        /// Skip the invalid records of the GFA file instead of failing.
         #[arg(long)]
-        cors: bool,
+        lenient: bool,
+        /// Conventions of the IRIs of the graph.
+        #[arg(long, value_enum, default_value_t)]
+        rdf_profile: RdfProfileName,
+        #[command(flatten)]
+        server: ServerArgs,
     },
     /// Start Oxigraph HTTP server in read-only mode.
     ///
@@ -68,15 +169,23 @@ enum Command {
     /// Opening as read-only while having an other process writing the database is undefined behavior.
     /// Please use the serve-secondary command in this case.
     ServeReadOnly {
-        /// Directory in which Oxigraph data are persisted.
-        #[arg(short, long, value_hint = ValueHint::DirPath)]
+        /// GFA or GBZ file to serve, "-" reads a GFA file from stdin.
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
         location: PathBuf,
         /// Host and port to listen to.
         #[arg(short, long, default_value = "localhost:7878")]
         bind: String,
-        /// Allows cross-origin requests
+        /// GAF file of read alignments to expose next to the graph.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        alignments: Option<PathBuf>,
+        /// Skip the invalid records of the GFA file instead of failing.
         #[arg(long)]
-        cors: bool,
+        lenient: bool,
+        /// Conventions of the IRIs of the graph.
+        #[arg(long, value_enum, default_value_t)]
+        rdf_profile: RdfProfileName,
+        #[command(flatten)]
+        server: ServerArgs,
     },
     /// Start Oxigraph HTTP server in secondary mode.
     ///
@@ -87,6 +196,9 @@ enum Command {
     /// Dirty reads might happen.
     ServeSecondary {
         /// Directory where the primary Oxigraph instance is writing to.
+        ///
+        /// Unlike the other commands, it can't be read from stdin and its invalid records are not skipped
+        /// because the graph follows the one of the primary instance.
         #[arg(long, value_hint = ValueHint::DirPath)]
         primary_location: PathBuf,
         /// Directory to which the current secondary instance might write to.
@@ -97,9 +209,14 @@ enum Command {
         /// Host and port to listen to.
         #[arg(short, long, default_value = "localhost:7878")]
         bind: String,
-        /// Allows cross-origin requests
-        #[arg(long)]
-        cors: bool,
+        /// GAF file of read alignments to expose next to the graph.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        alignments: Option<PathBuf>,
+        /// Conventions of the IRIs of the graph.
+        #[arg(long, value_enum, default_value_t)]
+        rdf_profile: RdfProfileName,
+        #[command(flatten)]
+        server: ServerArgs,
     },
     /// Creates database backup into a target directory.
     ///
@@ -182,9 +299,14 @@ enum Command {
     },
     /// Executes a SPARQL query against the store.
     Query {
-        /// Directory in which Oxigraph data are persisted.
-        #[arg(short, long, value_hint = ValueHint::DirPath)]
+        /// GFA or GBZ file to query.
+        ///
+        /// "-" reads a GFA file from stdin, the query must then be given with --query or --query-file.
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
         location: PathBuf,
+        /// Skip the invalid records of the GFA file instead of failing.
+        #[arg(long)]
+        lenient: bool,
         /// The SPARQL query to execute.
         ///
         /// If no query or query file are given, stdin is used.
@@ -210,6 +332,14 @@ enum Command {
         /// By default the format is guessed from the results file extension.
         #[arg(long, required_unless_present = "results_file")]
         results_format: Option<String>,
+        /// Conventions of the IRIs of the graph.
+        #[arg(long, value_enum, default_value_t)]
+        rdf_profile: RdfProfileName,
+        /// Number of partitions of the graph aggregated in parallel by aggregate queries.
+        ///
+        /// The default, 1, evaluates the whole query on a single thread.
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
         /// Prints to stderr a human-readable explanation of the query evaluation.
         ///
         /// Use the stats option to print also query evaluation statistics.
@@ -256,6 +386,19 @@ enum Command {
         #[arg(short, long, value_hint = ValueHint::DirPath)]
         location: PathBuf,
     },
+    /// Checks that a GFA file is a consistent pangenome graph.
+    ///
+    /// All the problems found are printed to stdout and the command fails if there is at least one.
+    Validate {
+        /// GFA or GBZ file to validate, "-" reads a GFA file from stdin.
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        location: PathBuf,
+        /// Skip the invalid records of the GFA file instead of failing.
+        #[arg(long)]
+        lenient: bool,
+    },
+    /// Prints the argon2 hash of a password or token read from stdin, for the --auth-file of serve.
+    HashSecret,
     /// Converts a RDF serialization from one format to an other.
     Convert {
         /// File to convert from.
@@ -308,38 +451,92 @@ pub fn main() -> anyhow::Result<()> {
     let matches = Args::parse();
     match matches.command {
         Command::Serve {
+            config: Some(config),
+            bind,
+            server,
+            ..
+        } => serve_datasets(&Config::from_file(&config)?, &bind, &server),
+        Command::Serve {
+            config: None,
             location,
             bind,
-            cors,
-        } => serve(
-            if let Some(location) = location {
-                Store::open(location)
-            } else {
-                Store::new()
-            }?,
-            &bind,
-            false,
-            cors,
-        ),
+            alignments,
+            lenient,
+            rdf_profile,
+            server,
+        } => {
+            let rdf_profile = RdfProfile::from(rdf_profile);
+            // Neither stdin nor an empty store might be read again
+            let reloadable = location
+                .as_deref()
+                .is_some_and(|location| location != Path::new("-"));
+            let load = move || {
+                with_alignments(
+                    if let Some(location) = &location {
+                        load_gfa(location, lenient)?
+                    } else {
+                        Store::new()?
+                    }
+                    .with_rdf_profile(rdf_profile),
+                    alignments.clone(),
+                )
+            };
+            let store = load()?;
+            serve(
+                ReloadableStore::new(store, reloadable.then(|| Box::new(load) as StoreLoader)),
+                &bind,
+                false,
+                &server,
+            )
+        }
         Command::ServeReadOnly {
             location,
             bind,
-            cors,
-        } => serve(Store::open_read_only(location)?, &bind, true, cors),
+            alignments,
+            lenient,
+            rdf_profile,
+            server,
+        } => {
+            let rdf_profile = RdfProfile::from(rdf_profile);
+            // stdin might not be read again
+            let reloadable = location != Path::new("-");
+            let load = move || {
+                with_alignments(
+                    load_gfa(&location, lenient)?.with_rdf_profile(rdf_profile),
+                    alignments.clone(),
+                )
+            };
+            let store = load()?;
+            serve(
+                ReloadableStore::new(store, reloadable.then(|| Box::new(load) as StoreLoader)),
+                &bind,
+                true,
+                &server,
+            )
+        }
         Command::ServeSecondary {
             primary_location,
             secondary_location,
             bind,
-            cors,
+            alignments,
+            rdf_profile,
+            server,
         } => serve(
-            if let Some(secondary_location) = secondary_location {
-                Store::open_persistent_secondary(primary_location, secondary_location)
-            } else {
-                Store::open_secondary(primary_location)
-            }?,
+            ReloadableStore::new(
+                with_alignments(
+                    if let Some(secondary_location) = secondary_location {
+                        Store::open_persistent_secondary(primary_location, secondary_location)
+                    } else {
+                        Store::open_secondary(primary_location)
+                    }?
+                    .with_rdf_profile(rdf_profile.into()),
+                    alignments,
+                )?,
+                None,
+            ),
             &bind,
             true,
-            cors,
+            &server,
         ),
         Command::Backup {
             location,
@@ -517,11 +714,14 @@ pub fn main() -> anyhow::Result<()> {
         }
         Command::Query {
             location,
+            lenient,
             query,
             query_file,
             query_base,
             results_file,
             results_format,
+            rdf_profile,
+            threads,
             explain,
             explain_file,
             stats,
@@ -533,12 +733,19 @@ pub fn main() -> anyhow::Result<()> {
                     format!("Not able to read query file {}", query_file.display())
                 })?
             } else {
+                ensure!(
+                    location != Path::new("-"),
+                    "The query must be given with --query or --query-file when the graph is read from stdin"
+                );
                 io::read_to_string(stdin().lock())?
             };
             let query = Query::parse(&query, query_base.as_deref())?;
-            let store = Store::open_read_only(location)?;
-            let (results, explanation) =
-                store.explain_query_opt(query, QueryOptions::default(), stats)?;
+            let store = load_gfa(&location, lenient)?.with_rdf_profile(rdf_profile.into());
+            let (results, explanation) = store.explain_query_opt(
+                query,
+                QueryOptions::default().with_num_threads(threads),
+                stats,
+            )?;
             let print_result = (|| {
                 match results? {
                     QueryResults::Solutions(solutions) => {
@@ -687,6 +894,35 @@ pub fn main() -> anyhow::Result<()> {
             store.optimize()?;
             Ok(())
         }
+        Command::HashSecret => {
+            let mut secret = String::new();
+            stdin().lock().read_to_string(&mut secret)?;
+            let secret = secret.trim_end_matches(['\r', '\n']);
+            ensure!(!secret.is_empty(), "The secret to hash is empty");
+            writeln!(stdout().lock(), "{}", hash_secret(secret)?)?;
+            Ok(())
+        }
+        Command::Validate { location, lenient } => {
+            let store = load_gfa(&location, lenient)?;
+            match store.validate() {
+                Ok(()) => {
+                    eprintln!("No problem found in {}", location.display());
+                    Ok(())
+                }
+                Err(ValidationError::Invalid(problems)) => {
+                    let mut stdout = stdout().lock();
+                    for problem in &problems {
+                        writeln!(stdout, "{problem}")?;
+                    }
+                    bail!(
+                        "{} problem(s) found in {}",
+                        problems.len(),
+                        location.display()
+                    )
+                }
+                Err(error) => Err(error.into()),
+            }
+        }
         Command::Convert {
             from_file,
             from_format,
@@ -785,6 +1021,39 @@ pub fn main() -> anyhow::Result<()> {
     }
 }
 
+fn load_gfa(location: &Path, lenient: bool) -> anyhow::Result<Store> {
+    let start = Instant::now();
+    let mut loader = Store::gfa_loader().on_progress(move |size| {
+        let elapsed = start.elapsed();
+        eprintln!(
+            "{size} GFA records parsed in {}s ({} r/s)",
+            elapsed.as_secs(),
+            ((size as f64) / elapsed.as_secs_f64()).round()
+        )
+    });
+    if lenient {
+        loader = loader.on_parse_error(move |e| {
+            eprintln!("Parsing error: {e}");
+            Ok(())
+        })
+    }
+    if location == Path::new("-") {
+        loader.load_from_read(stdin().lock())
+    } else {
+        loader.load_from_path(location)
+    }
+    .with_context(|| format!("Failed to load the graph file {}", location.display()))
+}
+
+fn with_alignments(mut store: Store, alignments: Option<PathBuf>) -> anyhow::Result<Store> {
+    if let Some(alignments) = alignments {
+        store
+            .load_alignments(&alignments)
+            .with_context(|| format!("Failed to load alignments from {}", alignments.display()))?;
+    }
+    Ok(store)
+}
+
 fn bulk_load(
     loader: &BulkLoader,
     read: impl Read,
@@ -902,32 +1171,229 @@ fn rdf_format_from_name(name: &str) -> anyhow::Result<RdfFormat> {
     bail!("The file format '{name}' is unknown")
 }
 
-fn serve(store: Store, bind: &str, read_only: bool, cors: bool) -> anyhow::Result<()> {
-    let mut server = if cors {
-        Server::new(cors_middleware(move |request| {
-            handle_request(request, store.clone(), read_only)
-                .unwrap_or_else(|(status, message)| error(status, message))
-        }))
+fn request_log_format_from_name(name: &str) -> anyhow::Result<RequestLogFormat> {
+    RequestLogFormat::from_name(name)
+        .with_context(|| format!("The request log format '{name}' is unknown"))
+}
+
+/// Parses a duration in seconds, e.g. `2.5`
+fn parse_timeout(value: &str) -> anyhow::Result<Duration> {
+    Ok(Duration::try_from_secs_f64(value.parse()?)?)
+}
+
+/// Handler of the requests to a dataset
+type RequestHandler = Box<dyn Fn(&mut Request) -> Response + Send + Sync>;
+
+fn serve(
+    store: ReloadableStore,
+    bind: &str,
+    read_only: bool,
+    args: &ServerArgs,
+) -> anyhow::Result<()> {
+    let options = ServerOptions {
+        read_only,
+        ..ServerOptions::from_args(args)?
+    };
+    listen(
+        dataset_handler(store, options, args.cors, args.reload_route)?,
+        bind,
+        args,
+    )
+}
+
+fn serve_datasets(config: &Config, bind: &str, args: &ServerArgs) -> anyhow::Result<()> {
+    let options = ServerOptions::from_args(args)?;
+    let mut handlers = BTreeMap::new();
+    for (name, dataset) in &config.datasets {
+        let dataset_options = dataset_options(name, dataset, &options)?;
+        let load = dataset_loader(name, dataset)?;
+        eprintln!("Loading the dataset {name}");
+        let store = load()?;
+        handlers.insert(
+            name.clone(),
+            dataset_handler(
+                ReloadableStore::new(store, Some(load)),
+                dataset_options,
+                args.cors || dataset.cors,
+                args.reload_route,
+            )?,
+        );
+    }
+    listen(
+        move |request: &mut Request| route_to_dataset(&handlers, request),
+        bind,
+        args,
+    )
+}
+
+/// The options of the server restricted to a dataset, each dataset counts its own metrics
+fn dataset_options(
+    name: &str,
+    dataset: &DatasetConfig,
+    options: &ServerOptions,
+) -> anyhow::Result<ServerOptions> {
+    let prologue = dataset.prologue();
+    Query::parse(&format!("{prologue}ASK {{}}"), None)
+        .with_context(|| format!("Invalid prefixes of the dataset {name}"))?;
+    Ok(ServerOptions {
+        read_only: dataset.read_only,
+        query_timeout: dataset
+            .query_timeout
+            .map(Duration::try_from_secs_f64)
+            .transpose()?
+            .or(options.query_timeout),
+        metrics: options
+            .metrics
+            .as_ref()
+            .map(|_| Arc::new(Metrics::default())),
+        path_prefix: format!("/{name}"),
+        prologue,
+        max_sparql_body_size: dataset.max_body_size,
+        fragment_cursors: Arc::default(),
+        ..options.clone()
+    })
+}
+
+fn dataset_loader(name: &str, dataset: &DatasetConfig) -> anyhow::Result<StoreLoader> {
+    let rdf_profile = RdfProfile::from(dataset.rdf_profile);
+    let base_iri = dataset_base_iri(name, dataset, rdf_profile)?;
+    let dataset = dataset.clone();
+    Ok(Box::new(move || {
+        let store = load_gfa(&dataset.location, dataset.lenient)?
+            .with_rdf_profile(rdf_profile)
+            .with_base_iri(&base_iri);
+        with_alignments(store, dataset.alignments.clone())
+    }))
+}
+
+/// The base IRI of the dataset, by default the host of the profile followed by `/<name>`
+/// since only the resources below the path of the dataset are described
+fn dataset_base_iri(
+    name: &str,
+    dataset: &DatasetConfig,
+    rdf_profile: RdfProfile,
+) -> anyhow::Result<String> {
+    if let Some(base_iri) = &dataset.base_iri {
+        return Ok(base_iri.clone());
+    }
+    let profile_base = Iri::parse(rdf_profile.base())?;
+    Ok(format!(
+        "{}://{}/{name}",
+        profile_base.scheme(),
+        profile_base.authority().unwrap_or_default()
+    ))
+}
+
+fn dataset_handler(
+    store: ReloadableStore,
+    mut options: ServerOptions,
+    cors: bool,
+    reload_route: bool,
+) -> anyhow::Result<RequestHandler> {
+    ensure!(
+        !reload_route || store.can_reload(),
+        "The graph might only be reloaded if it has been read from a file"
+    );
+    let cursors = Arc::clone(&options.fragment_cursors);
+    store.on_swap(move || cursors.clear());
+    let store = Arc::new(store);
+    if reload_route {
+        options.reload = Some(Arc::clone(&store));
+    }
+    let handler = move |request: &mut Request| {
+        handle_request(request, store.current(), &options)
+            .unwrap_or_else(|(status, message)| error(status, message))
+    };
+    Ok(if cors {
+        Box::new(cors_middleware(handler))
     } else {
-        Server::new(move |request| {
-            handle_request(request, store.clone(), read_only)
-                .unwrap_or_else(|(status, message)| error(status, message))
-        })
+        Box::new(handler)
+    })
+}
+
+/// Passes the request to the dataset named by the first segment of its path
+fn route_to_dataset(
+    handlers: &BTreeMap<String, RequestHandler>,
+    request: &mut Request,
+) -> Response {
+    let name = request
+        .url()
+        .path()
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+    if let Some(handler) = handlers.get(&name) {
+        handler(request)
+    } else {
+        let (status, message) = not_found(request);
+        error(status, message)
+    }
+}
+
+fn listen(
+    handler: impl Fn(&mut Request) -> Response + Send + Sync + 'static,
+    bind: &str,
+    args: &ServerArgs,
+) -> anyhow::Result<()> {
+    let max_connections = available_parallelism()?.get() * 128;
+    let ready = |scheme: &str| -> anyhow::Result<()> {
+        #[cfg(target_os = "linux")]
+        systemd_notify_ready()?;
+        eprintln!("Listening for requests at {scheme}://{bind}");
+        Ok(())
+    };
+    if let (Some(certificate), Some(private_key)) = (&args.tls_cert, &args.tls_key) {
+        let listeners = bind
+            .to_socket_addrs()?
+            .map(TcpListener::bind)
+            .collect::<io::Result<Vec<_>>>()?;
+        let server = Arc::new(TlsServer::new(
+            certificate,
+            private_key,
+            handler,
+            SERVER_NAME,
+            max_connections,
+            HTTP_TIMEOUT,
+        )?);
+        #[cfg(unix)]
+        server.reload_on_sighup()?;
+        let threads = listeners
+            .into_iter()
+            .map(|listener| {
+                let server = Arc::clone(&server);
+                thread::spawn(move || server.serve(listener))
+            })
+            .collect::<Vec<_>>();
+        ready("https")?;
+        for thread in threads {
+            if thread.join().is_err() {
+                bail!("The TLS listener panicked");
+            }
+        }
+    } else {
+        let mut server = http_server(handler, max_connections)?;
+        for socket in bind.to_socket_addrs()? {
+            server = server.bind(socket);
+        }
+        let server = server.spawn()?;
+        ready("http")?;
+        server.join()?;
     }
-    .with_global_timeout(HTTP_TIMEOUT)
-    .with_server_name(concat!("Oxigraph/", env!("CARGO_PKG_VERSION")))?
-    .with_max_concurrent_connections(available_parallelism()?.get() * 128);
-    for socket in bind.to_socket_addrs()? {
-        server = server.bind(socket);
-    }
-    let server = server.spawn()?;
-    #[cfg(target_os = "linux")]
-    systemd_notify_ready()?;
-    eprintln!("Listening for requests at http://{bind}");
-    server.join()?;
     Ok(())
 }
 
+fn http_server(
+    handler: impl Fn(&mut Request) -> Response + Send + Sync + 'static,
+    max_connections: usize,
+) -> anyhow::Result<Server> {
+    Ok(Server::new(handler)
+        .with_global_timeout(HTTP_TIMEOUT)
+        .with_server_name(SERVER_NAME)?
+        .with_max_concurrent_connections(max_connections))
+}
+
 fn cors_middleware(
     on_request: impl Fn(&mut Request) -> Response + Send + Sync + 'static,
 ) -> impl Fn(&mut Request) -> Response + Send + Sync + 'static {
@@ -974,12 +1440,132 @@ fn cors_middleware(
 
 type HttpError = (Status, String);
 
+/// Settings of the HTTP server shared by all the requests to a dataset
+#[derive(Clone, Default)]
+struct ServerOptions {
+    read_only: bool,
+    query_timeout: Option<Duration>,
+    metrics: Option<Arc<Metrics>>,
+    request_log: Option<RequestLogFormat>,
+    slow_query_log: Option<SlowQueryLog>,
+    /// Where the request and slow query logs are written
+    log_output: LogOutput,
+    /// If set, requests must authenticate and writes are checked against the permissions
+    credentials: Option<Credentials>,
+    /// If set, the /reload route swaps it with a new graph
+    reload: Option<Arc<ReloadableStore>>,
+    /// Path below which the routes are served, e.g. `/hprc`, empty for the server of a single dataset
+    path_prefix: String,
+    /// Prefix declarations added before the queries and the updates,
+    /// the positions of the syntax errors and the slow query log include them
+    prologue: String,
+    /// Maximal size of the SPARQL request bodies, [`MAX_SPARQL_BODY_SIZE`] if not set
+    max_sparql_body_size: Option<u64>,
+    /// Scans of the fragments stopped at the end of their last page
+    fragment_cursors: Arc<FragmentCursors>,
+}
+
+impl ServerOptions {
+    fn from_args(args: &ServerArgs) -> anyhow::Result<Self> {
+        Ok(Self {
+            query_timeout: args.query_timeout,
+            metrics: args.metrics.then(|| Arc::new(Metrics::default())),
+            request_log: args.request_log,
+            slow_query_log: args.slow_query_threshold.map(|threshold| SlowQueryLog {
+                threshold,
+                explain: args.slow_query_explain,
+            }),
+            credentials: args
+                .auth_file
+                .as_deref()
+                .map(Credentials::from_file)
+                .transpose()?,
+            ..Self::default()
+        })
+    }
+
+    fn sparql_body_size_limit(&self) -> u64 {
+        self.max_sparql_body_size.unwrap_or(MAX_SPARQL_BODY_SIZE)
+    }
+
+    /// The query or update text with the prefix declarations of the dataset
+    fn with_prologue<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.prologue.is_empty() {
+            text.into()
+        } else {
+            format!("{}{text}", self.prologue).into()
+        }
+    }
+}
+
 fn handle_request(
     request: &mut Request,
     store: Store,
-    read_only: bool,
+    options: &ServerOptions,
 ) -> Result<Response, HttpError> {
-    match (request.url().path(), request.method().as_ref()) {
+    if options.metrics.is_none() && options.request_log.is_none() {
+        return route_request(request, store, options);
+    }
+    let start = Instant::now();
+    let route = route_name(&store, request.url().path(), &options.path_prefix);
+    let mut result = route_request(request, store, options);
+    let status = match &result {
+        Ok(response) => response.status(),
+        Err((status, _)) => *status,
+    };
+    if let Some(metrics) = &options.metrics {
+        metrics.record_request(route, status);
+    }
+    if let Some(format) = options.request_log {
+        let entry = RequestLogEntry::new(
+            format,
+            options.log_output.clone(),
+            request,
+            route,
+            status,
+            start,
+        );
+        match &mut result {
+            Ok(response) => {
+                if let Some(size) = response.body().len() {
+                    entry.write(size);
+                } else {
+                    // The streamed bodies are logged once they have been sent
+                    let body = mem::replace(response.body_mut(), Body::from(Vec::new()));
+                    *response.body_mut() = Body::from_read(LoggedBody::new(body, entry));
+                }
+            }
+            Err((_, message)) => entry.write(u64::try_from(message.len()).unwrap_or(u64::MAX)),
+        }
+    }
+    result
+}
+
+fn route_request(
+    request: &mut Request,
+    store: Store,
+    options: &ServerOptions,
+) -> Result<Response, HttpError> {
+    let read_only = options.read_only;
+    let permission = if let Some(credentials) = &options.credentials {
+        let Some(permission) = credentials.authenticate(request) else {
+            return Ok(unauthorized());
+        };
+        Some(permission)
+    } else {
+        None
+    };
+    let Some(path) = request
+        .url()
+        .path()
+        .strip_prefix(options.path_prefix.as_str())
+    else {
+        return Err(not_found(request));
+    };
+    match (
+        if path.is_empty() { "/" } else { path },
+        request.method().as_ref(),
+    ) {
         ("/", "HEAD") => Ok(Response::builder(Status::OK)
             .with_header(HeaderName::CONTENT_TYPE, "text_html")
             .unwrap()
@@ -997,63 +1583,116 @@ fn handle_request(
             .unwrap()
             .with_body(LOGO)),
         ("/query", "GET") => {
-            configure_and_evaluate_sparql_query(&store, &[url_query(request)], None, request)
+            if url_query_parameter(request, "query").is_none() {
+                service_description(&store, request, read_only)
+            } else {
+                configure_and_evaluate_sparql_query(
+                    &store,
+                    &[url_query(request)],
+                    None,
+                    options,
+                    request,
+                )
+            }
         }
         ("/query", "POST") => {
             let content_type =
                 content_type(request).ok_or_else(|| bad_request("No Content-Type given"))?;
             if content_type == "application/sparql-query" {
-                let query = io::read_to_string(request.body_mut().take(MAX_SPARQL_BODY_SIZE))
-                    .map_err(bad_request)?;
+                let query =
+                    io::read_to_string(request.body_mut().take(options.sparql_body_size_limit()))
+                        .map_err(bad_request)?;
                 configure_and_evaluate_sparql_query(
                     &store,
                     &[url_query(request)],
                     Some(query),
+                    options,
                     request,
                 )
             } else if content_type == "application/x-www-form-urlencoded" {
                 let mut buffer = Vec::new();
                 request
                     .body_mut()
-                    .take(MAX_SPARQL_BODY_SIZE)
+                    .take(options.sparql_body_size_limit())
                     .read_to_end(&mut buffer)
                     .map_err(bad_request)?;
                 configure_and_evaluate_sparql_query(
                     &store,
                     &[url_query(request), &buffer],
                     None,
+                    options,
                     request,
                 )
             } else {
                 Err(unsupported_media_type(&content_type))
             }
         }
+        ("/fragments", "GET") => {
+            triple_pattern_fragment(&store, request, &options.fragment_cursors)
+        }
+        ("/metrics", "GET") => {
+            if let Some(metrics) = &options.metrics {
+                Ok(Response::builder(Status::OK)
+                    .with_header(HeaderName::CONTENT_TYPE, "text/plain; version=0.0.4")
+                    .unwrap()
+                    .with_body(metrics.render(&store)))
+            } else {
+                Err(not_found(request))
+            }
+        }
+        ("/reload", "POST") => {
+            let Some(reload) = &options.reload else {
+                return Err(not_found(request));
+            };
+            if !permission.map_or(true, |permission| permission.can_write(None)) {
+                return Err(forbidden());
+            }
+            if reload.reload() {
+                Ok(Response::builder(Status::ACCEPTED)
+                    .with_header(HeaderName::CONTENT_TYPE, "text/plain")
+                    .unwrap()
+                    .with_body("The graph is being reloaded"))
+            } else {
+                Err((
+                    Status::CONFLICT,
+                    "The graph is already being reloaded".into(),
+                ))
+            }
+        }
         ("/update", "POST") => {
             if read_only {
                 return Err(the_server_is_read_only());
             }
+            if !permission.map_or(true, Permission::can_write_some_graph) {
+                return Err(forbidden());
+            }
             let content_type =
                 content_type(request).ok_or_else(|| bad_request("No Content-Type given"))?;
             if content_type == "application/sparql-update" {
-                let update = io::read_to_string(request.body_mut().take(MAX_SPARQL_BODY_SIZE))
-                    .map_err(bad_request)?;
+                let update =
+                    io::read_to_string(request.body_mut().take(options.sparql_body_size_limit()))
+                        .map_err(bad_request)?;
                 configure_and_evaluate_sparql_update(
                     &store,
                     &[url_query(request)],
                     Some(update),
+                    permission,
+                    options,
                     request,
                 )
             } else if content_type == "application/x-www-form-urlencoded" {
                 let mut buffer = Vec::new();
                 request
                     .body_mut()
-                    .take(MAX_SPARQL_BODY_SIZE)
+                    .take(options.sparql_body_size_limit())
                     .read_to_end(&mut buffer)
                     .map_err(bad_request)?;
                 configure_and_evaluate_sparql_update(
                     &store,
                     &[url_query(request), &buffer],
                     None,
+                    permission,
+                    options,
                     request,
                 )
             } else {
@@ -1061,7 +1700,7 @@ fn handle_request(
             }
         }
         (path, "GET") if path.starts_with("/store") => {
-            if let Some(target) = store_target(request)? {
+            if let Some(target) = store_target(request, &options.path_prefix)? {
                 assert_that_graph_exists(&store, &target)?;
                 let format = rdf_content_negotiation(request)?;
 
@@ -1120,9 +1759,11 @@ fn handle_request(
             if read_only {
                 return Err(the_server_is_read_only());
             }
+            let target = store_target(request, &options.path_prefix)?;
+            check_graph_write(permission, target.as_ref())?;
             let content_type =
                 content_type(request).ok_or_else(|| bad_request("No Content-Type given"))?;
-            if let Some(target) = store_target(request)? {
+            if let Some(target) = target {
                 let format = RdfFormat::from_media_type(&content_type)
                     .ok_or_else(|| unsupported_media_type(&content_type))?;
                 let new = !match &target {
@@ -1166,7 +1807,9 @@ fn handle_request(
             if read_only {
                 return Err(the_server_is_read_only());
             }
-            if let Some(target) = store_target(request)? {
+            let target = store_target(request, &options.path_prefix)?;
+            check_graph_write(permission, target.as_ref())?;
+            if let Some(target) = target {
                 match target {
                     NamedGraphName::DefaultGraph => store
                         .clear_graph(GraphNameRef::DefaultGraph)
@@ -1196,9 +1839,11 @@ fn handle_request(
             if read_only {
                 return Err(the_server_is_read_only());
             }
+            let target = store_target(request, &options.path_prefix)?;
+            check_graph_write(permission, target.as_ref())?;
             let content_type =
                 content_type(request).ok_or_else(|| bad_request("No Content-Type given"))?;
-            if let Some(target) = store_target(request)? {
+            if let Some(target) = target {
                 let format = RdfFormat::from_media_type(&content_type)
                     .ok_or_else(|| unsupported_media_type(&content_type))?;
                 let new = assert_that_graph_exists(&store, &target).is_ok();
@@ -1216,8 +1861,10 @@ fn handle_request(
                     web_load_dataset(&store, request, format)?;
                     Ok(Response::builder(Status::NO_CONTENT).build())
                 } else {
-                    let graph =
-                        resolve_with_base(request, &format!("/store/{:x}", random::<u128>()))?;
+                    let graph = resolve_with_base(
+                        request,
+                        &format!("{}/store/{:x}", options.path_prefix, random::<u128>()),
+                    )?;
                     web_load_graph(&store, request, format, &graph.clone().into())?;
                     Ok(Response::builder(Status::CREATED)
                         .with_header(HeaderName::LOCATION, graph.into_string())
@@ -1227,19 +1874,34 @@ fn handle_request(
             }
         }
         (path, "HEAD") if path.starts_with("/store") => {
-            if let Some(target) = store_target(request)? {
+            if let Some(target) = store_target(request, &options.path_prefix)? {
                 assert_that_graph_exists(&store, &target)?;
             }
             Ok(Response::builder(Status::OK).build())
         }
-        _ => Err((
-            Status::NOT_FOUND,
-            format!(
-                "{} {} is not supported by this server",
-                request.method(),
-                request.url().path()
-            ),
-        )),
+        // The resources are below the path of the base IRI, even in a dataset with a path prefix
+        (_, "GET") => {
+            let resource =
+                resource_iri(&store, request.url().path()).ok_or_else(|| not_found(request))?;
+            describe_resource(&store, &resource, request)
+        }
+        _ => Err(not_found(request)),
+    }
+}
+
+/// Route label of the request metrics, with a bounded number of values
+fn route_name(store: &Store, path: &str, path_prefix: &str) -> &'static str {
+    match path.strip_prefix(path_prefix) {
+        Some("" | "/") => "/",
+        Some("/logo.svg") => "/logo.svg",
+        Some("/query") => "/query",
+        Some("/update") => "/update",
+        Some("/fragments") => "/fragments",
+        Some("/metrics") => "/metrics",
+        Some("/reload") => "/reload",
+        Some(path) if path.starts_with("/store") => "/store",
+        _ if resource_iri(store, path).is_some() => "resource",
+        _ => "unknown",
     }
 }
 
@@ -1274,8 +1936,10 @@ fn configure_and_evaluate_sparql_query(
     store: &Store,
     encoded: &[&[u8]],
     mut query: Option<String>,
+    options: &ServerOptions,
     request: &Request,
 ) -> Result<Response, HttpError> {
+    let mut timeout = options.query_timeout;
     let mut default_graph_uris = Vec::new();
     let mut named_graph_uris = Vec::new();
     let mut use_default_graph_as_union = false;
@@ -1291,6 +1955,12 @@ fn configure_and_evaluate_sparql_query(
                 "default-graph-uri" => default_graph_uris.push(v.into_owned()),
                 "union-default-graph" => use_default_graph_as_union = true,
                 "named-graph-uri" => named_graph_uris.push(v.into_owned()),
+                "timeout" => {
+                    // The requested timeout may only shorten the one of the server
+                    let requested = parse_timeout(&v)
+                        .map_err(|e| bad_request(format!("Invalid timeout {v}: {e}")))?;
+                    timeout = Some(timeout.map_or(requested, |timeout| timeout.min(requested)));
+                }
                 _ => (),
             }
         }
@@ -1302,19 +1972,516 @@ fn configure_and_evaluate_sparql_query(
         use_default_graph_as_union,
         default_graph_uris,
         named_graph_uris,
+        timeout,
+        options,
         request,
     )
 }
 
+/// Describes the endpoint with the [SPARQL 1.1 Service Description](https://www.w3.org/TR/sparql11-service-description/)
+/// vocabulary and its default graph with [VoID](https://www.w3.org/TR/void/) statistics
+fn service_description(
+    store: &Store,
+    request: &Request,
+    read_only: bool,
+) -> Result<Response, HttpError> {
+    let format = rdf_content_negotiation(request)?;
+    // Counting the triples would generate all of them, they are only given once counted
+    let size = store.graph_size();
+    let service = NamedNode::new(base_url(request)).map_err(bad_request)?;
+    let dataset = BlankNode::default();
+    let graph = BlankNode::default();
+    let mut triples = vec![
+        Triple::new(service.clone(), rdf::TYPE, vocab_iri(SD, "Service")),
+        Triple::new(service.clone(), vocab_iri(SD, "endpoint"), service.clone()),
+        Triple::new(
+            service.clone(),
+            vocab_iri(SD, "supportedLanguage"),
+            vocab_iri(SD, "SPARQL11Query"),
+        ),
+        Triple::new(
+            service.clone(),
+            vocab_iri(SD, "defaultDataset"),
+            dataset.clone(),
+        ),
+        Triple::new(dataset.clone(), rdf::TYPE, vocab_iri(SD, "Dataset")),
+        Triple::new(
+            dataset.clone(),
+            vocab_iri(SD, "defaultGraph"),
+            graph.clone(),
+        ),
+        Triple::new(graph.clone(), rdf::TYPE, vocab_iri(SD, "Graph")),
+        Triple::new(graph.clone(), rdf::TYPE, vocab_iri(VOID, "Dataset")),
+    ];
+    if let Some(count) = size.triples {
+        triples.push(Triple::new(
+            graph.clone(),
+            vocab_iri(VOID, "triples"),
+            integer_literal(count),
+        ));
+    }
+    if !read_only {
+        triples.push(Triple::new(
+            service.clone(),
+            vocab_iri(SD, "supportedLanguage"),
+            vocab_iri(SD, "SPARQL11Update"),
+        ));
+    }
+    for format in [
+        QueryResultsFormat::Json,
+        QueryResultsFormat::Xml,
+        QueryResultsFormat::Csv,
+        QueryResultsFormat::Tsv,
+    ] {
+        triples.push(Triple::new(
+            service.clone(),
+            vocab_iri(SD, "resultFormat"),
+            NamedNode::new_unchecked(format.iri()),
+        ));
+    }
+    for format in [
+        RdfFormat::NTriples,
+        RdfFormat::NQuads,
+        RdfFormat::Turtle,
+        RdfFormat::TriG,
+        RdfFormat::RdfXml,
+    ] {
+        triples.push(Triple::new(
+            service.clone(),
+            vocab_iri(SD, "resultFormat"),
+            NamedNode::new_unchecked(format.iri()),
+        ));
+    }
+    for function in EXTENSION_FUNCTIONS {
+        triples.push(Triple::new(
+            service.clone(),
+            vocab_iri(SD, "extensionFunction"),
+            function,
+        ));
+    }
+    for graph_name in store.named_graphs() {
+        let named_graph = BlankNode::default();
+        triples.push(Triple::new(
+            dataset.clone(),
+            vocab_iri(SD, "namedGraph"),
+            named_graph.clone(),
+        ));
+        triples.push(Triple::new(
+            named_graph,
+            vocab_iri(SD, "name"),
+            graph_name.map_err(internal_server_error)?,
+        ));
+    }
+    for (class, entities) in [
+        ("Node", size.nodes),
+        ("Path", size.paths),
+        ("Step", size.steps),
+    ] {
+        let partition = BlankNode::default();
+        triples.push(Triple::new(
+            graph.clone(),
+            vocab_iri(VOID, "classPartition"),
+            partition.clone(),
+        ));
+        triples.push(Triple::new(
+            partition.clone(),
+            vocab_iri(VOID, "class"),
+            vocab_iri(VG, class),
+        ));
+        triples.push(Triple::new(
+            partition,
+            vocab_iri(VOID, "entities"),
+            integer_literal(entities),
+        ));
+    }
+    let partition = BlankNode::default();
+    triples.push(Triple::new(
+        graph,
+        vocab_iri(VOID, "propertyPartition"),
+        partition.clone(),
+    ));
+    triples.push(Triple::new(
+        partition.clone(),
+        vocab_iri(VOID, "property"),
+        vocab_iri(VG, "links"),
+    ));
+    triples.push(Triple::new(
+        partition,
+        vocab_iri(VOID, "triples"),
+        integer_literal(size.edges),
+    ));
+
+    let mut writer = RdfSerializer::from_format(format).serialize_to_write(Vec::new());
+    for triple in &triples {
+        writer
+            .write_triple(triple.as_ref())
+            .map_err(internal_server_error)?;
+    }
+    Ok(Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, format.media_type())
+        .unwrap()
+        .with_body(writer.finish().map_err(internal_server_error)?))
+}
+
+fn vocab_iri(namespace: &str, local_name: &str) -> NamedNode {
+    NamedNode::new_unchecked(format!("{namespace}{local_name}"))
+}
+
+fn integer_literal(value: usize) -> Term {
+    Literal::new_typed_literal(value.to_string(), xsd::INTEGER).into()
+}
+
+type FragmentPattern = (Option<Subject>, Option<NamedNode>, Option<Term>);
+
+/// The scans of the last fragments served, kept where their page ended
+///
+/// The graph can not be sought to a given triple, so the next page of a fragment resumes the scan of the previous one
+/// instead of generating all the triples before it again.
+/// Each scan keeps the store it reads alive, so the scans idle for too long are dropped, and all of them when the graph is reloaded.
+/// The next page is then generated again from the current graph.
+struct FragmentCursors {
+    cursors: Mutex<VecDeque<FragmentCursor>>,
+    idle_timeout: Duration,
+}
+
+struct FragmentCursor {
+    pattern: FragmentPattern,
+    offset: usize,
+    quads: Peekable<QuadIter>,
+    saved_at: Instant,
+}
+
+impl Default for FragmentCursors {
+    fn default() -> Self {
+        Self {
+            cursors: Mutex::default(),
+            idle_timeout: FRAGMENT_CURSOR_IDLE_TIMEOUT,
+        }
+    }
+}
+
+impl FragmentCursors {
+    /// The scan of the pattern stopped after `offset` triples, if it is still there
+    fn resume(&self, pattern: &FragmentPattern, offset: usize) -> Option<Peekable<QuadIter>> {
+        let mut cursors = self.cursors.lock().unwrap();
+        self.drop_idle(&mut cursors);
+        let position = cursors
+            .iter()
+            .position(|cursor| cursor.pattern == *pattern && cursor.offset == offset)?;
+        cursors.remove(position).map(|cursor| cursor.quads)
+    }
+
+    fn save(&self, pattern: FragmentPattern, offset: usize, quads: Peekable<QuadIter>) {
+        let mut cursors = self.cursors.lock().unwrap();
+        self.drop_idle(&mut cursors);
+        if cursors.len() >= MAX_FRAGMENT_CURSORS {
+            cursors.pop_front();
+        }
+        cursors.push_back(FragmentCursor {
+            pattern,
+            offset,
+            quads,
+            saved_at: Instant::now(),
+        });
+    }
+
+    /// Drops all the scans, e.g. because they read a graph that is not served anymore
+    fn clear(&self) {
+        let cursors = mem::take(&mut *self.cursors.lock().unwrap());
+        // The last scan of a graph frees it, which should not block the other requests
+        drop(cursors);
+    }
+
+    fn drop_idle(&self, cursors: &mut VecDeque<FragmentCursor>) {
+        // The cursors are saved in order, so the idle ones are at the front
+        while cursors
+            .front()
+            .is_some_and(|cursor| cursor.saved_at.elapsed() >= self.idle_timeout)
+        {
+            cursors.pop_front();
+        }
+    }
+}
+
+/// Serves a page of the [Triple Pattern Fragment](https://linkeddatafragments.org/specification/triple-pattern-fragments/)
+/// of the default graph selected by the `subject`, `predicate` and `object` parameters, with its hypermedia controls
+fn triple_pattern_fragment(
+    store: &Store,
+    request: &Request,
+    cursors: &FragmentCursors,
+) -> Result<Response, HttpError> {
+    let format = fragment_content_negotiation(request)?;
+    let mut selectors = Vec::new();
+    let (mut subject, mut predicate, mut object) = (None, None, None);
+    let mut page = 1;
+    for (key, value) in request.url().query_pairs() {
+        match key.as_ref() {
+            "subject" => {
+                subject = match fragment_term(&value)? {
+                    None => None,
+                    Some(Term::NamedNode(node)) => Some(Subject::NamedNode(node)),
+                    Some(Term::BlankNode(node)) => Some(Subject::BlankNode(node)),
+                    Some(_) => return Err(bad_request("The subject should be an IRI")),
+                }
+            }
+            "predicate" => {
+                predicate = match fragment_term(&value)? {
+                    None => None,
+                    Some(Term::NamedNode(node)) => Some(node),
+                    Some(_) => return Err(bad_request("The predicate should be an IRI")),
+                }
+            }
+            "object" => object = fragment_term(&value)?,
+            "page" => {
+                page = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|page| *page > 0)
+                    .ok_or_else(|| bad_request(format!("Invalid page number: {value}")))?;
+                continue;
+            }
+            _ => continue,
+        }
+        if !value.is_empty() {
+            selectors.push((key, value));
+        }
+    }
+
+    let offset = (page - 1).saturating_mul(FRAGMENT_PAGE_SIZE);
+    let pattern = (subject.clone(), predicate.clone(), object.clone());
+    let mut quads = if let Some(quads) = cursors.resume(&pattern, offset) {
+        quads
+    } else {
+        let mut quads = store
+            .quads_for_pattern(
+                subject.as_ref().map(Subject::as_ref),
+                predicate.as_ref().map(NamedNode::as_ref),
+                object.as_ref().map(Term::as_ref),
+                Some(GraphNameRef::DefaultGraph),
+            )
+            .peekable();
+        // Without a scan to resume, e.g. for the first page, the previous triples are generated again
+        for quad in quads.by_ref().take(offset) {
+            quad.map_err(internal_server_error)?;
+        }
+        quads
+    };
+    let triples = quads
+        .by_ref()
+        .take(FRAGMENT_PAGE_SIZE)
+        .map(|quad| quad.map(Triple::from))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(internal_server_error)?;
+    let has_next = quads.peek().is_some();
+    if has_next {
+        cursors.save(pattern, offset + FRAGMENT_PAGE_SIZE, quads);
+    }
+    let count = if has_next || (triples.is_empty() && page > 1) {
+        store
+            .estimate_pattern_count(
+                subject.as_ref().map(Subject::as_ref),
+                predicate.as_ref().map(NamedNode::as_ref),
+                object.as_ref().map(Term::as_ref),
+            )
+            .map_err(internal_server_error)?
+            .max(offset + triples.len() + usize::from(has_next))
+    } else {
+        offset + triples.len()
+    };
+
+    let base = base_url(request);
+    let page_url = |page: usize| -> Result<NamedNode, HttpError> {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.extend_pairs(&selectors);
+        if page > 1 {
+            query.append_pair("page", &page.to_string());
+        }
+        let query = query.finish();
+        NamedNode::new(if query.is_empty() {
+            base.clone()
+        } else {
+            format!("{base}?{query}")
+        })
+        .map_err(bad_request)
+    };
+    let dataset = NamedNode::new(format!("{base}#dataset")).map_err(bad_request)?;
+    let fragment = page_url(page)?;
+    let search = BlankNode::default();
+    let mut metadata = vec![
+        Triple::new(dataset.clone(), rdf::TYPE, vocab_iri(VOID, "Dataset")),
+        Triple::new(dataset.clone(), rdf::TYPE, vocab_iri(HYDRA, "Collection")),
+        Triple::new(dataset.clone(), vocab_iri(VOID, "subset"), fragment.clone()),
+        Triple::new(dataset, vocab_iri(HYDRA, "search"), search.clone()),
+        Triple::new(
+            search.clone(),
+            vocab_iri(HYDRA, "template"),
+            Literal::new_simple_literal(format!("{base}{{?subject,predicate,object}}")),
+        ),
+        Triple::new(
+            search.clone(),
+            vocab_iri(HYDRA, "variableRepresentation"),
+            vocab_iri(HYDRA, "ExplicitRepresentation"),
+        ),
+        Triple::new(
+            fragment.clone(),
+            rdf::TYPE,
+            vocab_iri(HYDRA, "PartialCollectionView"),
+        ),
+        Triple::new(
+            fragment.clone(),
+            vocab_iri(VOID, "triples"),
+            integer_literal(count),
+        ),
+        Triple::new(
+            fragment.clone(),
+            vocab_iri(HYDRA, "totalItems"),
+            integer_literal(count),
+        ),
+        Triple::new(
+            fragment.clone(),
+            vocab_iri(HYDRA, "itemsPerPage"),
+            integer_literal(FRAGMENT_PAGE_SIZE),
+        ),
+        Triple::new(fragment.clone(), vocab_iri(HYDRA, "first"), page_url(1)?),
+    ];
+    if page > 1 {
+        metadata.push(Triple::new(
+            fragment.clone(),
+            vocab_iri(HYDRA, "previous"),
+            page_url(page - 1)?,
+        ));
+    }
+    if has_next {
+        metadata.push(Triple::new(
+            fragment.clone(),
+            vocab_iri(HYDRA, "next"),
+            page_url(page + 1)?,
+        ));
+    }
+    for (variable, property) in [
+        ("subject", rdf::SUBJECT),
+        ("predicate", rdf::PREDICATE),
+        ("object", rdf::OBJECT),
+    ] {
+        let mapping = BlankNode::default();
+        metadata.push(Triple::new(
+            search.clone(),
+            vocab_iri(HYDRA, "mapping"),
+            mapping.clone(),
+        ));
+        metadata.push(Triple::new(
+            mapping.clone(),
+            vocab_iri(HYDRA, "variable"),
+            Literal::new_simple_literal(variable),
+        ));
+        metadata.push(Triple::new(mapping, vocab_iri(HYDRA, "property"), property));
+    }
+
+    let mut writer = RdfSerializer::from_format(format).serialize_to_write(Vec::new());
+    for triple in &triples {
+        writer
+            .write_triple(triple.as_ref())
+            .map_err(internal_server_error)?;
+    }
+    if format.supports_datasets() {
+        // The controls are kept apart from the data, in a graph about the fragment
+        let graph =
+            NamedNode::new(format!("{}#metadata", fragment.as_str())).map_err(bad_request)?;
+        writer
+            .write_quad(
+                Triple::new(
+                    graph.clone(),
+                    NamedNode::new_unchecked("http://xmlns.com/foaf/0.1/primaryTopic"),
+                    fragment,
+                )
+                .as_ref()
+                .in_graph(graph.as_ref()),
+            )
+            .map_err(internal_server_error)?;
+        for triple in &metadata {
+            writer
+                .write_quad(triple.as_ref().in_graph(graph.as_ref()))
+                .map_err(internal_server_error)?;
+        }
+    } else {
+        for triple in &metadata {
+            writer
+                .write_triple(triple.as_ref())
+                .map_err(internal_server_error)?;
+        }
+    }
+    Ok(Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, format.media_type())
+        .unwrap()
+        .with_body(writer.finish().map_err(internal_server_error)?))
+}
+
+/// Parses a term of a fragment selector written in the hydra explicit representation,
+/// e.g. `http://example.com/s`, `"foo"@en` or `"1"^^http://www.w3.org/2001/XMLSchema#integer`
+///
+/// Empty values and variables (`?x`) select any term.
+fn fragment_term(value: &str) -> Result<Option<Term>, HttpError> {
+    if value.is_empty() || value.starts_with('?') {
+        return Ok(None);
+    }
+    if let Some(literal) = value.strip_prefix('"') {
+        let end = literal
+            .rfind('"')
+            .ok_or_else(|| bad_request(format!("Invalid literal: {value}")))?;
+        let (lexical, suffix) = (&literal[..end], &literal[end + 1..]);
+        return Ok(Some(if let Some(datatype) = suffix.strip_prefix("^^") {
+            let datatype = datatype.trim_start_matches('<').trim_end_matches('>');
+            Literal::new_typed_literal(lexical, NamedNode::new(datatype).map_err(bad_request)?)
+                .into()
+        } else if let Some(language) = suffix.strip_prefix('@') {
+            Literal::new_language_tagged_literal(lexical, language)
+                .map_err(bad_request)?
+                .into()
+        } else if suffix.is_empty() {
+            Literal::new_simple_literal(lexical).into()
+        } else {
+            return Err(bad_request(format!("Invalid literal: {value}")));
+        }));
+    }
+    if let Some(id) = value.strip_prefix("_:") {
+        return Ok(Some(BlankNode::new(id).map_err(bad_request)?.into()));
+    }
+    let iri = value.trim_start_matches('<').trim_end_matches('>');
+    Ok(Some(NamedNode::new(iri).map_err(bad_request)?.into()))
+}
+
 fn evaluate_sparql_query(
     store: &Store,
     query: &str,
     use_default_graph_as_union: bool,
     default_graph_uris: Vec<String>,
     named_graph_uris: Vec<String>,
+    timeout: Option<Duration>,
+    options: &ServerOptions,
     request: &Request,
 ) -> Result<Response, HttpError> {
-    let mut query = Query::parse(query, Some(&base_url(request))).map_err(bad_request)?;
+    let query_text = query;
+    let mut query = Query::parse(&options.with_prologue(query_text), Some(&base_url(request)))
+        .map_err(|e| {
+            if let Some(metrics) = &options.metrics {
+                metrics.record_query_error("syntax");
+            }
+            bad_request(e)
+        })?;
+    let mut timer = QueryTimer::start(
+        options.metrics.as_ref(),
+        options.slow_query_log.map(|log| {
+            SlowQuery::new(
+                log,
+                options.log_output.clone(),
+                query_text,
+                &default_graph_uris,
+                &named_graph_uris,
+                use_default_graph_as_union,
+            )
+        }),
+        &query,
+    );
 
     if use_default_graph_as_union {
         if !default_graph_uris.is_empty() || !named_graph_uris.is_empty() {
@@ -1340,7 +2507,29 @@ fn evaluate_sparql_query(
         );
     }
 
-    let results = store.query(query).map_err(internal_server_error)?;
+    let mut query_options = QueryOptions::default();
+    if let Some(timeout) = timeout {
+        query_options = query_options.with_timeout(timeout);
+    }
+    if let Some(metrics) = &options.metrics {
+        query_options = query_options.with_wrapped_service_handler(|inner| {
+            MeteredServiceHandler::new(inner, Arc::clone(metrics))
+        });
+    }
+    let results = if timer.explain() {
+        store
+            .explain_query_opt(query, query_options, true)
+            .and_then(|(results, explanation)| {
+                timer.set_explanation(explanation);
+                results
+            })
+    } else {
+        store.query_opt(query, query_options)
+    };
+    let results = timer.check(results).map_err(|e| match e {
+        EvaluationError::Interrupted => (Status::SERVICE_UNAVAILABLE, e.to_string()),
+        e => internal_server_error(e),
+    })?;
     match results {
         QueryResults::Solutions(solutions) => {
             let format = query_results_content_negotiation(request)?;
@@ -1350,12 +2539,13 @@ fn evaluate_sparql_query(
                         QueryResultsSerializer::from_format(format)
                             .serialize_solutions_to_write(w, solutions.variables().to_vec())?,
                         solutions,
+                        timer,
                     ))
                 },
-                |(mut writer, mut solutions)| {
+                |(mut writer, mut solutions, timer)| {
                     Ok(if let Some(solution) = solutions.next() {
-                        writer.write(&solution?)?;
-                        Some((writer, solutions))
+                        writer.write(&timer.check(solution)?)?;
+                        Some((writer, solutions, timer))
                     } else {
                         writer.finish()?;
                         None
@@ -1382,12 +2572,13 @@ fn evaluate_sparql_query(
                     Ok((
                         RdfSerializer::from_format(format).serialize_to_write(w),
                         triples,
+                        timer,
                     ))
                 },
-                |(mut writer, mut triples)| {
+                |(mut writer, mut triples, timer)| {
                     Ok(if let Some(t) = triples.next() {
-                        writer.write_triple(&t?)?;
-                        Some((writer, triples))
+                        writer.write_triple(&timer.check(t)?)?;
+                        Some((writer, triples, timer))
                     } else {
                         writer.finish()?;
                         None
@@ -1403,6 +2594,8 @@ fn configure_and_evaluate_sparql_update(
     store: &Store,
     encoded: &[&[u8]],
     mut update: Option<String>,
+    permission: Option<&Permission>,
+    options: &ServerOptions,
     request: &Request,
 ) -> Result<Response, HttpError> {
     let mut use_default_graph_as_union = false;
@@ -1431,6 +2624,8 @@ fn configure_and_evaluate_sparql_update(
         use_default_graph_as_union,
         default_graph_uris,
         named_graph_uris,
+        permission,
+        options,
         request,
     )
 }
@@ -1441,10 +2636,25 @@ fn evaluate_sparql_update(
     use_default_graph_as_union: bool,
     default_graph_uris: Vec<String>,
     named_graph_uris: Vec<String>,
+    permission: Option<&Permission>,
+    options: &ServerOptions,
     request: &Request,
 ) -> Result<Response, HttpError> {
-    let mut update =
-        Update::parse(update, Some(base_url(request).as_str())).map_err(bad_request)?;
+    let mut update = Update::parse(
+        &options.with_prologue(update),
+        Some(base_url(request).as_str()),
+    )
+    .map_err(bad_request)?;
+    if let Some(permission) = permission {
+        // Updates writing to graphs only known during their evaluation need the write permission on the full dataset
+        let allowed = permission.can_write(None)
+            || update
+                .written_graphs()
+                .is_some_and(|graphs| graphs.iter().all(|graph| permission.can_write(Some(graph))));
+        if !allowed {
+            return Err(forbidden());
+        }
+    }
 
     if use_default_graph_as_union {
         if !default_graph_uris.is_empty() || !named_graph_uris.is_empty() {
@@ -1485,8 +2695,23 @@ fn evaluate_sparql_update(
     Ok(Response::builder(Status::NO_CONTENT).build())
 }
 
-fn store_target(request: &Request) -> Result<Option<NamedGraphName>, HttpError> {
-    if request.url().path() == "/store" {
+/// Checks the permission to write a graph of the Graph Store Protocol, `None` being the full dataset
+fn check_graph_write(
+    permission: Option<&Permission>,
+    target: Option<&NamedGraphName>,
+) -> Result<(), HttpError> {
+    let Some(permission) = permission else {
+        return Ok(());
+    };
+    if permission.can_write(target.cloned().map(GraphName::from).as_ref()) {
+        Ok(())
+    } else {
+        Err(forbidden())
+    }
+}
+
+fn store_target(request: &Request, path_prefix: &str) -> Result<Option<NamedGraphName>, HttpError> {
+    if request.url().path().strip_prefix(path_prefix) == Some("/store") {
         let mut graph = None;
         let mut default = false;
         for (k, v) in request.url().query_pairs() {
@@ -1552,6 +2777,104 @@ impl From<NamedGraphName> for GraphName {
     }
 }
 
+/// The IRI of the node, path or step the request path stands for.
+///
+/// Only paths below the path of the base IRI of the store are resources,
+/// so that the IRIs of the graph resolve when the server is deployed at their base.
+fn resource_iri(store: &Store, path: &str) -> Option<NamedNode> {
+    let base = Iri::parse(store.base_iri()).ok()?;
+    let local = path.strip_prefix(base.path().trim_end_matches('/'))?;
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    let is_resource = if let Some(id) = local.strip_prefix("/node/") {
+        is_number(id)
+    } else if let Some(path) = local.strip_prefix("/path/") {
+        // Path names might contain slashes, a step is only told apart by its rank at the end
+        match path.rsplit_once("/step/") {
+            Some((name, rank)) if is_number(rank) => !name.is_empty(),
+            _ => !path.is_empty(),
+        }
+    } else {
+        false
+    };
+    if !is_resource {
+        return None;
+    }
+    NamedNode::new(format!("{}{local}", base.as_str().trim_end_matches('/'))).ok()
+}
+
+/// Serves the concise bounded description of a resource of the graph, i.e. the triples it is the subject of
+fn describe_resource(
+    store: &Store,
+    resource: &NamedNode,
+    request: &Request,
+) -> Result<Response, HttpError> {
+    let format = resource_content_negotiation(request)?;
+    let triples = store
+        .quads_for_pattern(
+            Some(resource.as_ref().into()),
+            None,
+            None,
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .map(|quad| quad.map(Triple::from))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(internal_server_error)?;
+    if triples.is_empty() {
+        return Err((
+            Status::NOT_FOUND,
+            format!("The resource {resource} does not exist"),
+        ));
+    }
+    let Some(format) = format else {
+        return Ok(Response::builder(Status::OK)
+            .with_header(HeaderName::CONTENT_TYPE, "text/html; charset=utf-8")
+            .unwrap()
+            .with_body(resource_html(resource, &triples)));
+    };
+    let mut writer = RdfSerializer::from_format(format).serialize_to_write(Vec::new());
+    for triple in &triples {
+        writer
+            .write_triple(triple.as_ref())
+            .map_err(internal_server_error)?;
+    }
+    Ok(Response::builder(Status::OK)
+        .with_header(HeaderName::CONTENT_TYPE, format.media_type())
+        .unwrap()
+        .with_body(writer.finish().map_err(internal_server_error)?))
+}
+
+fn resource_html(resource: &NamedNode, triples: &[Triple]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n<table>\n",
+        escape_html(resource.as_str())
+    );
+    for triple in triples {
+        let object = match &triple.object {
+            Term::NamedNode(object) => html_link(object),
+            Term::Literal(object) => escape_html(object.value()),
+            object => escape_html(&object.to_string()),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{object}</td></tr>\n",
+            html_link(&triple.predicate)
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+fn html_link(node: &NamedNode) -> String {
+    let iri = escape_html(node.as_str());
+    format!("<a href=\"{iri}\">{iri}</a>")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 fn rdf_content_negotiation(request: &Request) -> Result<RdfFormat, HttpError> {
     content_negotiation(
         request,
@@ -1565,6 +2888,41 @@ fn rdf_content_negotiation(request: &Request) -> Result<RdfFormat, HttpError> {
     )
 }
 
+/// The RDF format of a resource description, `None` for the HTML view
+fn resource_content_negotiation(request: &Request) -> Result<Option<RdfFormat>, HttpError> {
+    content_negotiation(
+        request,
+        |media_type| {
+            if media_type.eq_ignore_ascii_case("text/html")
+                || media_type.eq_ignore_ascii_case("application/xhtml+xml")
+            {
+                Some(None)
+            } else {
+                RdfFormat::from_media_type(media_type).map(Some)
+            }
+        },
+        Some(RdfFormat::Turtle),
+        &[
+            ("application", Some(RdfFormat::NTriples)),
+            ("text", Some(RdfFormat::Turtle)),
+        ],
+        "text/turtle, application/n-triples, application/rdf+xml or text/html",
+    )
+}
+
+fn fragment_content_negotiation(request: &Request) -> Result<RdfFormat, HttpError> {
+    content_negotiation(
+        request,
+        RdfFormat::from_media_type,
+        RdfFormat::TriG,
+        &[
+            ("application", RdfFormat::TriG),
+            ("text", RdfFormat::Turtle),
+        ],
+        "application/trig or text/turtle",
+    )
+}
+
 fn query_results_content_negotiation(request: &Request) -> Result<QueryResultsFormat, HttpError> {
     content_negotiation(
         request,
@@ -1723,10 +3081,38 @@ fn error(status: Status, message: impl fmt::Display) -> Response {
         .with_body(message.to_string())
 }
 
+fn not_found(request: &Request) -> HttpError {
+    (
+        Status::NOT_FOUND,
+        format!(
+            "{} {} is not supported by this server",
+            request.method(),
+            request.url().path()
+        ),
+    )
+}
+
 fn bad_request(message: impl fmt::Display) -> HttpError {
     (Status::BAD_REQUEST, message.to_string())
 }
 
+fn unauthorized() -> Response {
+    Response::builder(Status::UNAUTHORIZED)
+        .with_header(
+            HeaderName::WWW_AUTHENTICATE,
+            "Basic realm=\"Oxigraph\", Bearer realm=\"Oxigraph\"",
+        )
+        .unwrap()
+        .with_body("Valid credentials are required")
+}
+
+fn forbidden() -> HttpError {
+    (
+        Status::FORBIDDEN,
+        "The credentials do not allow this operation".into(),
+    )
+}
+
 fn the_server_is_read_only() -> HttpError {
     (Status::FORBIDDEN, "The server is read-only".into())
 }
@@ -1856,6 +3242,7 @@ mod tests {
     use assert_fs::{NamedTempFile, TempDir};
     use flate2::write::GzEncoder;
     use flate2::Compression;
+    use oxigraph::model::QuadRef;
     use predicates::prelude::*;
     use std::fs::remove_dir_all;
     use std::io::read_to_string;
@@ -2142,6 +3529,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn cli_query_graph_from_stdin() -> Result<()> {
+        cli_command()?
+            .arg("query")
+            .arg("--location")
+            .arg("-")
+            .arg("--lenient")
+            .arg("--query")
+            .arg(
+                "ASK { <https://example.org/node/1> a <http://biohackathon.org/resource/vg#Node> }",
+            )
+            .arg("--results-format")
+            .arg("csv")
+            .write_stdin("S\t1\tA\n")
+            .assert()
+            .stdout("true")
+            .success();
+        // stdin can't contain both the graph and the query
+        cli_command()?
+            .arg("query")
+            .arg("--location")
+            .arg("-")
+            .arg("--results-format")
+            .arg("csv")
+            .write_stdin("S\t1\tA\n")
+            .assert()
+            .failure();
+        Ok(())
+    }
+
     #[test]
     fn cli_construct_query_stdin() -> Result<()> {
         let store_dir = initialized_cli_store(
@@ -2518,10 +3935,330 @@ mod tests {
 
     #[test]
     fn get_without_query() -> Result<()> {
-        ServerTest::new()?.test_status(
-            Request::builder(Method::GET, "http://localhost/query".parse()?).build(),
+        let server = ServerTest {
+            store: Store::gfa_loader()
+                .load_from_read(b"S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\n".as_ref())?,
+        };
+        let request = Request::builder(Method::GET, "http://localhost/query".parse()?)
+            .with_header(HeaderName::ACCEPT, "application/n-triples")?
+            .build();
+        let mut response = server.exec(request);
+        let body = read_to_string(response.body_mut())?;
+        assert_eq!(response.status(), Status::OK, "Error message: {body}");
+        assert!(body.contains("<http://localhost/query> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://www.w3.org/ns/sparql-service-description#Service> ."));
+        assert!(body.contains("<http://localhost/query> <http://www.w3.org/ns/sparql-service-description#extensionFunction> <http://biohackathon.org/resource/vg#jaccard> ."));
+        assert!(body.contains("<http://rdfs.org/ns/void#entities> \"2\"^^<http://www.w3.org/2001/XMLSchema#integer> ."));
+        // The triples are not generated to describe the service
+        assert_eq!(server.store.graph_size().triples, None);
+
+        let triples = server.store.statistics()?.triples;
+        let request = Request::builder(Method::GET, "http://localhost/query".parse()?)
+            .with_header(HeaderName::ACCEPT, "application/n-triples")?
+            .build();
+        let mut response = server.exec(request);
+        let body = read_to_string(response.body_mut())?;
+        assert!(body.contains(&format!(
+            "<http://rdfs.org/ns/void#triples> \"{triples}\"^^<http://www.w3.org/2001/XMLSchema#integer> ."
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn get_fragment() -> Result<()> {
+        let server = ServerTest {
+            store: Store::gfa_loader().load_from_read(b"S\t1\tA\nS\t2\tC\n".as_ref())?,
+        };
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/fragments?predicate=http%3A%2F%2Fwww.w3.org%2F1999%2F02%2F22-rdf-syntax-ns%23type&object=http%3A%2F%2Fbiohackathon.org%2Fresource%2Fvg%23Node".parse()?,
+        )
+        .with_header(HeaderName::ACCEPT, "application/n-triples")?
+        .build();
+        let mut response = server.exec(request);
+        let body = read_to_string(response.body_mut())?;
+        assert_eq!(response.status(), Status::OK, "Error message: {body}");
+        assert!(body.contains("<https://example.org/node/2> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://biohackathon.org/resource/vg#Node> ."));
+        assert!(body.contains("<http://www.w3.org/ns/hydra/core#totalItems> \"2\"^^<http://www.w3.org/2001/XMLSchema#integer> ."));
+        assert!(!body.contains("<http://www.w3.org/ns/hydra/core#next>"));
+
+        let request = Request::builder(
+            Method::GET,
+            "http://localhost/fragments?subject=%22foo%22".parse()?,
+        )
+        .build();
+        server.test_status(request, Status::BAD_REQUEST)
+    }
+
+    #[test]
+    fn get_fragment_pages() -> Result<()> {
+        let gfa = (1..=120)
+            .map(|id| format!("S\t{id}\tA\n"))
+            .collect::<String>();
+        let store = Store::gfa_loader().load_from_read(gfa.as_bytes())?;
+        let options = ServerOptions::default();
+        let url = "http://localhost/fragments?predicate=http%3A%2F%2Fwww.w3.org%2F1999%2F02%2F22-rdf-syntax-ns%23type&object=http%3A%2F%2Fbiohackathon.org%2Fresource%2Fvg%23Node";
+        let fetch = |page: &str, options: &ServerOptions| -> Result<String> {
+            let mut request = Request::builder(Method::GET, format!("{url}{page}").parse()?)
+                .with_header(HeaderName::ACCEPT, "application/n-triples")?
+                .build();
+            let mut response = handle_request(&mut request, store.clone(), options)
+                .unwrap_or_else(|(status, message)| error(status, message));
+            let body = read_to_string(response.body_mut())?;
+            assert_eq!(response.status(), Status::OK, "Error message: {body}");
+            Ok(body)
+        };
+        let nodes = |body: &str| {
+            let mut nodes = body
+                .lines()
+                .filter(|line| line.starts_with("<https://example.org/node/"))
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            nodes.sort();
+            nodes
+        };
+
+        let first = fetch("", &options)?;
+        assert_eq!(nodes(&first).len(), FRAGMENT_PAGE_SIZE);
+        assert!(first.contains(&format!(
+            "<http://www.w3.org/ns/hydra/core#first> <{url}> ."
+        )));
+        assert!(first.contains(&format!(
+            "<http://www.w3.org/ns/hydra/core#next> <{url}&page=2> ."
+        )));
+        assert!(first.contains("<http://www.w3.org/ns/hydra/core#itemsPerPage> \"100\"^^<http://www.w3.org/2001/XMLSchema#integer> ."));
+        assert!(first.contains("<http://www.w3.org/ns/hydra/core#totalItems> \"120\"^^<http://www.w3.org/2001/XMLSchema#integer> ."));
+        assert!(!first.contains("<http://www.w3.org/ns/hydra/core#previous>"));
+
+        // The second page resumes the scan of the first one
+        let second = fetch("&page=2", &options)?;
+        assert_eq!(nodes(&second).len(), 20);
+        assert!(second.contains(&format!(
+            "<http://www.w3.org/ns/hydra/core#previous> <{url}> ."
+        )));
+        assert!(!second.contains("<http://www.w3.org/ns/hydra/core#next>"));
+        assert!(second.contains("<http://www.w3.org/ns/hydra/core#totalItems> \"120\"^^<http://www.w3.org/2001/XMLSchema#integer> ."));
+        let mut all = nodes(&first);
+        all.extend(nodes(&second));
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 120);
+
+        // Without the scan of the first page, the same page is generated from the beginning
+        assert_eq!(
+            nodes(&fetch("&page=2", &ServerOptions::default())?),
+            nodes(&second)
+        );
+        assert_eq!(nodes(&fetch("&page=3", &options)?).len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn fragment_cursors_release() -> Result<()> {
+        let store = Store::gfa_loader().load_from_read(b"S\t1\tA\n".as_ref())?;
+        let scan = || store.quads_for_pattern(None, None, None, None).peekable();
+
+        let cursors = FragmentCursors::default();
+        cursors.save((None, None, None), 100, scan());
+        assert!(cursors.resume(&(None, None, None), 100).is_some());
+        cursors.save((None, None, None), 100, scan());
+        cursors.clear();
+        assert!(cursors.resume(&(None, None, None), 100).is_none());
+
+        let cursors = FragmentCursors {
+            idle_timeout: Duration::ZERO,
+            ..FragmentCursors::default()
+        };
+        cursors.save((None, None, None), 100, scan());
+        assert!(cursors.resume(&(None, None, None), 100).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn get_fragment_bound_terms() -> Result<()> {
+        let server = ServerTest {
+            store: Store::gfa_loader()
+                .load_from_read(b"S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\n".as_ref())?,
+        };
+        let empty = "<http://www.w3.org/ns/hydra/core#totalItems> \"0\"^^<http://www.w3.org/2001/XMLSchema#integer> .";
+        for (query, expected) in [
+            (
+                "subject=https%3A%2F%2Fexample.org%2Fnode%2F1",
+                "<https://example.org/node/1> <http://biohackathon.org/resource/vg#links> <https://example.org/node/2> .",
+            ),
+            (
+                "predicate=http%3A%2F%2Fbiohackathon.org%2Fresource%2Fvg%23links&object=https%3A%2F%2Fexample.org%2Fnode%2F2",
+                "<https://example.org/node/1> <http://biohackathon.org/resource/vg#links> <https://example.org/node/2> .",
+            ),
+            ("object=%22C%22", "<https://example.org/node/2> <http://www.w3.org/1999/02/22-rdf-syntax-ns#value> \"C\" ."),
+            ("predicate=http%3A%2F%2Fwww.w3.org%2F1999%2F02%2F22-rdf-syntax-ns%23type&object=http%3A%2F%2Fx%2FFoo", empty),
+            ("subject=https%3A%2F%2Fexample.org%2Fnode%2F1%2Fx", empty),
+        ] {
+            let request = Request::builder(
+                Method::GET,
+                format!("http://localhost/fragments?{query}").parse()?,
+            )
+            .with_header(HeaderName::ACCEPT, "application/n-triples")?
+            .build();
+            let mut response = server.exec(request);
+            let body = read_to_string(response.body_mut())?;
+            assert_eq!(response.status(), Status::OK, "Error message: {body}");
+            assert!(body.contains(expected), "{expected} not in {body}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn get_query_timeout() -> Result<()> {
+        let server = ServerTest::new()?;
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=ASK%20{%20?s%20?p%20?o%20}&timeout=0".parse()?,
+            )
+            .build(),
+            Status::SERVICE_UNAVAILABLE,
+        )?;
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=ASK%20{%20?s%20?p%20?o%20}&timeout=60".parse()?,
+            )
+            .build(),
+            Status::OK,
+        )?;
+        server.test_status(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=ASK%20{%20?s%20?p%20?o%20}&timeout=foo".parse()?,
+            )
+            .build(),
+            Status::BAD_REQUEST,
+        )
+    }
+
+    #[test]
+    fn get_query_timeout_while_streaming() -> Result<()> {
+        // The solutions are evaluated after the 200 status has been sent, the body is then truncated
+        let mut response = ServerTest::new()?.exec(
+            Request::builder(
+                Method::GET,
+                "http://localhost/query?query=SELECT%20*%20WHERE%20{%20?s%20?p%20?o%20}&timeout=0"
+                    .parse()?,
+            )
+            .with_header(HeaderName::ACCEPT, "application/sparql-results+json")?
+            .build(),
+        );
+        assert_eq!(response.status(), Status::OK);
+        let body = read_to_string(response.body_mut())?;
+        assert!(body.starts_with("{\"head\":"), "{body}");
+        assert!(
+            body.ends_with(
+                "The query evaluation has been interrupted by its timeout or cancellation"
+            ),
+            "{body}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn get_metrics() -> Result<()> {
+        let server = ServerTest::new()?;
+        server.test_status(
+            Request::builder(Method::GET, "http://localhost/metrics".parse()?).build(),
+            Status::NOT_FOUND,
+        )?;
+
+        let options = ServerOptions {
+            metrics: Some(Arc::new(Metrics::default())),
+            ..ServerOptions::default()
+        };
+        let exec = |mut request: Request| {
+            handle_request(&mut request, server.store.clone(), &options)
+                .unwrap_or_else(|(status, message)| error(status, message))
+        };
+        ServerTest::check_status(
+            exec(
+                Request::builder(
+                    Method::GET,
+                    "http://localhost/query?query=ASK%20{%20?s%20?p%20?o%20}".parse()?,
+                )
+                .build(),
+            ),
+            Status::OK,
+        )?;
+        ServerTest::check_status(
+            exec(
+                Request::builder(Method::GET, "http://localhost/query?query=foo".parse()?).build(),
+            ),
             Status::BAD_REQUEST,
+        )?;
+        let mut response =
+            exec(Request::builder(Method::GET, "http://localhost/metrics".parse()?).build());
+        let body = read_to_string(response.body_mut())?;
+        assert_eq!(response.status(), Status::OK, "Error message: {body}");
+        assert!(body.contains("oxigraph_http_requests_total{route=\"/query\",status=\"200\"} 1"));
+        assert!(body.contains("oxigraph_http_requests_total{route=\"/query\",status=\"400\"} 1"));
+        assert!(body.contains("oxigraph_query_duration_seconds_count{form=\"ask\"} 1"));
+        assert!(body.contains("oxigraph_query_errors_total{reason=\"syntax\"} 1"));
+        assert!(body.contains("oxigraph_queries_in_flight 0"));
+        assert!(body.contains("oxigraph_graph_nodes 0"));
+        // The triples are not generated to export their number
+        assert!(!body.contains("oxigraph_graph_triples"));
+        server.store.statistics()?;
+        let mut response =
+            exec(Request::builder(Method::GET, "http://localhost/metrics".parse()?).build());
+        assert!(read_to_string(response.body_mut())?.contains("oxigraph_graph_triples 0"));
+        Ok(())
+    }
+
+    #[test]
+    fn request_and_slow_query_logs() -> Result<()> {
+        let server = ServerTest::new()?;
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let options = ServerOptions {
+            request_log: Some(RequestLogFormat::Json),
+            slow_query_log: Some(SlowQueryLog {
+                threshold: Duration::ZERO,
+                explain: false,
+            }),
+            log_output: LogOutput::Memory(Arc::clone(&lines)),
+            ..ServerOptions::default()
+        };
+        let mut request = Request::builder(
+            Method::GET,
+            "http://localhost/query?query=SELECT%20*%20WHERE%20%7B%20%3Fs%20%3Fp%20%3Fo%20%7D"
+                .parse()?,
         )
+        .with_header(HeaderName::ACCEPT, "application/sparql-results+json")?
+        .build();
+        let mut response = handle_request(&mut request, server.store.clone(), &options)
+            .unwrap_or_else(|(status, message)| error(status, message));
+        assert_eq!(response.status(), Status::OK);
+        // The results are streamed, nothing is logged before they are sent
+        assert!(lines.lock().unwrap().is_empty());
+        let body = read_to_string(response.body_mut())?;
+        drop(response);
+
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert!(
+            lines[0].contains("\"slow_query\":\"SELECT * WHERE { ?s ?p ?o }\",\"duration\":"),
+            "{}",
+            lines[0]
+        );
+        assert!(
+            lines[1].contains(
+                "\"method\":\"GET\",\"path\":\"/query\",\"route\":\"/query\",\"status\":200,"
+            ),
+            "{}",
+            lines[1]
+        );
+        assert!(
+            lines[1].ends_with(&format!(",\"size\":{}}}", body.len())),
+            "{}",
+            lines[1]
+        );
+        Ok(())
     }
 
     #[test]
@@ -2595,6 +4332,240 @@ mod tests {
         )
     }
 
+    #[test]
+    fn post_update_with_authentication() -> Result<()> {
+        let server = ServerTest::new()?;
+        let options = ServerOptions {
+            credentials: Some(Credentials::parse(&format!(
+                "# alice:secret might only write <http://example.com/g>\n\
+                 user alice {} write <http://example.com/g>\n\
+                 token reader {} read\n",
+                hash_secret("secret")?,
+                hash_secret("reader-token")?
+            ))?),
+            ..ServerOptions::default()
+        };
+        let exec = |mut request: Request| {
+            handle_request(&mut request, server.store.clone(), &options)
+                .unwrap_or_else(|(status, message)| error(status, message))
+        };
+        let query = |authorization: &str| -> Result<Request> {
+            Ok(Request::builder(
+                Method::GET,
+                "http://localhost/query?query=ASK%20{%20?s%20?p%20?o%20}".parse()?,
+            )
+            .with_header(HeaderName::AUTHORIZATION, authorization)?
+            .build())
+        };
+        let update = |graph: &str, authorization: &str| -> Result<Request> {
+            Ok(
+                Request::builder(Method::POST, "http://localhost/update".parse()?)
+                    .with_header(HeaderName::AUTHORIZATION, authorization)?
+                    .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")?
+                    .with_body(format!(
+                        "INSERT DATA {{ GRAPH <{graph}> {{ <http://example.com> <http://example.com> <http://example.com> }} }}"
+                    )),
+            )
+        };
+
+        ServerTest::check_status(
+            exec(Request::builder(Method::GET, "http://localhost/query".parse()?).build()),
+            Status::UNAUTHORIZED,
+        )?;
+        ServerTest::check_status(
+            exec(query("Bearer reader:wrong-token")?),
+            Status::UNAUTHORIZED,
+        )?;
+        ServerTest::check_status(exec(query("Bearer reader:reader-token")?), Status::OK)?;
+        ServerTest::check_status(
+            exec(update(
+                "http://example.com/g",
+                "Bearer reader:reader-token",
+            )?),
+            Status::FORBIDDEN,
+        )?;
+        ServerTest::check_status(
+            exec(update("http://example.com/g", "Basic YWxpY2U6c2VjcmV0")?),
+            Status::NO_CONTENT,
+        )?;
+        ServerTest::check_status(
+            exec(update(
+                "http://example.com/other",
+                "Basic YWxpY2U6c2VjcmV0",
+            )?),
+            Status::FORBIDDEN,
+        )
+    }
+
+    #[test]
+    fn route_to_datasets() -> Result<()> {
+        let dataset = |name: &str, read_only: bool, prologue: &str| {
+            dataset_handler(
+                ReloadableStore::new(Store::new()?, None),
+                ServerOptions {
+                    read_only,
+                    path_prefix: format!("/{name}"),
+                    prologue: prologue.into(),
+                    ..ServerOptions::default()
+                },
+                false,
+                false,
+            )
+        };
+        let handlers = BTreeMap::from([
+            (
+                "a".to_owned(),
+                dataset("a", false, "PREFIX ex: <http://example.com/> ")?,
+            ),
+            ("b".to_owned(), dataset("b", true, "")?),
+        ]);
+        let update = |name: &str| -> Result<Request> {
+            Ok(Request::builder(
+                Method::POST,
+                format!("http://localhost/{name}/update").parse()?,
+            )
+            .with_header(HeaderName::CONTENT_TYPE, "application/sparql-update")?
+            .with_body("INSERT DATA { ex:s ex:p ex:o }"))
+        };
+        let query = |name: &str| -> Result<Request> {
+            Ok(Request::builder(
+                Method::GET,
+                format!("http://localhost/{name}/query?query=ASK%20{{%20ex:s%20?p%20?o%20}}")
+                    .parse()?,
+            )
+            .build())
+        };
+
+        ServerTest::check_status(
+            route_to_dataset(&handlers, &mut update("a")?),
+            Status::NO_CONTENT,
+        )?;
+        ServerTest::check_status(route_to_dataset(&handlers, &mut query("a")?), Status::OK)?;
+        ServerTest::check_status(
+            route_to_dataset(&handlers, &mut update("b")?),
+            Status::FORBIDDEN,
+        )?;
+        ServerTest::check_status(
+            route_to_dataset(&handlers, &mut query("b")?),
+            Status::BAD_REQUEST,
+        )?;
+        ServerTest::check_status(
+            route_to_dataset(&handlers, &mut query("c")?),
+            Status::NOT_FOUND,
+        )?;
+        ServerTest::check_status(
+            route_to_dataset(
+                &handlers,
+                &mut Request::builder(Method::GET, "http://localhost/query".parse()?).build(),
+            ),
+            Status::NOT_FOUND,
+        )
+    }
+
+    #[test]
+    fn dataset_options_and_base_iri() -> Result<()> {
+        let config = Config::parse(
+            "[datasets.a]\nlocation = \"a.gfa\"\nrdf-profile = \"vg\"\n\
+             [datasets.b]\nlocation = \"b.gfa\"\nbase-iri = \"http://example.com/b\"\n",
+        )?;
+        let (a, b) = (&config.datasets["a"], &config.datasets["b"]);
+        assert_eq!(
+            dataset_base_iri("a", a, RdfProfile::Vg)?,
+            "http://example.org/a"
+        );
+        assert_eq!(
+            dataset_base_iri("b", b, RdfProfile::default())?,
+            "http://example.com/b"
+        );
+
+        // Each dataset counts its own requests
+        let options = ServerOptions {
+            metrics: Some(Arc::new(Metrics::default())),
+            ..ServerOptions::default()
+        };
+        let handlers = BTreeMap::from([
+            (
+                "a".to_owned(),
+                dataset_handler(
+                    ReloadableStore::new(Store::new()?, None),
+                    dataset_options("a", a, &options)?,
+                    false,
+                    false,
+                )?,
+            ),
+            (
+                "b".to_owned(),
+                dataset_handler(
+                    ReloadableStore::new(Store::new()?, None),
+                    dataset_options("b", b, &options)?,
+                    false,
+                    false,
+                )?,
+            ),
+        ]);
+        let mut query = Request::builder(
+            Method::GET,
+            "http://localhost/a/query?query=ASK%20{%20?s%20?p%20?o%20}".parse()?,
+        )
+        .build();
+        ServerTest::check_status(route_to_dataset(&handlers, &mut query), Status::OK)?;
+        let metrics = |name: &str| -> Result<String> {
+            let mut request = Request::builder(
+                Method::GET,
+                format!("http://localhost/{name}/metrics").parse()?,
+            )
+            .build();
+            Ok(read_to_string(
+                route_to_dataset(&handlers, &mut request).body_mut(),
+            )?)
+        };
+        let query_requests = "oxigraph_http_requests_total{route=\"/query\",status=\"200\"} 1";
+        assert!(metrics("a")?.contains(query_requests));
+        assert!(!metrics("b")?.contains(query_requests));
+        Ok(())
+    }
+
+    #[test]
+    fn post_reload() -> Result<()> {
+        let ex = NamedNodeRef::new("http://example.com")?;
+        let quad = QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph);
+        let store = Arc::new(ReloadableStore::new(
+            Store::new()?,
+            Some(Box::new(move || {
+                let store = Store::new()?;
+                store.insert(quad)?;
+                Ok(store)
+            })),
+        ));
+        let previous = store.current();
+        let options = ServerOptions {
+            reload: Some(Arc::clone(&store)),
+            ..ServerOptions::default()
+        };
+        let mut request =
+            Request::builder(Method::POST, "http://localhost/reload".parse()?).build();
+        ServerTest::check_status(
+            handle_request(&mut request, store.current(), &options)
+                .unwrap_or_else(|(status, message)| error(status, message)),
+            Status::ACCEPTED,
+        )?;
+        let start = Instant::now();
+        while !store.current().contains(quad)? {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "The reload timed out"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!previous.contains(quad)?);
+
+        // Not enabled
+        ServerTest::new()?.test_status(
+            Request::builder(Method::POST, "http://localhost/reload".parse()?).build(),
+            Status::NOT_FOUND,
+        )
+    }
+
     #[test]
     fn graph_store_url_normalization() -> Result<()> {
         let server = ServerTest::new()?;
@@ -2965,6 +4936,73 @@ mod tests {
         server.test_status(request, Status::NOT_FOUND)
     }
 
+    #[test]
+    fn get_resource_description() -> Result<()> {
+        let server = ServerTest {
+            store: Store::gfa_loader().load_from_read(b"S\t1\tA\n".as_ref())?,
+        };
+        let request = Request::builder(Method::GET, "http://localhost/node/1".parse()?)
+            .with_header(HeaderName::ACCEPT, "application/n-triples")?
+            .build();
+        let mut response = server.exec(request);
+        let body = read_to_string(response.body_mut())?;
+        assert_eq!(response.status(), Status::OK, "Error message: {body}");
+        assert!(body.contains("<https://example.org/node/1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://biohackathon.org/resource/vg#Node> ."));
+
+        let request = Request::builder(Method::GET, "http://localhost/node/1".parse()?)
+            .with_header(HeaderName::ACCEPT, "text/html")?
+            .build();
+        server.test_status(request, Status::OK)?;
+
+        let request = Request::builder(Method::GET, "http://localhost/node/9".parse()?).build();
+        server.test_status(request, Status::NOT_FOUND)?;
+
+        for path in [
+            "/node/1/x",
+            "/node/",
+            "/node/x",
+            "/path/",
+            "/edge/1",
+            "/foo",
+        ] {
+            let request =
+                Request::builder(Method::GET, format!("http://localhost{path}").parse()?).build();
+            server.test_status(request, Status::NOT_FOUND)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn get_path_resource_description() -> Result<()> {
+        let server = ServerTest {
+            store: Store::gfa_loader().load_from_read(
+                b"S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tx/a\t1+,2+\t*\n".as_ref(),
+            )?,
+        };
+        let request = Request::builder(Method::GET, "http://localhost/path/x/a".parse()?)
+            .with_header(HeaderName::ACCEPT, "application/n-triples")?
+            .build();
+        let mut response = server.exec(request);
+        let body = read_to_string(response.body_mut())?;
+        assert_eq!(response.status(), Status::OK, "Error message: {body}");
+        assert!(body.contains("<https://example.org/path/x/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://biohackathon.org/resource/vg#Path> ."));
+
+        let request = Request::builder(Method::GET, "http://localhost/path/x/a/step/2".parse()?)
+            .with_header(HeaderName::ACCEPT, "application/n-triples")?
+            .build();
+        let mut response = server.exec(request);
+        let body = read_to_string(response.body_mut())?;
+        assert_eq!(response.status(), Status::OK, "Error message: {body}");
+        assert!(body.contains("<https://example.org/path/x/a/step/2> <http://biohackathon.org/resource/vg#node> <https://example.org/node/2> ."));
+
+        for path in ["/path/x/a/step/9", "/path/x/a/step/x", "/path/y"] {
+            let request =
+                Request::builder(Method::GET, format!("http://localhost{path}").parse()?).build();
+            server.test_status(request, Status::NOT_FOUND)?;
+        }
+        Ok(())
+    }
+
     struct ServerTest {
         store: Store,
     }
@@ -2977,13 +5015,20 @@ mod tests {
         }
 
         fn exec(&self, mut request: Request) -> Response {
-            handle_request(&mut request, self.store.clone(), false)
+            handle_request(&mut request, self.store.clone(), &ServerOptions::default())
                 .unwrap_or_else(|(status, message)| error(status, message))
         }
 
         fn exec_read_only(&self, mut request: Request) -> Response {
-            handle_request(&mut request, self.store.clone(), true)
-                .unwrap_or_else(|(status, message)| error(status, message))
+            handle_request(
+                &mut request,
+                self.store.clone(),
+                &ServerOptions {
+                    read_only: true,
+                    ..ServerOptions::default()
+                },
+            )
+            .unwrap_or_else(|(status, message)| error(status, message))
         }
 
         fn test_status(&self, request: Request, expected_status: Status) -> Result<()> {
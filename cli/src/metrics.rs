@@ -0,0 +1,337 @@
+//! Metrics of the HTTP server exposed in the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format).
+
+use crate::log::SlowQuery;
+use oxhttp::model::Status;
+use oxigraph::model::NamedNode;
+use oxigraph::sparql::{EvaluationError, Query, QueryExplanation, QueryResults, ServiceHandler};
+use oxigraph::store::Store;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10., 60.,
+];
+
+/// Registry of the metrics collected by the server
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<(&'static str, u16), u64>>,
+    queries_in_flight: AtomicUsize,
+    query_latencies: Mutex<BTreeMap<&'static str, Histogram>>,
+    query_errors: Mutex<BTreeMap<&'static str, u64>>,
+    service_calls: Mutex<BTreeMap<&'static str, u64>>,
+    service_latencies: Mutex<Histogram>,
+}
+
+impl Metrics {
+    /// Counts a handled HTTP request
+    pub fn record_request(&self, route: &'static str, status: Status) {
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((route, status.into()))
+            .or_default() += 1;
+    }
+
+    /// Counts a query as in flight until [`Metrics::record_query_end`] is called
+    pub fn record_query_start(&self) {
+        self.queries_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the latency of a query, `form` is one of "select", "construct", "describe" or "ask"
+    pub fn record_query_end(&self, form: &'static str, latency: Duration) {
+        self.queries_in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.query_latencies
+            .lock()
+            .unwrap()
+            .entry(form)
+            .or_default()
+            .observe(latency);
+    }
+
+    /// Counts a query that failed, `reason` is one of "syntax", "timeout" or "evaluation"
+    pub fn record_query_error(&self, reason: &'static str) {
+        *self.query_errors.lock().unwrap().entry(reason).or_default() += 1;
+    }
+
+    fn record_service_call(&self, latency: Duration, successful: bool) {
+        *self
+            .service_calls
+            .lock()
+            .unwrap()
+            .entry(if successful { "success" } else { "error" })
+            .or_default() += 1;
+        self.service_latencies.lock().unwrap().observe(latency);
+    }
+
+    /// Writes all the metrics, including the sizes of the loaded graph
+    ///
+    /// The number of triples is only written once it has been counted, counting it generates all of them.
+    pub fn render(&self, store: &Store) -> String {
+        let size = store.graph_size();
+        let mut output = String::new();
+
+        write_header(
+            &mut output,
+            "oxigraph_http_requests_total",
+            "counter",
+            "Number of handled HTTP requests by route and status.",
+        );
+        for ((route, status), count) in self.requests.lock().unwrap().iter() {
+            writeln!(
+                output,
+                "oxigraph_http_requests_total{{route=\"{route}\",status=\"{status}\"}} {count}"
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut output,
+            "oxigraph_queries_in_flight",
+            "gauge",
+            "Number of SPARQL queries being evaluated or streamed.",
+        );
+        writeln!(
+            output,
+            "oxigraph_queries_in_flight {}",
+            self.queries_in_flight.load(Ordering::Relaxed)
+        )
+        .unwrap();
+
+        write_header(
+            &mut output,
+            "oxigraph_query_duration_seconds",
+            "histogram",
+            "Duration of the SPARQL queries by query form, until their results are fully streamed.",
+        );
+        for (form, histogram) in self.query_latencies.lock().unwrap().iter() {
+            histogram.write(
+                &mut output,
+                "oxigraph_query_duration_seconds",
+                &format!("form=\"{form}\""),
+            );
+        }
+
+        write_header(
+            &mut output,
+            "oxigraph_query_errors_total",
+            "counter",
+            "Number of failed SPARQL queries by reason.",
+        );
+        for (reason, count) in self.query_errors.lock().unwrap().iter() {
+            writeln!(
+                output,
+                "oxigraph_query_errors_total{{reason=\"{reason}\"}} {count}"
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut output,
+            "oxigraph_service_calls_total",
+            "counter",
+            "Number of SPARQL SERVICE calls by outcome.",
+        );
+        for (outcome, count) in self.service_calls.lock().unwrap().iter() {
+            writeln!(
+                output,
+                "oxigraph_service_calls_total{{outcome=\"{outcome}\"}} {count}"
+            )
+            .unwrap();
+        }
+
+        write_header(
+            &mut output,
+            "oxigraph_service_duration_seconds",
+            "histogram",
+            "Duration of the SPARQL SERVICE calls.",
+        );
+        self.service_latencies.lock().unwrap().write(
+            &mut output,
+            "oxigraph_service_duration_seconds",
+            "",
+        );
+
+        for (name, help, value) in [
+            (
+                "oxigraph_graph_triples",
+                "Number of triples of the default graph.",
+                size.triples,
+            ),
+            (
+                "oxigraph_graph_nodes",
+                "Number of nodes of the loaded graph.",
+                Some(size.nodes),
+            ),
+            (
+                "oxigraph_graph_paths",
+                "Number of paths of the loaded graph.",
+                Some(size.paths),
+            ),
+            (
+                "oxigraph_graph_edges",
+                "Number of edges of the loaded graph.",
+                Some(size.edges),
+            ),
+            (
+                "oxigraph_graph_steps",
+                "Number of steps of the paths of the loaded graph.",
+                Some(size.steps),
+            ),
+        ] {
+            if let Some(value) = value {
+                write_header(&mut output, name, "gauge", help);
+                writeln!(output, "{name} {value}").unwrap();
+            }
+        }
+        output
+    }
+}
+
+fn write_header(output: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(output, "# HELP {name} {help}").unwrap();
+    writeln!(output, "# TYPE {name} {kind}").unwrap();
+}
+
+/// Latency histogram with cumulative buckets
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, latency: Duration) {
+        let latency = latency.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if latency <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += latency;
+    }
+
+    /// Writes the histogram samples, `labels` is a possibly empty list of labels like `form="select"`
+    fn write(&self, output: &mut String, name: &str, labels: &str) {
+        let (bucket_labels, labels) = if labels.is_empty() {
+            (String::new(), String::new())
+        } else {
+            (format!("{labels},"), format!("{{{labels}}}"))
+        };
+        for (bucket, bound) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            writeln!(
+                output,
+                "{name}_bucket{{{bucket_labels}le=\"{bound}\"}} {bucket}"
+            )
+            .unwrap();
+        }
+        writeln!(
+            output,
+            "{name}_bucket{{{bucket_labels}le=\"+Inf\"}} {}",
+            self.count
+        )
+        .unwrap();
+        writeln!(output, "{name}_sum{labels} {}", self.sum).unwrap();
+        writeln!(output, "{name}_count{labels} {}", self.count).unwrap();
+    }
+}
+
+/// Tracks a query until the end of the streaming of its results, for the metrics and the slow query log
+pub struct QueryTimer {
+    metrics: Option<Arc<Metrics>>,
+    slow_query: Option<SlowQuery>,
+    form: &'static str,
+    start: Instant,
+}
+
+impl QueryTimer {
+    pub fn start(
+        metrics: Option<&Arc<Metrics>>,
+        slow_query: Option<SlowQuery>,
+        query: &Query,
+    ) -> Self {
+        if let Some(metrics) = metrics {
+            metrics.record_query_start();
+        }
+        Self {
+            metrics: metrics.cloned(),
+            slow_query,
+            form: match query {
+                Query::Select { .. } => "select",
+                Query::Construct { .. } => "construct",
+                Query::Describe { .. } => "describe",
+                Query::Ask { .. } => "ask",
+            },
+            start: Instant::now(),
+        }
+    }
+
+    /// If the slow query log wants the explanation of the evaluation
+    pub fn explain(&self) -> bool {
+        self.slow_query.as_ref().is_some_and(SlowQuery::explain)
+    }
+
+    pub fn set_explanation(&mut self, explanation: QueryExplanation) {
+        if let Some(slow_query) = &mut self.slow_query {
+            slow_query.set_explanation(explanation);
+        }
+    }
+
+    /// Counts the error of a query evaluation result, if any
+    pub fn check<T>(&self, result: Result<T, EvaluationError>) -> Result<T, EvaluationError> {
+        if let (Some(metrics), Err(e)) = (&self.metrics, &result) {
+            metrics.record_query_error(if matches!(e, EvaluationError::Interrupted) {
+                "timeout"
+            } else {
+                "evaluation"
+            });
+        }
+        result
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        if let Some(metrics) = &self.metrics {
+            metrics.record_query_end(self.form, duration);
+        }
+        if let Some(slow_query) = &self.slow_query {
+            slow_query.report(duration);
+        }
+    }
+}
+
+/// [`ServiceHandler`] counting the SERVICE calls of the handler it wraps and measuring their latency
+pub struct MeteredServiceHandler {
+    inner: Arc<dyn ServiceHandler<Error = EvaluationError>>,
+    metrics: Arc<Metrics>,
+}
+
+impl MeteredServiceHandler {
+    pub fn new(
+        inner: Arc<dyn ServiceHandler<Error = EvaluationError>>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+impl ServiceHandler for MeteredServiceHandler {
+    type Error = EvaluationError;
+
+    fn handle(&self, service_name: NamedNode, query: Query) -> Result<QueryResults, Self::Error> {
+        let start = Instant::now();
+        let result = self.inner.handle(service_name, query);
+        self.metrics
+            .record_service_call(start.elapsed(), result.is_ok());
+        result
+    }
+}
@@ -0,0 +1,147 @@
+//! Hot reload of the graph served by the HTTP server.
+//!
+//! The new [`Store`] is built in a background thread and then swapped with the current one.
+//! The requests keep a clone of the [`Store`] they started with, so in-flight queries finish on the previous graph,
+//! which is dropped when the last of them ends.
+//! What else keeps the previous store, like the scans of the fragments being paged, is released by the swap listeners.
+
+use oxigraph::store::Store;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Instant;
+
+/// Builds a new [`Store`] from the same sources as the served one
+pub type StoreLoader = Box<dyn Fn() -> anyhow::Result<Store> + Send + Sync>;
+
+/// Called after a new [`Store`] has replaced the current one
+pub type SwapListener = Box<dyn Fn() + Send + Sync>;
+
+/// The [`Store`] served by the HTTP server, that might be replaced while the server is running
+pub struct ReloadableStore {
+    current: RwLock<Store>,
+    loader: Option<StoreLoader>,
+    reloading: AtomicBool,
+    swap_listeners: Mutex<Vec<SwapListener>>,
+}
+
+impl ReloadableStore {
+    pub fn new(store: Store, loader: Option<StoreLoader>) -> Self {
+        Self {
+            current: RwLock::new(store),
+            loader,
+            reloading: AtomicBool::new(false),
+            swap_listeners: Mutex::default(),
+        }
+    }
+
+    /// Calls `listener` each time the store is replaced, to release what still refers to the previous one
+    pub fn on_swap(&self, listener: impl Fn() + Send + Sync + 'static) {
+        self.swap_listeners.lock().unwrap().push(Box::new(listener));
+    }
+
+    /// The store new requests should be evaluated against
+    pub fn current(&self) -> Store {
+        self.current.read().unwrap().clone()
+    }
+
+    /// If the store sources allow to build it again, i.e. it has not been read from stdin or created empty
+    pub fn can_reload(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Starts to build the new store in the background
+    ///
+    /// Returns `false` if a reload is already running.
+    /// If the loading fails, the error is logged and the current store kept.
+    pub fn reload(self: &Arc<Self>) -> bool {
+        if self.loader.is_none() || self.reloading.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        let this = Arc::clone(self);
+        thread::spawn(move || {
+            let _guard = ReloadingGuard(&this.reloading);
+            let start = Instant::now();
+            eprintln!("Reloading the graph");
+            let Some(loader) = &this.loader else {
+                return;
+            };
+            match loader() {
+                Ok(store) => {
+                    let previous = mem::replace(&mut *this.current.write().unwrap(), store);
+                    // Freeing a whole graph takes time, the new requests should not wait for it
+                    drop(previous);
+                    for listener in this.swap_listeners.lock().unwrap().iter() {
+                        listener();
+                    }
+                    eprintln!(
+                        "Graph reloaded in {}s, the new requests use it",
+                        start.elapsed().as_secs()
+                    );
+                }
+                Err(e) => eprintln!("Failed to reload the graph, keeping the previous one: {e:#}"),
+            }
+        });
+        true
+    }
+}
+
+/// Allows the next reload when the current one ends, even if the loader panics
+struct ReloadingGuard<'a>(&'a AtomicBool);
+
+impl Drop for ReloadingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::panic_in_result_fn)]
+
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reload_after_panic() -> anyhow::Result<()> {
+        let store = Arc::new(ReloadableStore::new(
+            Store::new()?,
+            Some(Box::new(|| -> anyhow::Result<Store> {
+                panic!("The loader panics")
+            })),
+        ));
+        assert!(store.reload());
+        let start = Instant::now();
+        while store.reloading.load(Ordering::Acquire) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "The reload is never allowed again"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(store.reload(), "A new reload should start after the panic");
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_listener() -> anyhow::Result<()> {
+        let store = Arc::new(ReloadableStore::new(
+            Store::new()?,
+            Some(Box::new(|| -> anyhow::Result<Store> { Ok(Store::new()?) })),
+        ));
+        let swaps = Arc::new(AtomicBool::new(false));
+        let listener_swaps = Arc::clone(&swaps);
+        store.on_swap(move || listener_swaps.store(true, Ordering::Release));
+        assert!(store.reload());
+        let start = Instant::now();
+        while !swaps.load(Ordering::Acquire) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "The swap listener is never called"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,618 @@
+//! HTTPS server.
+//!
+//! The TLS connections are decrypted in the process and their HTTP/1.1 requests are given to the same handler
+//! as the plain HTTP server, with `https` URLs: only the requests that really arrived over TLS get this scheme.
+//! On Unix, SIGHUP reloads the certificate and the private key for the following connections.
+
+use anyhow::Context;
+use oxhttp::model::{Body, HeaderName, Method, Request, Response, Status};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use std::cmp::min;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+/// The connections sending longer request heads are closed
+const MAX_REQUEST_HEAD_SIZE: u64 = 64 * 1024;
+/// The longest line giving the size of a chunk of a request body
+const MAX_CHUNK_SIZE_LINE: u64 = 1024;
+
+/// Accepts TLS connections and answers their requests with the handler
+pub struct TlsServer {
+    certificate: PathBuf,
+    private_key: PathBuf,
+    config: Mutex<Arc<ServerConfig>>,
+    reload_requested: Arc<AtomicBool>,
+    handler: Box<dyn Fn(&mut Request) -> Response + Send + Sync>,
+    server_name: String,
+    max_connections: usize,
+    connections: Arc<AtomicUsize>,
+    timeout: Duration,
+}
+
+impl TlsServer {
+    /// Connections are closed after `timeout` without data, and refused when `max_connections` are open
+    pub fn new(
+        certificate: &Path,
+        private_key: &Path,
+        handler: impl Fn(&mut Request) -> Response + Send + Sync + 'static,
+        server_name: &str,
+        max_connections: usize,
+        timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            config: Mutex::new(load_config(certificate, private_key)?),
+            certificate: certificate.into(),
+            private_key: private_key.into(),
+            reload_requested: Arc::default(),
+            handler: Box::new(handler),
+            server_name: server_name.into(),
+            max_connections,
+            connections: Arc::default(),
+            timeout,
+        })
+    }
+
+    /// Accepts connections until the listener fails, each of them in its own thread
+    pub fn serve(self: &Arc<Self>, listener: TcpListener) {
+        for client in listener.incoming() {
+            let client = match client {
+                Ok(client) => client,
+                Err(e) => {
+                    eprintln!("Failed to accept a TLS connection: {e}");
+                    continue;
+                }
+            };
+            let Some(slot) = ConnectionSlot::acquire(&self.connections, self.max_connections)
+            else {
+                // Dropping the connection closes it
+                continue;
+            };
+            if let Err(e) = client
+                .set_read_timeout(Some(self.timeout))
+                .and_then(|()| client.set_write_timeout(Some(self.timeout)))
+            {
+                eprintln!("Failed to set up a TLS connection: {e}");
+                continue;
+            }
+            let connection = match ServerConnection::new(self.config()) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    eprintln!("Failed to start a TLS connection: {e}");
+                    continue;
+                }
+            };
+            let this = Arc::clone(self);
+            thread::spawn(move || {
+                let _slot = slot;
+                let stream = SharedStream::new(StreamOwned::new(connection, client));
+                if let Err(e) = this.serve_connection(&stream) {
+                    if !matches!(
+                        e.kind(),
+                        ErrorKind::BrokenPipe
+                            | ErrorKind::ConnectionReset
+                            | ErrorKind::UnexpectedEof
+                            | ErrorKind::WouldBlock
+                            | ErrorKind::TimedOut
+                    ) {
+                        eprintln!("TLS connection error: {e}");
+                    }
+                }
+            });
+        }
+    }
+
+    /// Requests the reload of the certificate on SIGHUP
+    #[cfg(unix)]
+    pub fn reload_on_sighup(&self) -> io::Result<()> {
+        signal_hook::flag::register(
+            signal_hook::consts::SIGHUP,
+            Arc::clone(&self.reload_requested),
+        )?;
+        Ok(())
+    }
+
+    /// The current configuration, reloaded from the files if SIGHUP has been received
+    fn config(&self) -> Arc<ServerConfig> {
+        let mut config = self.config.lock().unwrap();
+        if self.reload_requested.swap(false, Ordering::Relaxed) {
+            match load_config(&self.certificate, &self.private_key) {
+                Ok(new_config) => {
+                    *config = new_config;
+                    eprintln!("TLS certificate reloaded");
+                }
+                Err(e) => eprintln!(
+                    "Failed to reload the TLS certificate, keeping the previous one: {e:#}"
+                ),
+            }
+        }
+        Arc::clone(&config)
+    }
+
+    /// Answers the requests of the connection until the client or a response closes it
+    fn serve_connection(&self, stream: &SharedStream) -> io::Result<()> {
+        loop {
+            let Some(head) = read_head(&mut *stream.lock())? else {
+                return Ok(());
+            };
+            let (mut request, head) = match parse_request(&head, stream) {
+                Ok(request) => request,
+                Err(message) => {
+                    let mut response = Response::builder(Status::BAD_REQUEST).with_body(message);
+                    return self.write_response(stream, &mut response, false, false);
+                }
+            };
+            if head.expects_continue {
+                let mut stream = stream.lock();
+                stream
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+                stream.get_mut().flush()?;
+            }
+            let mut response = (self.handler)(&mut request);
+            // The next request starts after the end of the body, even if the handler did not read it
+            io::copy(request.body_mut(), &mut io::sink())?;
+            self.write_response(stream, &mut response, head.is_head, head.keep_alive)?;
+            if !head.keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    fn write_response(
+        &self,
+        stream: &SharedStream,
+        response: &mut Response,
+        is_head: bool,
+        keep_alive: bool,
+    ) -> io::Result<()> {
+        let status = u16::from(response.status());
+        let mut head = Vec::new();
+        write!(
+            head,
+            "HTTP/1.1 {status} \r\nServer: {}\r\n",
+            self.server_name
+        )?;
+        for (name, value) in response.headers() {
+            let name: &str = name.as_ref();
+            // The framing of the body is chosen here
+            if [
+                "Connection",
+                "Content-Length",
+                "Transfer-Encoding",
+                "Server",
+            ]
+            .iter()
+            .any(|framing| name.eq_ignore_ascii_case(framing))
+            {
+                continue;
+            }
+            let value: &[u8] = value.as_ref();
+            head.extend_from_slice(name.as_bytes());
+            head.extend_from_slice(b": ");
+            head.extend_from_slice(value);
+            head.extend_from_slice(b"\r\n");
+        }
+        // The 1xx, 204 and 304 responses never have a body
+        let has_body = !matches!(status, 100..=199 | 204 | 304);
+        let length = response.body().len();
+        if has_body {
+            match length {
+                Some(length) => write!(head, "Content-Length: {length}\r\n")?,
+                None => head.extend_from_slice(b"Transfer-Encoding: chunked\r\n"),
+            }
+        }
+        if !keep_alive {
+            head.extend_from_slice(b"Connection: close\r\n");
+        }
+        head.extend_from_slice(b"\r\n");
+
+        let mut stream = stream.lock();
+        let mut writer = BufWriter::new(stream.get_mut());
+        writer.write_all(&head)?;
+        if has_body && !is_head {
+            if length.is_some() {
+                io::copy(response.body_mut(), &mut writer)?;
+            } else {
+                let mut buffer = vec![0; 16 * 1024];
+                loop {
+                    let read = response.body_mut().read(&mut buffer)?;
+                    if read == 0 {
+                        break;
+                    }
+                    write!(writer, "{read:X}\r\n")?;
+                    writer.write_all(&buffer[..read])?;
+                    writer.write_all(b"\r\n")?;
+                }
+                writer.write_all(b"0\r\n\r\n")?;
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+        if !keep_alive {
+            stream.get_mut().conn.send_close_notify();
+            stream.get_mut().flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// The TLS stream of a connection, shared by the body of the current request and the writing of the responses
+#[derive(Clone)]
+struct SharedStream(Arc<Mutex<BufReader<StreamOwned<ServerConnection, TcpStream>>>>);
+
+impl SharedStream {
+    fn new(stream: StreamOwned<ServerConnection, TcpStream>) -> Self {
+        Self(Arc::new(Mutex::new(BufReader::new(stream))))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BufReader<StreamOwned<ServerConnection, TcpStream>>> {
+        self.0.lock().unwrap()
+    }
+}
+
+/// What the server needs to know from the head of a request besides the request itself
+struct RequestHead {
+    is_head: bool,
+    keep_alive: bool,
+    expects_continue: bool,
+}
+
+/// Reads the head of the next request, `None` if the client closed the connection before it
+fn read_head(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut head = Vec::new();
+    loop {
+        let limit = MAX_REQUEST_HEAD_SIZE.saturating_sub(head.len() as u64);
+        if limit == 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "The request head is too long",
+            ));
+        }
+        let start = head.len();
+        if reader.by_ref().take(limit).read_until(b'\n', &mut head)? == 0 {
+            return if head.is_empty() {
+                Ok(None)
+            } else {
+                Err(ErrorKind::UnexpectedEof.into())
+            };
+        }
+        let line = &head[start..];
+        if line == b"\r\n" || line == b"\n" {
+            if start == 0 {
+                // Empty lines are allowed before the request line
+                head.clear();
+                continue;
+            }
+            return String::from_utf8(head)
+                .map(Some)
+                .map_err(|e| io::Error::new(ErrorKind::InvalidData, e));
+        }
+    }
+}
+
+/// Builds the request from its head, its body is read from the stream when the handler needs it
+fn parse_request(head: &str, stream: &SharedStream) -> Result<(Request, RequestHead), String> {
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("Invalid request line: {request_line}"));
+    };
+    let method = Method::from_str(method).map_err(|e| e.to_string())?;
+    let mut keep_alive = match version {
+        "HTTP/1.1" => true,
+        "HTTP/1.0" => false,
+        _ => return Err(format!("Unsupported HTTP version: {version}")),
+    };
+    let mut headers = Vec::new();
+    let mut host = None;
+    let mut content_length = None;
+    let mut chunked = false;
+    let mut expects_continue = false;
+    for line in lines.filter(|line| !line.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(format!("Invalid header: {line}"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("Host") {
+            host = Some(value);
+        } else if name.eq_ignore_ascii_case("Content-Length") {
+            let length = value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid Content-Length: {value}"))?;
+            if content_length.is_some_and(|previous| previous != length) {
+                return Err("Conflicting Content-Length headers".into());
+            }
+            content_length = Some(length);
+        } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+            if !value
+                .rsplit(',')
+                .next()
+                .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+            {
+                return Err(format!("Unsupported Transfer-Encoding: {value}"));
+            }
+            chunked = true;
+        } else if name.eq_ignore_ascii_case("Connection") {
+            if value
+                .split(',')
+                .any(|option| option.trim().eq_ignore_ascii_case("close"))
+            {
+                keep_alive = false;
+            }
+        } else if name.eq_ignore_ascii_case("Expect") {
+            expects_continue = value.eq_ignore_ascii_case("100-continue");
+        }
+        headers.push((name, value));
+    }
+    if chunked && content_length.is_some() {
+        return Err("Content-Length and Transfer-Encoding can not be used together".into());
+    }
+    let mut url = if target.starts_with('/') {
+        let host = host.ok_or("The Host header is required")?;
+        Url::parse(&format!("https://{host}{target}"))
+    } else {
+        Url::parse(target)
+    }
+    .map_err(|e| format!("Invalid request target {target}: {e}"))?;
+    // The scheme is the one of the connection, whatever the request says
+    url.set_scheme("https")
+        .map_err(|()| format!("Invalid request target {target}"))?;
+    let is_head = method == Method::HEAD;
+    let mut request = Request::builder(method, url);
+    for (name, value) in headers {
+        request = request
+            .with_header(
+                HeaderName::from_str(name).map_err(|e| e.to_string())?,
+                value,
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    let framing = if chunked {
+        BodyFraming::Chunked {
+            remaining: 0,
+            done: false,
+        }
+    } else {
+        BodyFraming::Length(content_length.unwrap_or(0))
+    };
+    let body = Body::from_read(RequestBody {
+        stream: stream.clone(),
+        framing,
+    });
+    Ok((
+        request.with_body(body),
+        RequestHead {
+            is_head,
+            keep_alive,
+            expects_continue,
+        },
+    ))
+}
+
+/// The body of a request, read from the connection as the handler consumes it
+struct RequestBody {
+    stream: SharedStream,
+    framing: BodyFraming,
+}
+
+enum BodyFraming {
+    Length(u64),
+    Chunked { remaining: u64, done: bool },
+}
+
+impl Read for RequestBody {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut stream = self.stream.lock();
+        let chunked = matches!(self.framing, BodyFraming::Chunked { .. });
+        let remaining = match &mut self.framing {
+            BodyFraming::Length(remaining) => remaining,
+            BodyFraming::Chunked { done: true, .. } => return Ok(0),
+            BodyFraming::Chunked { remaining, done } => {
+                if *remaining == 0 {
+                    *remaining = read_chunk_size(&mut *stream)?;
+                    if *remaining == 0 {
+                        // The trailers are ignored
+                        while !read_chunk_line(&mut *stream)?.is_empty() {}
+                        *done = true;
+                        return Ok(0);
+                    }
+                }
+                remaining
+            }
+        };
+        if *remaining == 0 || buffer.is_empty() {
+            return Ok(0);
+        }
+        let max = usize::try_from(*remaining)
+            .map_or(buffer.len(), |remaining| min(remaining, buffer.len()));
+        let read = stream.read(&mut buffer[..max])?;
+        if read == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        *remaining -= read as u64;
+        if *remaining == 0 && chunked {
+            // The CRLF after the chunk data
+            if !read_chunk_line(&mut *stream)?.is_empty() {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid end of chunk",
+                ));
+            }
+        }
+        Ok(read)
+    }
+}
+
+fn read_chunk_size(reader: &mut impl BufRead) -> io::Result<u64> {
+    let line = read_chunk_line(reader)?;
+    // The chunk extensions are ignored
+    let size = line.split(';').next().unwrap_or_default().trim();
+    u64::from_str_radix(size, 16).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid chunk size: {size}"),
+        )
+    })
+}
+
+/// Reads a line of the chunked body framing, without its line ending
+fn read_chunk_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = Vec::new();
+    if reader
+        .by_ref()
+        .take(MAX_CHUNK_SIZE_LINE)
+        .read_until(b'\n', &mut line)?
+        == 0
+    {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(line)
+        .map(|line| line.trim_end_matches(['\r', '\n']).to_owned())
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// One of the connections the server might have open at the same time, released when dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn acquire(connections: &Arc<AtomicUsize>, max_connections: usize) -> Option<Self> {
+        if connections.fetch_add(1, Ordering::AcqRel) >= max_connections {
+            connections.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        }
+        Some(Self(Arc::clone(connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn load_config(certificate: &Path, private_key: &Path) -> anyhow::Result<Arc<ServerConfig>> {
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(
+        File::open(certificate)
+            .with_context(|| format!("Failed to open {}", certificate.display()))?,
+    ))
+    .collect::<Result<Vec<CertificateDer<'static>>, _>>()
+    .with_context(|| {
+        format!(
+            "Failed to read the certificates of {}",
+            certificate.display()
+        )
+    })?;
+    let private_key: PrivateKeyDer<'static> = rustls_pemfile::private_key(&mut BufReader::new(
+        File::open(private_key)
+            .with_context(|| format!("Failed to open {}", private_key.display()))?,
+    ))?
+    .with_context(|| format!("No private key found in {}", private_key.display()))?;
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certificates, private_key)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::panic_in_result_fn)]
+
+    use super::*;
+    use rustls::{ClientConfig, ClientConnection, RootCertStore};
+
+    #[test]
+    fn test_serve_requests() -> anyhow::Result<()> {
+        let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls");
+        // The handler answers with the URL and the body of the request
+        let server = Arc::new(TlsServer::new(
+            Path::new(&format!("{directory}/localhost.pem")),
+            Path::new(&format!("{directory}/localhost.key")),
+            |request: &mut Request| {
+                let mut body = String::new();
+                let answer = match request.body_mut().read_to_string(&mut body) {
+                    Ok(_) => format!("{} {body}", request.url()),
+                    Err(e) => e.to_string(),
+                };
+                Response::builder(Status::OK).with_body(answer)
+            },
+            "Oxigraph/test",
+            4,
+            Duration::from_secs(10),
+        )?);
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        {
+            let server = Arc::clone(&server);
+            thread::spawn(move || server.serve(listener));
+        }
+
+        let mut roots = RootCertStore::empty();
+        for certificate in rustls_pemfile::certs(&mut BufReader::new(File::open(format!(
+            "{directory}/ca.pem"
+        ))?)) {
+            roots.add(certificate?)?;
+        }
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        let mut client = StreamOwned::new(
+            ClientConnection::new(Arc::new(config), "localhost".try_into()?)?,
+            TcpStream::connect(address)?,
+        );
+        // The scheme claimed by the client is ignored
+        client.write_all(b"POST /query HTTP/1.1\r\nHost: localhost\r\nX-Forwarded-Proto: http\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nAS\r\n1\r\nK\r\n0\r\n\r\n")?;
+        client.write_all(b"POST /update HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\nConnection: close\r\n\r\nCLEAR")?;
+        let mut response = String::new();
+        client.read_to_string(&mut response)?;
+        assert!(response.starts_with("HTTP/1.1 200 \r\n"), "{response}");
+        assert!(
+            response.contains("\r\n\r\nhttps://localhost/query ASK"),
+            "{response}"
+        );
+        assert!(
+            response.ends_with("\r\n\r\nhttps://localhost/update CLEAR"),
+            "{response}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_framing() -> anyhow::Result<()> {
+        assert_eq!(
+            read_head(&mut b"\r\nGET / HTTP/1.1\r\n\r\nrest".as_ref())?.as_deref(),
+            Some("GET / HTTP/1.1\r\n\r\n")
+        );
+        assert_eq!(read_head(&mut b"".as_ref())?, None);
+        assert!(read_head(&mut b"GET / HTTP/1.1\r\n".as_ref()).is_err());
+        assert_eq!(read_chunk_size(&mut b"1a;ext=1\r\n".as_ref())?, 26);
+        assert!(read_chunk_size(&mut b"zz\r\n".as_ref()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_connection_slots() {
+        let connections = Arc::default();
+        let first = ConnectionSlot::acquire(&connections, 1);
+        assert!(first.is_some());
+        assert!(ConnectionSlot::acquire(&connections, 1).is_none());
+        drop(first);
+        assert!(ConnectionSlot::acquire(&connections, 1).is_some());
+    }
+}
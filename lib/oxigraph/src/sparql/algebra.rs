@@ -142,6 +142,95 @@ impl Update {
     pub fn using_datasets_mut(&mut self) -> impl Iterator<Item = &mut QueryDataset> {
         self.using_datasets.iter_mut().filter_map(Option::as_mut)
     }
+
+    /// Returns the graphs the update might write to or `None` if they are only known during its evaluation.
+    ///
+    /// It is the case if a template uses a variable as graph name or if an operation targets all the named graphs.
+    ///
+    /// ```
+    /// use oxigraph::model::NamedNode;
+    /// use oxigraph::sparql::Update;
+    ///
+    /// let update = Update::parse(
+    ///     "INSERT DATA { GRAPH <http://example.com/g> { <http://example.com/s> <http://example.com/p> 1 } }",
+    ///     None,
+    /// )?;
+    /// assert_eq!(
+    ///     update.written_graphs(),
+    ///     Some(vec![NamedNode::new("http://example.com/g")?.into()])
+    /// );
+    /// assert_eq!(Update::parse("CLEAR ALL", None)?.written_graphs(), None);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn written_graphs(&self) -> Option<Vec<GraphName>> {
+        fn add(graphs: &mut Vec<GraphName>, graph: GraphName) {
+            if !graphs.contains(&graph) {
+                graphs.push(graph);
+            }
+        }
+        fn add_pattern(
+            graphs: &mut Vec<GraphName>,
+            graph: &spargebra::term::GraphNamePattern,
+        ) -> Option<()> {
+            add(
+                graphs,
+                match graph {
+                    spargebra::term::GraphNamePattern::NamedNode(graph) => graph.clone().into(),
+                    spargebra::term::GraphNamePattern::DefaultGraph => GraphName::DefaultGraph,
+                    spargebra::term::GraphNamePattern::Variable(_) => return None,
+                },
+            );
+            Some(())
+        }
+        fn from_algebra(graph: &spargebra::term::GraphName) -> GraphName {
+            match graph {
+                spargebra::term::GraphName::NamedNode(graph) => graph.clone().into(),
+                spargebra::term::GraphName::DefaultGraph => GraphName::DefaultGraph,
+            }
+        }
+
+        let mut graphs = Vec::new();
+        for operation in &self.inner.operations {
+            match operation {
+                GraphUpdateOperation::InsertData { data } => {
+                    for quad in data {
+                        add(&mut graphs, from_algebra(&quad.graph_name));
+                    }
+                }
+                GraphUpdateOperation::DeleteData { data } => {
+                    for quad in data {
+                        add(&mut graphs, from_algebra(&quad.graph_name));
+                    }
+                }
+                GraphUpdateOperation::DeleteInsert { delete, insert, .. } => {
+                    for quad in delete {
+                        add_pattern(&mut graphs, &quad.graph_name)?;
+                    }
+                    for quad in insert {
+                        add_pattern(&mut graphs, &quad.graph_name)?;
+                    }
+                }
+                GraphUpdateOperation::Load { destination, .. } => {
+                    add(&mut graphs, from_algebra(destination));
+                }
+                GraphUpdateOperation::Clear { graph, .. }
+                | GraphUpdateOperation::Drop { graph, .. } => match graph {
+                    spargebra::algebra::GraphTarget::NamedNode(graph) => {
+                        add(&mut graphs, graph.clone().into());
+                    }
+                    spargebra::algebra::GraphTarget::DefaultGraph => {
+                        add(&mut graphs, GraphName::DefaultGraph);
+                    }
+                    spargebra::algebra::GraphTarget::NamedGraphs
+                    | spargebra::algebra::GraphTarget::AllGraphs => return None,
+                },
+                GraphUpdateOperation::Create { graph, .. } => {
+                    add(&mut graphs, graph.clone().into());
+                }
+            }
+        }
+        Some(graphs)
+    }
 }
 
 impl fmt::Display for Update {
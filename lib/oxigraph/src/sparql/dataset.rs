@@ -2,16 +2,18 @@ use crate::model::TermRef;
 use crate::sparql::algebra::QueryDataset;
 use crate::sparql::EvaluationError;
 use crate::storage::numeric_encoder::{insert_term, EncodedQuad, EncodedTerm, StrHash, StrLookup};
-use crate::storage::{StorageError, StorageReader};
+use crate::storage::{PathComparison, ScanPartition, StorageError, StorageReader, SubgraphRadius};
+use sparopt::{PatternTerm, Statistics};
 use std::cell::RefCell;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::empty;
 
 pub struct DatasetView {
     reader: StorageReader,
     extra: RefCell<HashMap<StrHash, String>>,
     dataset: EncodedDatasetSpec,
+    partition: Option<ScanPartition>,
 }
 
 impl DatasetView {
@@ -28,6 +30,53 @@ impl DatasetView {
             reader,
             extra: RefCell::new(HashMap::default()),
             dataset,
+            partition: None,
+        }
+    }
+
+    /// Splits the scans of the store into `count` partitions
+    pub fn scan_partitions(&self, count: usize) -> Vec<ScanPartition> {
+        self.reader.scan_partitions(count)
+    }
+
+    pub fn compare_paths(
+        &self,
+        first: &EncodedTerm,
+        second: &EncodedTerm,
+    ) -> Option<PathComparison> {
+        self.reader.compare_paths(first, second)
+    }
+
+    pub fn private_nodes(&self, path: &EncodedTerm) -> Option<usize> {
+        self.reader.private_nodes(path)
+    }
+
+    pub fn neighbourhood(
+        &self,
+        seed: &EncodedTerm,
+        radius: SubgraphRadius,
+    ) -> Option<HashSet<u64>> {
+        self.reader.neighbourhood(seed, radius)
+    }
+
+    pub fn node_id(&self, node: &EncodedTerm) -> Option<u64> {
+        self.reader.node_id(node)
+    }
+
+    /// The strings added to the view during the evaluation
+    pub fn extra_strs(&self) -> Vec<(StrHash, String)> {
+        self.extra
+            .borrow()
+            .iter()
+            .map(|(key, value)| (*key, value.clone()))
+            .collect()
+    }
+
+    /// What is needed to build the same view from another thread
+    pub fn seed(&self) -> DatasetSeed {
+        DatasetSeed {
+            reader: self.reader.clone(),
+            dataset: self.dataset.clone(),
         }
     }
 
@@ -39,9 +88,14 @@ impl DatasetView {
         graph_name: Option<&EncodedTerm>,
     ) -> impl Iterator<Item = Result<EncodedQuad, EvaluationError>> + 'static {
         // ) -> Vec<crate::model::Term> {
-        self.reader
-            .quads_for_pattern(subject, predicate, object, graph_name)
-            .map(|t| t.map_err(Into::into))
+        if let Some(partition) = &self.partition {
+            self.reader
+                .quads_for_pattern_in_partition(subject, predicate, object, graph_name, partition)
+        } else {
+            self.reader
+                .quads_for_pattern(subject, predicate, object, graph_name)
+        }
+        .map(|t| t.map_err(Into::into))
     }
 
     #[allow(clippy::needless_collect)]
@@ -181,6 +235,38 @@ impl StrLookup for DatasetView {
     }
 }
 
+impl Statistics for DatasetView {
+    fn triple_pattern_cardinality(
+        &self,
+        subject: PatternTerm<'_>,
+        predicate: PatternTerm<'_>,
+        object: PatternTerm<'_>,
+    ) -> Option<usize> {
+        self.reader
+            .triple_pattern_cardinality(subject, predicate, object)
+    }
+}
+
+/// A [`DatasetView`] that can be sent to another thread
+#[derive(Clone)]
+pub struct DatasetSeed {
+    reader: StorageReader,
+    dataset: EncodedDatasetSpec,
+}
+
+impl DatasetSeed {
+    /// The view of the whole dataset, or of a single partition of its scans
+    pub fn view(&self, partition: Option<ScanPartition>) -> DatasetView {
+        DatasetView {
+            reader: self.reader.clone(),
+            extra: RefCell::new(HashMap::default()),
+            dataset: self.dataset.clone(),
+            partition,
+        }
+    }
+}
+
+#[derive(Clone)]
 struct EncodedDatasetSpec {
     default: Option<Vec<EncodedTerm>>,
     named: Option<Vec<EncodedTerm>>,
@@ -16,7 +16,7 @@ pub enum EvaluationError {
     Parsing(#[from] SparqlSyntaxError),
     /// An error from the storage.
     #[error(transparent)]
-    Storage(#[from] StorageError),
+    Storage(StorageError),
     /// An error while parsing an external RDF file.
     #[error(transparent)]
     GraphParsing(#[from] RdfParseError),
@@ -50,6 +50,19 @@ pub enum EvaluationError {
     /// The results are not a RDF graph
     #[error("The query results are not a RDF graph")]
     NotAGraph,
+    /// The evaluation has been stopped by its timeout or its cancellation token
+    #[error("The query evaluation has been interrupted by its timeout or cancellation")]
+    Interrupted,
+}
+
+impl From<StorageError> for EvaluationError {
+    #[inline]
+    fn from(error: StorageError) -> Self {
+        match error {
+            StorageError::Interrupted => Self::Interrupted,
+            error => Self::Storage(error),
+        }
+    }
 }
 
 impl From<Infallible> for EvaluationError {
@@ -79,6 +92,7 @@ impl From<EvaluationError> for io::Error {
             | EvaluationError::UnsupportedContentType(_)
             | EvaluationError::ServiceDoesNotReturnSolutions
             | EvaluationError::NotAGraph => Self::new(io::ErrorKind::InvalidInput, error),
+            EvaluationError::Interrupted => Self::new(io::ErrorKind::TimedOut, error),
         }
     }
 }
@@ -8,6 +8,8 @@ use crate::sparql::service::ServiceHandler;
 use crate::sparql::CustomFunctionRegistry;
 use crate::storage::numeric_encoder::*;
 use crate::storage::small_string::SmallString;
+use crate::storage::vg_vocab::vg;
+use crate::storage::{Interruption, SubgraphRadius, INTERRUPTION_CHECK_INTERVAL};
 use digest::Digest;
 use json_event_parser::{JsonEvent, ToWriteJsonWriter};
 use md5::Md5;
@@ -28,7 +30,7 @@ use sparopt::algebra::{
     AggregateExpression, Expression, GraphPattern, JoinAlgorithm, LeftJoinAlgorithm,
     MinusAlgorithm, OrderExpression,
 };
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
@@ -130,6 +132,10 @@ pub struct SimpleEvaluator {
     service_handler: Arc<dyn ServiceHandler<Error = EvaluationError>>,
     custom_functions: Arc<CustomFunctionRegistry>,
     run_stats: bool,
+    num_threads: usize,
+    /// The quad pattern driving the evaluation and the partition of the dataset it is restricted to
+    partitioned: Option<(*const GraphPattern, Rc<DatasetView>)>,
+    interruption: Interruption,
 }
 
 impl SimpleEvaluator {
@@ -147,9 +153,26 @@ impl SimpleEvaluator {
             service_handler,
             custom_functions,
             run_stats,
+            num_threads: 1,
+            partitioned: None,
+            interruption: Interruption::default(),
         }
     }
 
+    /// Evaluates the scans below aggregations on `num_threads` threads
+    #[must_use]
+    pub fn with_num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Stops the evaluation with an error when it is interrupted
+    #[must_use]
+    pub fn with_interruption(mut self, interruption: Interruption) -> Self {
+        self.interruption = interruption;
+        self
+    }
+
     pub fn evaluate_select(&self, pattern: &GraphPattern) -> (QueryResults, Rc<EvalNodeWithStats>) {
         let mut variables = Vec::new();
         let (eval, stats) = self.graph_pattern_evaluator(pattern, &mut variables);
@@ -274,6 +297,18 @@ impl SimpleEvaluator {
                 })
             })
         }
+        if self.interruption.is_enabled() {
+            let interruption = self.interruption.clone();
+            let inner_evaluator = evaluator;
+            evaluator = Rc::new(move |tuple| {
+                Box::new(InterruptibleIterator {
+                    inner: inner_evaluator(tuple),
+                    interruption: interruption.clone(),
+                    steps_since_check: 0,
+                    interrupted: false,
+                })
+            })
+        }
         (evaluator, stats)
     }
 
@@ -382,7 +417,12 @@ impl SimpleEvaluator {
                     encoded_variables,
                     &self.dataset,
                 );
-                let dataset = Rc::clone(&self.dataset);
+                let dataset = match &self.partitioned {
+                    Some((driving, partition)) if std::ptr::eq(pattern, *driving) => {
+                        Rc::clone(partition)
+                    }
+                    _ => Rc::clone(&self.dataset),
+                };
                 Rc::new(move |from| {
                     let iter = dataset.encoded_quads_for_pattern(
                         subject.get_pattern_value(&from).as_ref(),
@@ -965,6 +1005,15 @@ impl SimpleEvaluator {
                 aggregates,
                 variables,
             } => {
+                if let Some(evaluator) = self.parallel_group_evaluator(
+                    inner,
+                    variables,
+                    aggregates,
+                    encoded_variables,
+                    stat_children,
+                ) {
+                    return evaluator;
+                }
                 let (child, child_stats) = self.graph_pattern_evaluator(inner, encoded_variables);
                 stat_children.push(child_stats);
                 let key_variables = variables
@@ -1060,6 +1109,216 @@ impl SimpleEvaluator {
         }
     }
 
+    /// Aggregates `inner` on each partition of the dataset as a task of the rayon thread pool,
+    /// the partial aggregates of the partitions are then combined on the calling thread.
+    ///
+    /// Only the quad pattern driving the evaluation is restricted to a partition,
+    /// so the union of the solutions of all partitions is the solutions of `inner`.
+    /// The aggregates must be combinable from their partial values:
+    /// `COUNT` and `SUM` without `DISTINCT`, `MIN`, `MAX` and `SAMPLE`.
+    #[cfg(not(target_family = "wasm"))]
+    fn parallel_group_evaluator(
+        &self,
+        inner: &GraphPattern,
+        variables: &[Variable],
+        aggregates: &[(Variable, AggregateExpression)],
+        encoded_variables: &mut Vec<Variable>,
+        stat_children: &mut Vec<Rc<EvalNodeWithStats>>,
+    ) -> Option<Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>> {
+        if self.num_threads <= 1 || self.partitioned.is_some() {
+            return None;
+        }
+        let driving_label = eval_node_label(driving_quad_pattern(inner)?);
+        let accumulator_builders = aggregates
+            .iter()
+            .map(|(variable, aggregate)| {
+                Some(Self::accumulator_builder(
+                    &self.dataset,
+                    &AggregateExpression::FunctionCall {
+                        name: partial_aggregate_combination(aggregate)?,
+                        expr: Expression::Variable(variable.clone()),
+                        distinct: false,
+                    },
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        // The solutions of each group are counted to skip the partitions without any
+        let solution_count = Variable::new_unchecked("__oxigraph_partition_solutions");
+        let mut partial_aggregates = aggregates.to_vec();
+        partial_aggregates.push((
+            solution_count.clone(),
+            AggregateExpression::CountSolutions { distinct: false },
+        ));
+        let group = Arc::new(GraphPattern::Group {
+            inner: Box::new(inner.clone()),
+            variables: variables.to_vec(),
+            aggregates: partial_aggregates,
+        });
+        // Encoded before the partitions copy the variables so that they share their positions
+        let key_variables = variables
+            .iter()
+            .map(|v| encode_variable(encoded_variables, v))
+            .collect::<Rc<[_]>>();
+        let accumulator_variables = aggregates
+            .iter()
+            .map(|(v, _)| encode_variable(encoded_variables, v))
+            .collect::<Rc<[_]>>();
+        let solution_count = encode_variable(encoded_variables, &solution_count);
+        let partition_variables = encoded_variables.clone();
+
+        let partitions = self.dataset.scan_partitions(self.num_threads);
+        let stats = Rc::new(EvalNodeWithStats {
+            label: format!(
+                "PartitionedAggregate(partitions = {}, driving = {driving_label})",
+                partitions.len()
+            ),
+            children: Vec::new(),
+            exec_count: Cell::new(0),
+            exec_duration: Cell::new(self.run_stats.then(DayTimeDuration::default)),
+        });
+        stat_children.push(Rc::clone(&stats));
+        let seed = self.dataset.seed();
+        let base_iri = self.base_iri.as_ref().map(|iri| iri.as_ref().clone());
+        let now = self.now;
+        let run_stats = self.run_stats;
+        let service_handler = Arc::clone(&self.service_handler);
+        let custom_functions = Arc::clone(&self.custom_functions);
+        let interruption = self.interruption.clone();
+        let dataset = Rc::clone(&self.dataset);
+        Some(Rc::new(move |from| {
+            let start = Timer::now();
+            let mut partial_groups = partitions.iter().map(|_| None).collect::<Vec<_>>();
+            rayon_core::scope(|scope| {
+                for (partition, partial) in partitions.iter().zip(&mut partial_groups) {
+                    let (group, seed, base_iri, interruption) =
+                        (&group, &seed, &base_iri, &interruption);
+                    let (service_handler, custom_functions) = (&service_handler, &custom_functions);
+                    let mut variables = partition_variables.clone();
+                    let from = from.clone();
+                    scope.spawn(move |_| {
+                        let GraphPattern::Group { inner, .. } = group.as_ref() else {
+                            return;
+                        };
+                        let evaluator = Self {
+                            dataset: Rc::new(seed.view(None)),
+                            base_iri: base_iri.clone().map(Rc::new),
+                            now,
+                            service_handler: Arc::clone(service_handler),
+                            custom_functions: Arc::clone(custom_functions),
+                            run_stats: false,
+                            num_threads: 1,
+                            partitioned: driving_quad_pattern(inner).map(|driving| {
+                                (
+                                    driving as *const GraphPattern,
+                                    Rc::new(seed.view(Some(partition.clone()))),
+                                )
+                            }),
+                            interruption: interruption.clone(),
+                        };
+                        // The solutions are aggregated while they are scanned,
+                        // only a tuple per group is kept
+                        let (eval, _) = evaluator.graph_pattern_evaluator(group, &mut variables);
+                        let groups = eval(from).collect::<Vec<_>>();
+                        *partial = Some((groups, evaluator.dataset.extra_strs()));
+                    });
+                }
+            });
+
+            let tuple_size = from.capacity();
+            let mut errors = Vec::new();
+            let mut accumulators_for_group =
+                HashMap::<Vec<Option<EncodedTerm>>, Vec<Box<dyn Accumulator>>>::default();
+            if key_variables.is_empty() {
+                // There is always a single group if there is no GROUP BY
+                accumulators_for_group.insert(
+                    Vec::new(),
+                    accumulator_builders.iter().map(|c| c()).collect(),
+                );
+            }
+            let mut partial_count = 0;
+            for (groups, strs) in partial_groups.into_iter().flatten() {
+                // The strings created by the partitions are needed to decode their aggregates
+                for (key, value) in strs {
+                    dataset.insert_str(&key, &value);
+                }
+                for group in groups {
+                    let group = match group {
+                        Ok(group) => group,
+                        Err(error) => {
+                            errors.push(error);
+                            continue;
+                        }
+                    };
+                    partial_count += 1;
+                    if group.get(solution_count) == Some(&EncodedTerm::from(0_i64)) {
+                        continue; // The partition has no solution
+                    }
+                    let key = key_variables
+                        .iter()
+                        .map(|v| group.get(*v).cloned())
+                        .collect();
+                    let accumulators = accumulators_for_group
+                        .entry(key)
+                        .or_insert_with(|| accumulator_builders.iter().map(|c| c()).collect());
+                    for (accumulator, variable) in
+                        accumulators.iter_mut().zip(accumulator_variables.iter())
+                    {
+                        accumulator.add(group.get(*variable).cloned());
+                    }
+                }
+            }
+            if run_stats {
+                stats.exec_count.set(stats.exec_count.get() + partial_count);
+                stats.exec_duration.set(
+                    stats
+                        .exec_duration
+                        .get()
+                        .and_then(|stat| stat.checked_add(start.elapsed()?)),
+                );
+            }
+            let key_variables = Rc::clone(&key_variables);
+            let accumulator_variables = Rc::clone(&accumulator_variables);
+            Box::new(
+                errors
+                    .into_iter()
+                    .map(Err)
+                    .chain(
+                        accumulators_for_group
+                            .into_iter()
+                            .map(move |(key, accumulators)| {
+                                let mut result = EncodedTuple::with_capacity(tuple_size);
+                                for (variable, value) in key_variables.iter().zip(key) {
+                                    if let Some(value) = value {
+                                        result.set(*variable, value);
+                                    }
+                                }
+                                for (accumulator, variable) in
+                                    accumulators.into_iter().zip(accumulator_variables.iter())
+                                {
+                                    if let Some(value) = accumulator.state() {
+                                        result.set(*variable, value);
+                                    }
+                                }
+                                Ok(result)
+                            }),
+                    ),
+            )
+        }))
+    }
+
+    #[cfg(target_family = "wasm")]
+    #[allow(clippy::unused_self)]
+    fn parallel_group_evaluator(
+        &self,
+        _inner: &GraphPattern,
+        _variables: &[Variable],
+        _aggregates: &[(Variable, AggregateExpression)],
+        _encoded_variables: &mut Vec<Variable>,
+        _stat_children: &mut Vec<Rc<EvalNodeWithStats>>,
+    ) -> Option<Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>> {
+        None
+    }
+
     fn evaluate_service(
         &self,
         service_name: &TupleSelector,
@@ -2739,6 +2998,89 @@ impl SimpleEvaluator {
                                     _ => None,
                                 })
                             }
+                            vg::SHARED_NODES | vg::SHARED_BASES | vg::JACCARD
+                                if parameters.len() == 2 =>
+                            {
+                                let first = self.expression_evaluator(
+                                    &parameters[0],
+                                    encoded_variables,
+                                    stat_children,
+                                );
+                                let second = self.expression_evaluator(
+                                    &parameters[1],
+                                    encoded_variables,
+                                    stat_children,
+                                );
+                                let function_name = function_name.clone();
+                                let dataset = Rc::clone(&self.dataset);
+                                Rc::new(move |tuple| {
+                                    let comparison =
+                                        dataset.compare_paths(&first(tuple)?, &second(tuple)?)?;
+                                    Some(match function_name.as_ref() {
+                                        vg::SHARED_NODES => {
+                                            Integer::from(comparison.shared_nodes as i64).into()
+                                        }
+                                        vg::SHARED_BASES => {
+                                            Integer::from(comparison.shared_bases as i64).into()
+                                        }
+                                        _ => Double::from(comparison.jaccard()).into(),
+                                    })
+                                })
+                            }
+                            vg::WITHIN_STEPS | vg::WITHIN_BASES if parameters.len() == 3 => {
+                                let node = self.expression_evaluator(
+                                    &parameters[0],
+                                    encoded_variables,
+                                    stat_children,
+                                );
+                                let seed = self.expression_evaluator(
+                                    &parameters[1],
+                                    encoded_variables,
+                                    stat_children,
+                                );
+                                let radius = self.expression_evaluator(
+                                    &parameters[2],
+                                    encoded_variables,
+                                    stat_children,
+                                );
+                                let counts_bases = function_name.as_ref() == vg::WITHIN_BASES;
+                                let dataset = Rc::clone(&self.dataset);
+                                // The same neighbourhood is usually tested for every solution
+                                let neighbourhoods = RefCell::new(HashMap::new());
+                                Rc::new(move |tuple| {
+                                    let EncodedTerm::IntegerLiteral(radius) = radius(tuple)? else {
+                                        return None;
+                                    };
+                                    let radius = u64::try_from(i64::from(radius)).ok()?;
+                                    let radius = if counts_bases {
+                                        SubgraphRadius::Bases(radius)
+                                    } else {
+                                        SubgraphRadius::Steps(radius)
+                                    };
+                                    let node = dataset.node_id(&node(tuple)?)?;
+                                    let seed = seed(tuple)?;
+                                    let neighbourhood = neighbourhoods
+                                        .borrow_mut()
+                                        .entry((seed.clone(), radius))
+                                        .or_insert_with(|| {
+                                            dataset.neighbourhood(&seed, radius).map(Rc::new)
+                                        })
+                                        .clone()?;
+                                    Some(neighbourhood.contains(&node).into())
+                                })
+                            }
+                            vg::PRIVATE_NODES if parameters.len() == 1 => {
+                                let path = self.expression_evaluator(
+                                    &parameters[0],
+                                    encoded_variables,
+                                    stat_children,
+                                );
+                                let dataset = Rc::clone(&self.dataset);
+                                Rc::new(move |tuple| {
+                                    let count = dataset.private_nodes(&path(tuple)?)?;
+                                    Some(Integer::from(count as i64).into())
+                                })
+                            }
                             _ => Rc::new(|_| None),
                         }
                     }
@@ -3143,6 +3485,40 @@ pub(super) fn compile_pattern(pattern: &str, flags: Option<&str>) -> Option<Rege
     regex_builder.build().ok()
 }
 
+/// The aggregate combining the partial values of `aggregate` computed on disjoint sets of solutions
+#[cfg(not(target_family = "wasm"))]
+fn partial_aggregate_combination(aggregate: &AggregateExpression) -> Option<AggregateFunction> {
+    match aggregate {
+        AggregateExpression::CountSolutions { distinct: false }
+        | AggregateExpression::FunctionCall {
+            name: AggregateFunction::Count | AggregateFunction::Sum,
+            distinct: false,
+            ..
+        } => Some(AggregateFunction::Sum),
+        AggregateExpression::FunctionCall { name, .. } => match name {
+            AggregateFunction::Min => Some(AggregateFunction::Min),
+            AggregateFunction::Max => Some(AggregateFunction::Max),
+            AggregateFunction::Sample => Some(AggregateFunction::Sample),
+            _ => None,
+        },
+        AggregateExpression::CountSolutions { distinct: true } => None,
+    }
+}
+
+/// The leftmost quad pattern of `pattern`, each of its solutions is extended independently of the others
+fn driving_quad_pattern(pattern: &GraphPattern) -> Option<&GraphPattern> {
+    match pattern {
+        GraphPattern::QuadPattern { .. } => Some(pattern),
+        GraphPattern::Join { left, .. }
+        | GraphPattern::LeftJoin { left, .. }
+        | GraphPattern::Lateral { left, .. } => driving_quad_pattern(left),
+        GraphPattern::Filter { inner, .. } | GraphPattern::Extend { inner, .. } => {
+            driving_quad_pattern(inner)
+        }
+        _ => None,
+    }
+}
+
 fn decode_bindings(
     dataset: Rc<DatasetView>,
     iter: EncodedTuplesIterator,
@@ -5657,6 +6033,30 @@ impl Iterator for StatsIterator {
     }
 }
 
+/// Stops the solutions of an operator with an error when the evaluation is interrupted
+struct InterruptibleIterator {
+    inner: EncodedTuplesIterator,
+    interruption: Interruption,
+    steps_since_check: u32,
+    interrupted: bool,
+}
+
+impl Iterator for InterruptibleIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.interrupted {
+            return None;
+        }
+        if self.steps_since_check == 0 && self.interruption.is_interrupted() {
+            self.interrupted = true;
+            return Some(Err(EvaluationError::Interrupted));
+        }
+        self.steps_since_check = (self.steps_since_check + 1) % INTERRUPTION_CHECK_INTERVAL;
+        self.inner.next()
+    }
+}
+
 pub struct EvalNodeWithStats {
     pub label: String,
     pub children: Vec<Rc<EvalNodeWithStats>>,
@@ -12,16 +12,17 @@ pub mod results;
 mod service;
 mod update;
 
-use crate::model::{NamedNode, Term};
+use crate::model::{NamedNode, NamedNodeRef, Term};
 pub use crate::sparql::algebra::{Query, QueryDataset, Update};
 use crate::sparql::dataset::DatasetView;
 pub use crate::sparql::error::EvaluationError;
 use crate::sparql::eval::{EvalNodeWithStats, SimpleEvaluator, Timer};
 pub use crate::sparql::model::{QueryResults, QuerySolution, QuerySolutionIter, QueryTripleIter};
-pub use crate::sparql::service::ServiceHandler;
 use crate::sparql::service::{EmptyServiceHandler, ErrorConversionServiceHandler};
+pub use crate::sparql::service::{ServiceHandler, SimpleServiceHandler};
 pub(crate) use crate::sparql::update::evaluate_update;
-use crate::storage::StorageReader;
+use crate::storage::vg_vocab::vg;
+use crate::storage::{Interruption, StorageReader};
 use json_event_parser::{JsonEvent, ToWriteJsonWriter};
 pub use oxrdf::{Variable, VariableNameParseError};
 use oxsdatatypes::{DayTimeDuration, Float};
@@ -30,10 +31,24 @@ use sparopt::algebra::GraphPattern;
 use sparopt::Optimizer;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+#[cfg(not(target_family = "wasm"))]
+use std::time::Instant;
 use std::{fmt, io};
 
+/// The functions on pangenome graphs available in SPARQL in addition to the standard ones,
+/// e.g. `vg:jaccard(?path1, ?path2)` or `vg:withinSteps(?node, ?seed, 3)`.
+pub const EXTENSION_FUNCTIONS: [NamedNodeRef<'static>; 6] = [
+    vg::SHARED_NODES,
+    vg::SHARED_BASES,
+    vg::JACCARD,
+    vg::PRIVATE_NODES,
+    vg::WITHIN_STEPS,
+    vg::WITHIN_BASES,
+];
+
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn evaluate_query(
     reader: StorageReader,
@@ -42,7 +57,11 @@ pub(crate) fn evaluate_query(
     run_stats: bool,
 ) -> Result<(Result<QueryResults, EvaluationError>, QueryExplanation), EvaluationError> {
     let query = query.try_into().map_err(Into::into)?;
-    let dataset = DatasetView::new(reader, &query.dataset);
+    let interruption = options.interruption();
+    let dataset = DatasetView::new(
+        reader.with_interruption(interruption.clone()),
+        &query.dataset,
+    );
     let start_planning = Timer::now();
     let (results, plan_node_with_stats, planning_duration) = match query.inner {
         spargebra::Query::Select {
@@ -50,7 +69,7 @@ pub(crate) fn evaluate_query(
         } => {
             let mut pattern = GraphPattern::from(&pattern);
             if !options.without_optimizations {
-                pattern = Optimizer::optimize_graph_pattern(pattern);
+                pattern = Optimizer::optimize_graph_pattern_with_statistics(pattern, &dataset);
             }
             let planning_duration = start_planning.elapsed();
             let (results, explanation) = SimpleEvaluator::new(
@@ -60,6 +79,8 @@ pub(crate) fn evaluate_query(
                 Arc::new(options.custom_functions),
                 run_stats,
             )
+            .with_num_threads(options.num_threads)
+            .with_interruption(interruption.clone())
             .evaluate_select(&pattern);
             (Ok(results), explanation, planning_duration)
         }
@@ -68,9 +89,12 @@ pub(crate) fn evaluate_query(
         } => {
             let mut pattern = GraphPattern::from(&pattern);
             if !options.without_optimizations {
-                pattern = Optimizer::optimize_graph_pattern(GraphPattern::Reduced {
-                    inner: Box::new(pattern),
-                });
+                pattern = Optimizer::optimize_graph_pattern_with_statistics(
+                    GraphPattern::Reduced {
+                        inner: Box::new(pattern),
+                    },
+                    &dataset,
+                );
             }
             let planning_duration = start_planning.elapsed();
             let (results, explanation) = SimpleEvaluator::new(
@@ -80,6 +104,8 @@ pub(crate) fn evaluate_query(
                 Arc::new(options.custom_functions),
                 run_stats,
             )
+            .with_num_threads(options.num_threads)
+            .with_interruption(interruption.clone())
             .evaluate_ask(&pattern);
             (results, explanation, planning_duration)
         }
@@ -91,9 +117,12 @@ pub(crate) fn evaluate_query(
         } => {
             let mut pattern = GraphPattern::from(&pattern);
             if !options.without_optimizations {
-                pattern = Optimizer::optimize_graph_pattern(GraphPattern::Reduced {
-                    inner: Box::new(pattern),
-                });
+                pattern = Optimizer::optimize_graph_pattern_with_statistics(
+                    GraphPattern::Reduced {
+                        inner: Box::new(pattern),
+                    },
+                    &dataset,
+                );
             }
             let planning_duration = start_planning.elapsed();
             let (results, explanation) = SimpleEvaluator::new(
@@ -103,6 +132,8 @@ pub(crate) fn evaluate_query(
                 Arc::new(options.custom_functions),
                 run_stats,
             )
+            .with_num_threads(options.num_threads)
+            .with_interruption(interruption.clone())
             .evaluate_construct(&pattern, &template);
             (Ok(results), explanation, planning_duration)
         }
@@ -111,9 +142,12 @@ pub(crate) fn evaluate_query(
         } => {
             let mut pattern = GraphPattern::from(&pattern);
             if !options.without_optimizations {
-                pattern = Optimizer::optimize_graph_pattern(GraphPattern::Reduced {
-                    inner: Box::new(pattern),
-                });
+                pattern = Optimizer::optimize_graph_pattern_with_statistics(
+                    GraphPattern::Reduced {
+                        inner: Box::new(pattern),
+                    },
+                    &dataset,
+                );
             }
             let planning_duration = start_planning.elapsed();
             let (results, explanation) = SimpleEvaluator::new(
@@ -123,6 +157,8 @@ pub(crate) fn evaluate_query(
                 Arc::new(options.custom_functions),
                 run_stats,
             )
+            .with_num_threads(options.num_threads)
+            .with_interruption(interruption.clone())
             .evaluate_describe(&pattern);
             (Ok(results), explanation, planning_duration)
         }
@@ -161,6 +197,9 @@ pub struct QueryOptions {
     http_timeout: Option<Duration>,
     http_redirection_limit: usize,
     without_optimizations: bool,
+    num_threads: usize,
+    timeout: Option<Duration>,
+    cancellation_token: Option<CancellationToken>,
 }
 
 pub(crate) type CustomFunctionRegistry =
@@ -185,6 +224,19 @@ impl QueryOptions {
         self
     }
 
+    /// Wraps the [`ServiceHandler`] of the `SERVICE` calls, e.g. to measure them.
+    ///
+    /// `wrap` is given the handler set before or else the default one, with the HTTP settings set before.
+    #[inline]
+    #[must_use]
+    pub fn with_wrapped_service_handler<H: ServiceHandler + 'static>(
+        self,
+        wrap: impl FnOnce(Arc<dyn ServiceHandler<Error = EvaluationError>>) -> H,
+    ) -> Self {
+        let service_handler = wrap(self.service_handler());
+        self.with_service_handler(service_handler)
+    }
+
     /// Sets a timeout for HTTP requests done during SPARQL evaluation.
     #[cfg(feature = "http-client")]
     #[inline]
@@ -240,10 +292,87 @@ impl QueryOptions {
         self
     }
 
+    /// Evaluates the aggregates on `num_threads` partitions of the graph in parallel.
+    ///
+    /// The nodes and paths of the graph are split into as many partitions, each of them aggregated
+    /// by a task of the global rayon thread pool, so that whole-pangenome statistics use all the cores.
+    /// Only `COUNT` and `SUM` without `DISTINCT`, `MIN`, `MAX` and `SAMPLE` are combined from
+    /// the partitions, the other aggregates are evaluated on the calling thread.
+    /// By default, and with a value of at most `1`, queries are evaluated on the calling thread.
+    ///
+    /// ```
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::new()?;
+    /// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+    ///     "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p ?o }",
+    ///     QueryOptions::default().with_num_threads(4),
+    /// )? {
+    ///     assert!(solutions.next().unwrap()?.get("c").is_some());
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[cfg(not(target_family = "wasm"))]
+    #[inline]
+    #[must_use]
+    pub fn with_num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Stops the evaluation of the query after `timeout`.
+    ///
+    /// The timeout starts when the evaluation starts and also covers the iteration of the results,
+    /// which then returns an [`EvaluationError::Interrupted`] error.
+    ///
+    /// ```
+    /// use oxigraph::sparql::QueryOptions;
+    /// use oxigraph::store::Store;
+    /// use std::time::Duration;
+    ///
+    /// let store = Store::new()?;
+    /// store.query_opt(
+    ///     "SELECT * WHERE { ?s ?p ?o }",
+    ///     QueryOptions::default().with_timeout(Duration::from_secs(30)),
+    /// )?;
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[cfg(not(target_family = "wasm"))]
+    #[inline]
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stops the evaluation of the query when `token` is cancelled, see [`CancellationToken`].
+    #[inline]
+    #[must_use]
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    fn interruption(&self) -> Interruption {
+        #[cfg(not(target_family = "wasm"))]
+        let deadline = self
+            .timeout
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+        #[cfg(target_family = "wasm")]
+        let deadline = None;
+        Interruption::new(
+            self.cancellation_token
+                .as_ref()
+                .map(|token| Arc::clone(&token.cancelled)),
+            deadline,
+        )
+    }
+
     fn service_handler(&self) -> Arc<dyn ServiceHandler<Error = EvaluationError>> {
         self.service_handler.clone().unwrap_or_else(|| {
             if cfg!(feature = "http-client") {
-                Arc::new(service::SimpleServiceHandler::new(
+                Arc::new(SimpleServiceHandler::new(
                     self.http_timeout,
                     self.http_redirection_limit,
                 ))
@@ -262,6 +391,52 @@ impl QueryOptions {
     }
 }
 
+/// A handle to stop query evaluations from another thread.
+///
+/// The scans and the operators of the queries evaluated with it, see [`QueryOptions::with_cancellation_token`],
+/// check it regularly and then return an [`EvaluationError::Interrupted`] error.
+///
+/// ```
+/// use oxigraph::sparql::{CancellationToken, EvaluationError, QueryOptions, QueryResults};
+/// use oxigraph::store::Store;
+///
+/// let store = Store::gfa_loader().load_from_read(b"S\t1\tA\n".as_ref())?;
+/// let token = CancellationToken::new();
+/// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+///     "SELECT * WHERE { ?s ?p ?o }",
+///     QueryOptions::default().with_cancellation_token(token.clone()),
+/// )? {
+///     token.cancel();
+///     assert!(matches!(
+///         solutions.next(),
+///         Some(Err(EvaluationError::Interrupted))
+///     ));
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the evaluations using this token
+    #[inline]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
 /// Options for SPARQL update evaluation.
 #[derive(Clone, Default)]
 pub struct UpdateOptions {
@@ -92,11 +92,24 @@ impl<S: ServiceHandler> ServiceHandler for ErrorConversionServiceHandler<S> {
     }
 }
 
+/// The default [`ServiceHandler`]: sends the query to the SPARQL endpoint named by the SERVICE IRI using HTTP.
+///
+/// It is useful to build handlers that decorate the default behavior, e.g. to log or measure the SERVICE calls.
+/// The calls always fail if the `http-client` feature is disabled.
+///
+/// ```
+/// use oxigraph::sparql::{QueryOptions, SimpleServiceHandler};
+/// use std::time::Duration;
+///
+/// let options = QueryOptions::default()
+///     .with_service_handler(SimpleServiceHandler::new(Some(Duration::from_secs(10)), 0));
+/// ```
 pub struct SimpleServiceHandler {
     client: Client,
 }
 
 impl SimpleServiceHandler {
+    /// Builds a handler with an optional timeout and an upper bound of the number of followed redirections per HTTP request.
     pub fn new(http_timeout: Option<Duration>, http_redirection_limit: usize) -> Self {
         Self {
             client: Client::new(http_timeout, http_redirection_limit),
@@ -1,5 +1,6 @@
 use crate::io::{RdfFormat, RdfParseError};
 use crate::storage::numeric_encoder::EncodedTerm;
+use crate::storage::validation::ValidationProblem;
 use oxiri::IriParseError;
 use oxrdf::TermRef;
 use std::error::Error;
@@ -15,6 +16,9 @@ pub enum StorageError {
     /// Error related to data corruption.
     #[error(transparent)]
     Corruption(#[from] CorruptionError),
+    /// The scan has been stopped by the timeout or the cancellation of the query evaluation.
+    #[error("The scan of the graph has been interrupted")]
+    Interrupted,
     #[doc(hidden)]
     #[error("{0}")]
     Other(#[source] Box<dyn Error + Send + Sync + 'static>),
@@ -26,6 +30,7 @@ impl From<StorageError> for io::Error {
         match error {
             StorageError::Io(error) => error,
             StorageError::Corruption(error) => error.into(),
+            StorageError::Interrupted => Self::new(io::ErrorKind::TimedOut, error),
             StorageError::Other(error) => Self::new(io::ErrorKind::Other, error),
         }
     }
@@ -137,3 +142,100 @@ impl From<SerializerError> for io::Error {
         }
     }
 }
+
+/// An error in a [GFA](https://github.com/GFA-spec/GFA-spec) record, located in its file.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid GFA record at line {line}, column {column}: {message}")]
+pub struct GfaParseError {
+    line: u64,
+    column: u64,
+    message: String,
+}
+
+impl GfaParseError {
+    #[inline]
+    pub(crate) fn new(line: u64, column: u64, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// The 1-based line of the invalid record.
+    #[inline]
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    /// The 1-based column, in bytes, of the field that made the record invalid.
+    #[inline]
+    pub fn column(&self) -> u64 {
+        self.column
+    }
+
+    /// The error message of the GFA parser.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<GfaParseError> for io::Error {
+    #[inline]
+    fn from(error: GfaParseError) -> Self {
+        Self::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// An error raised while loading a GFA file into a [`Store`](crate::store::Store).
+#[derive(Debug, thiserror::Error)]
+pub enum GfaLoaderError {
+    /// An error raised while reading or decompressing the file.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// An invalid record.
+    #[error(transparent)]
+    Parsing(#[from] GfaParseError),
+}
+
+impl From<GfaLoaderError> for io::Error {
+    #[inline]
+    fn from(error: GfaLoaderError) -> Self {
+        match error {
+            GfaLoaderError::Io(error) => error,
+            GfaLoaderError::Parsing(error) => error.into(),
+        }
+    }
+}
+
+impl From<GfaLoaderError> for StorageError {
+    #[inline]
+    fn from(error: GfaLoaderError) -> Self {
+        match error {
+            GfaLoaderError::Io(error) => Self::Io(error),
+            GfaLoaderError::Parsing(error) => Self::Other(Box::new(error)),
+        }
+    }
+}
+
+/// An error returned by [`Store::validate`](crate::store::Store::validate).
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    /// An error raised while reading the store.
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    /// The graph breaks some invariants, all the problems found are listed.
+    #[error("The graph is invalid, {} problem(s) found", .0.len())]
+    Invalid(Vec<ValidationProblem>),
+}
+
+impl From<ValidationError> for io::Error {
+    #[inline]
+    fn from(error: ValidationError) -> Self {
+        match error {
+            ValidationError::Storage(error) => error.into(),
+            ValidationError::Invalid(_) => Self::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
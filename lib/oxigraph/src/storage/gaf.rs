@@ -0,0 +1,253 @@
+use crate::storage::error::StorageError;
+use gfa::gfa::Orientation;
+use handlegraph::handle::Handle;
+use handlegraph::handlegraph::HandleGraph;
+use handlegraph::packedgraph::PackedGraph;
+use handlegraph::pathhandlegraph::{GraphPathNames, GraphPaths, GraphPathsSteps};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+const GAF_MANDATORY_COLUMNS: usize = 12;
+const MISSING_MAPPING_QUALITY: u8 = 255;
+
+/// A single read alignment of a GAF file
+#[derive(Debug, Clone)]
+pub struct GafAlignment {
+    pub read_name: String,
+    pub read_length: u64,
+    pub mapping_quality: Option<u8>,
+    pub walk: Vec<Handle>,
+}
+
+impl GafAlignment {
+    /// The walk through the graph as written in GAF, e.g. `>1>3<5`
+    pub fn walk_text(&self) -> String {
+        self.walk
+            .iter()
+            .map(|handle| {
+                let orientation = if handle.is_reverse() { '<' } else { '>' };
+                format!("{}{}", orientation, handle.unpack_number())
+            })
+            .collect()
+    }
+}
+
+/// Alignments of a GAF file indexed by the nodes and edges they traverse
+#[derive(Debug, Default)]
+pub struct GafIndex {
+    alignments: Vec<GafAlignment>,
+    by_node: HashMap<u64, Vec<usize>>,
+    edges: Vec<(Handle, Handle, u64)>,
+    edge_ids: HashMap<(u64, bool, u64, bool), usize>,
+}
+
+impl GafIndex {
+    /// Reads GAF records and resolves them against `graph`.
+    ///
+    /// Walks can either be oriented node lists (`>1<2`) or path names with
+    /// a path interval, in which case the traversed steps of the path are used.
+    pub fn parse(reader: impl BufRead, graph: &PackedGraph) -> Result<Self, StorageError> {
+        let mut index = Self::default();
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let alignment = parse_record(&line, graph).map_err(|msg| {
+                StorageError::Other(
+                    format!("Invalid GAF record on line {}: {}", line_number + 1, msg).into(),
+                )
+            })?;
+            index.insert(alignment);
+        }
+        Ok(index)
+    }
+
+    fn insert(&mut self, alignment: GafAlignment) {
+        let id = self.alignments.len();
+        for handle in &alignment.walk {
+            let ids = self.by_node.entry(handle.unpack_number()).or_default();
+            // A read might visit the same node several times, it is only counted once
+            if ids.last() != Some(&id) {
+                ids.push(id);
+            }
+        }
+        for pair in alignment.walk.windows(2) {
+            let key = edge_key(pair[0], pair[1]);
+            if let Some(edge_id) = self.edge_ids.get(&key) {
+                self.edges[*edge_id].2 += 1;
+            } else {
+                self.edge_ids.insert(key, self.edges.len());
+                let (from, to) = canonical_edge(pair[0], pair[1]);
+                self.edges.push((from, to, 1));
+            }
+        }
+        self.alignments.push(alignment);
+    }
+
+    pub fn len(&self) -> usize {
+        self.alignments.len()
+    }
+
+    pub fn alignment(&self, id: usize) -> Option<&GafAlignment> {
+        self.alignments.get(id)
+    }
+
+    /// Ids of the alignments touching the node
+    pub fn alignments_on_node(&self, node_id: u64) -> &[usize] {
+        self.by_node.get(&node_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number of alignments touching the node
+    pub fn node_coverage(&self, node_id: u64) -> u64 {
+        self.alignments_on_node(node_id).len() as u64
+    }
+
+    pub fn covered_nodes(&self) -> Vec<u64> {
+        let mut nodes = self.by_node.keys().copied().collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The edge in canonical orientation with the number of alignments traversing it
+    pub fn edge(&self, id: usize) -> Option<(Handle, Handle, u64)> {
+        self.edges.get(id).copied()
+    }
+
+    pub fn edge_id(&self, from: Handle, to: Handle) -> Option<usize> {
+        self.edge_ids.get(&edge_key(from, to)).copied()
+    }
+}
+
+fn parse_record(line: &str, graph: &PackedGraph) -> Result<GafAlignment, String> {
+    let columns = line.split('\t').collect::<Vec<_>>();
+    if columns.len() < GAF_MANDATORY_COLUMNS {
+        return Err(format!(
+            "expected at least {} columns, found {}",
+            GAF_MANDATORY_COLUMNS,
+            columns.len()
+        ));
+    }
+    let read_length = parse_number(columns[1], "query length")?;
+    let walk = if columns[5].starts_with('>') || columns[5].starts_with('<') {
+        parse_walk(columns[5], graph)?
+    } else {
+        let start = parse_number(columns[7], "path start")?;
+        let end = parse_number(columns[8], "path end")?;
+        path_interval_walk(columns[5], start, end, graph)?
+    };
+    let mapping_quality = match columns[11].parse::<u8>() {
+        Ok(MISSING_MAPPING_QUALITY) => None,
+        Ok(quality) => Some(quality),
+        Err(_) => return Err(format!("invalid mapping quality '{}'", columns[11])),
+    };
+    Ok(GafAlignment {
+        read_name: columns[0].to_owned(),
+        read_length,
+        mapping_quality,
+        walk,
+    })
+}
+
+fn parse_number(value: &str, name: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} '{}'", name, value))
+}
+
+fn parse_walk(walk: &str, graph: &PackedGraph) -> Result<Vec<Handle>, String> {
+    let mut handles = Vec::new();
+    let mut rest = walk;
+    while let Some(marker) = rest.chars().next() {
+        let orientation = match marker {
+            '>' => Orientation::Forward,
+            '<' => Orientation::Backward,
+            _ => return Err(format!("invalid walk '{}'", walk)),
+        };
+        rest = &rest[1..];
+        let id_end = rest.find(['>', '<']).unwrap_or(rest.len());
+        handles.push(node_handle(&rest[..id_end], orientation, graph)?);
+        rest = &rest[id_end..];
+    }
+    if handles.is_empty() {
+        return Err(format!("empty walk '{}'", walk));
+    }
+    Ok(handles)
+}
+
+fn node_handle(id: &str, orientation: Orientation, graph: &PackedGraph) -> Result<Handle, String> {
+    let id = id
+        .parse::<u64>()
+        .map_err(|_| format!("invalid node id '{}'", id))?;
+    if !graph.has_node(id) {
+        return Err(format!("node {} is not in the graph", id));
+    }
+    Ok(Handle::new(id, orientation))
+}
+
+fn path_interval_walk(
+    path_name: &str,
+    start: u64,
+    end: u64,
+    graph: &PackedGraph,
+) -> Result<Vec<Handle>, String> {
+    let path_id = graph
+        .get_path_id(path_name.as_bytes())
+        .ok_or_else(|| format!("path {} is not in the graph", path_name))?;
+    if end <= start {
+        return Err(format!("empty path interval {}-{}", start, end));
+    }
+    // GAF intervals are 0-based and half-open, path positions are 1-based
+    let mut step = graph
+        .path_step_at_base(path_id, start as usize + 1)
+        .ok_or_else(|| format!("position {} is outside of path {}", start, path_name))?;
+    let last_step = graph
+        .path_step_at_base(path_id, end as usize)
+        .ok_or_else(|| format!("position {} is outside of path {}", end, path_name))?;
+    let mut handles = Vec::new();
+    loop {
+        let handle = graph
+            .path_handle_at_step(path_id, step)
+            .ok_or_else(|| format!("path {} has a step without node", path_name))?;
+        handles.push(handle);
+        if step == last_step {
+            break;
+        }
+        step = graph
+            .path_next_step(path_id, step)
+            .ok_or_else(|| format!("interval {}-{} exceeds path {}", start, end, path_name))?;
+    }
+    Ok(handles)
+}
+
+/// An edge and its reverse complement are the same edge
+pub fn canonical_edge(from: Handle, to: Handle) -> (Handle, Handle) {
+    let flipped = (to.flip(), from.flip());
+    let as_key = |(a, b): (Handle, Handle)| {
+        (
+            a.unpack_number(),
+            a.is_reverse(),
+            b.unpack_number(),
+            b.is_reverse(),
+        )
+    };
+    if as_key(flipped) < as_key((from, to)) {
+        flipped
+    } else {
+        (from, to)
+    }
+}
+
+pub fn edge_key(from: Handle, to: Handle) -> (u64, bool, u64, bool) {
+    let (from, to) = canonical_edge(from, to);
+    (
+        from.unpack_number(),
+        from.is_reverse(),
+        to.unpack_number(),
+        to.is_reverse(),
+    )
+}
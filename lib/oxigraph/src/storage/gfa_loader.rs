@@ -0,0 +1,264 @@
+use crate::storage::error::{GfaLoaderError, GfaParseError};
+use crate::storage::Storage;
+use gfa::gfa::GFA;
+use gfa::optfields::OptionalFields;
+use gfa::parser::GFAParser;
+use std::io::{BufRead, BufReader, Read};
+
+/// Number of records parsed between two calls of the progress hooks
+const PROGRESS_INTERVAL: u64 = 100_000;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+/// Reads a GFA file line by line and builds a [`Storage`] from it
+#[derive(Default)]
+pub struct StorageGfaLoader {
+    hooks: Vec<Box<dyn Fn(u64)>>,
+    on_parse_error: Option<Box<dyn Fn(GfaParseError) -> Result<(), GfaParseError>>>,
+}
+
+impl StorageGfaLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_progress(mut self, callback: impl Fn(u64) + 'static) -> Self {
+        self.hooks.push(Box::new(callback));
+        self
+    }
+
+    pub fn on_parse_error(
+        mut self,
+        callback: impl Fn(GfaParseError) -> Result<(), GfaParseError> + 'static,
+    ) -> Self {
+        self.on_parse_error = Some(Box::new(callback));
+        self
+    }
+
+    /// Parses the content of `read`, which might be compressed with gzip or zstd
+    pub fn load(&self, read: impl Read) -> Result<Storage, GfaLoaderError> {
+        let mut reader = decompress(read)?;
+        let parser: GFAParser<usize, OptionalFields> = GFAParser::new();
+        let mut gfa = GFA::default();
+        let mut buffer = Vec::new();
+        let mut line_number = 0;
+        let mut record_count = 0;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            line_number += 1;
+            let line = trim_line_end(&buffer);
+            if !is_supported_record(line) {
+                // Comments and record types unknown to the parser are ignored, as in the specification
+                continue;
+            }
+            match parser.parse_gfa_line(line) {
+                Ok(record) => {
+                    gfa.insert_line(record);
+                    record_count += 1;
+                    if record_count % PROGRESS_INTERVAL == 0 {
+                        self.notify_progress(record_count);
+                    }
+                }
+                Err(error) => {
+                    let error =
+                        GfaParseError::new(line_number, error_column(line), error.to_string());
+                    match &self.on_parse_error {
+                        Some(callback) => callback(error)?,
+                        None => return Err(error.into()),
+                    }
+                }
+            }
+        }
+        self.notify_progress(record_count);
+        Ok(Storage::from_parsed_gfa(&gfa))
+    }
+
+    pub(super) fn notify_progress(&self, record_count: u64) {
+        for hook in &self.hooks {
+            hook(record_count);
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn decompress<'a>(read: impl Read + 'a) -> Result<Box<dyn BufRead + 'a>, GfaLoaderError> {
+    let mut reader = BufReader::new(read);
+    let header = reader.fill_buf()?;
+    Ok(if header.starts_with(GZIP_MAGIC) {
+        Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(reader)))
+    } else if header.starts_with(ZSTD_MAGIC) {
+        Box::new(BufReader::new(zstd::stream::read::Decoder::with_buffer(
+            reader,
+        )?))
+    } else {
+        Box::new(reader)
+    })
+}
+
+#[cfg(target_family = "wasm")]
+fn decompress<'a>(read: impl Read + 'a) -> Result<Box<dyn BufRead + 'a>, GfaLoaderError> {
+    let mut reader = BufReader::new(read);
+    let header = reader.fill_buf()?;
+    if header.starts_with(GZIP_MAGIC) || header.starts_with(ZSTD_MAGIC) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Compressed GFA files are not supported on this platform",
+        )
+        .into());
+    }
+    Ok(Box::new(reader))
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn is_supported_record(line: &[u8]) -> bool {
+    matches!(
+        line,
+        [b'H' | b'S' | b'L' | b'C' | b'P'] | [b'H' | b'S' | b'L' | b'C' | b'P', b'\t', ..]
+    )
+}
+
+/// The kind of the mandatory fields of a record, used to locate errors
+#[derive(Clone, Copy)]
+enum FieldKind {
+    SegmentId,
+    Orientation,
+    Unsigned,
+    Walk,
+    Text,
+}
+
+/// Best effort 1-based column of the field that made the record invalid.
+///
+/// The GFA parser does not report where a record is invalid, so the mandatory fields
+/// are checked again here, followed by the shape of the optional fields.
+fn error_column(line: &[u8]) -> u64 {
+    use FieldKind::{Orientation, SegmentId, Text, Unsigned, Walk};
+    let mandatory: &[FieldKind] = match line.first() {
+        Some(b'S') => &[SegmentId, Text],
+        Some(b'L') => &[SegmentId, Orientation, SegmentId, Orientation, Text],
+        Some(b'C') => &[
+            SegmentId,
+            Orientation,
+            SegmentId,
+            Orientation,
+            Unsigned,
+            Text,
+        ],
+        Some(b'P') => &[Text, Walk, Text],
+        _ => &[],
+    };
+    let mut fields = Vec::new();
+    let mut start = 0;
+    for field in line.split(|b| *b == b'\t') {
+        fields.push((start, field));
+        start += field.len() + 1;
+    }
+    for (i, kind) in mandatory.iter().enumerate() {
+        let Some((start, field)) = fields.get(i + 1) else {
+            // A missing field is reported at the end of the line
+            return line.len() as u64 + 1;
+        };
+        if !is_valid_field(field, *kind) {
+            return *start as u64 + 1;
+        }
+    }
+    fields
+        .iter()
+        .skip(mandatory.len() + 1)
+        .find(|(_, field)| !is_valid_optional_field(field))
+        .map_or(1, |(start, _)| *start as u64 + 1)
+}
+
+fn is_valid_field(field: &[u8], kind: FieldKind) -> bool {
+    match kind {
+        FieldKind::SegmentId | FieldKind::Unsigned => {
+            !field.is_empty() && field.iter().all(u8::is_ascii_digit)
+        }
+        FieldKind::Orientation => matches!(field, b"+" | b"-"),
+        FieldKind::Walk => field.split(|b| *b == b',').all(|step| {
+            matches!(step, [id @ .., b'+' | b'-'] if is_valid_field(id, FieldKind::SegmentId))
+        }),
+        FieldKind::Text => !field.is_empty(),
+    }
+}
+
+/// Checks the `TAG:TYPE:VALUE` shape of an optional field
+fn is_valid_optional_field(field: &[u8]) -> bool {
+    matches!(
+        field,
+        [tag0, tag1, b':', b'A' | b'i' | b'f' | b'Z' | b'J' | b'H' | b'B', b':', ..]
+            if tag0.is_ascii_alphabetic() && tag1.is_ascii_alphanumeric()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use handlegraph::handlegraph::HandleGraph;
+    use std::cell::Cell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    const GFA: &str =
+        "H\tVN:Z:1.0\nS\t1\tCAAATAAG\nS\t2\tA\nP\tx/a\t1+,2+\t8M,1M\nL\t1\t+\t2\t+\t0M\n";
+
+    #[test]
+    fn test_load_plain() {
+        let storage = StorageGfaLoader::new().load(GFA.as_bytes()).unwrap();
+        assert_eq!(storage.graph.node_count(), 2, "Number of nodes");
+    }
+
+    #[test]
+    fn test_load_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(GFA.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let count = Rc::new(Cell::new(0));
+        let progress = Rc::clone(&count);
+        StorageGfaLoader::new()
+            .on_progress(move |records| progress.set(records))
+            .load(compressed.as_slice())
+            .unwrap();
+        assert_eq!(count.get(), 5, "Number of parsed records");
+    }
+
+    #[test]
+    fn test_load_zstd() {
+        let compressed = zstd::encode_all(GFA.as_bytes(), 0).unwrap();
+        StorageGfaLoader::new().load(compressed.as_slice()).unwrap();
+    }
+
+    #[test]
+    fn test_error_location() {
+        let gfa = "H\tVN:Z:1.0\nS\t1\tCAAATAAG\nL\t1\t+\t2\tx\t0M\n";
+        let Err(GfaLoaderError::Parsing(error)) = StorageGfaLoader::new().load(gfa.as_bytes())
+        else {
+            panic!("The invalid orientation should be reported");
+        };
+        assert_eq!(error.line(), 3);
+        assert_eq!(error.column(), 9);
+    }
+
+    #[test]
+    fn test_lenient() {
+        let gfa = "S\t1\tCAAATAAG\nS\tx\tA\nS\t2\tA\n";
+        let errors = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&errors);
+        StorageGfaLoader::new()
+            .on_parse_error(move |_| {
+                counter.set(counter.get() + 1);
+                Ok(())
+            })
+            .load(gfa.as_bytes())
+            .unwrap();
+        assert_eq!(errors.get(), 1, "Number of skipped records");
+    }
+}
@@ -0,0 +1,183 @@
+use crate::storage::gaf::edge_key;
+use gfa::gfa::GFA;
+use gfa::optfields::{OptFieldVal, OptionalFields};
+use handlegraph::handle::Handle;
+use std::collections::HashMap;
+
+/// Value of an optional GFA field, typed as far as RDF literals can represent it
+#[derive(Debug, Clone, PartialEq)]
+pub enum GfaTagValue {
+    Int(i64),
+    Float(f32),
+    /// Characters, strings, JSON, byte and number arrays are kept in their textual form
+    Text(String),
+}
+
+/// An optional field like `LN:i:42`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GfaTag {
+    pub name: String,
+    pub value: GfaTagValue,
+}
+
+/// A GFA link with its overlap and optional fields
+#[derive(Debug, Clone)]
+pub struct GfaLink {
+    pub from: Handle,
+    pub to: Handle,
+    pub overlap: Option<String>,
+    pub tags: Vec<GfaTag>,
+}
+
+/// The overlaps and optional fields of a GFA path
+#[derive(Debug, Clone, Default)]
+pub struct GfaPathTags {
+    pub overlaps: Option<String>,
+    pub tags: Vec<GfaTag>,
+}
+
+/// Everything of a GFA file that is not kept by the `PackedGraph`
+///
+/// Only elements with an overlap or at least one optional field are stored,
+/// blunt overlaps (`0M`) are treated like missing ones.
+#[derive(Debug, Default)]
+pub struct GfaTags {
+    segments: HashMap<u64, Vec<GfaTag>>,
+    links: Vec<GfaLink>,
+    link_ids: HashMap<(u64, bool, u64, bool), usize>,
+    paths: HashMap<String, GfaPathTags>,
+}
+
+impl GfaTags {
+    pub fn from_gfa(gfa: &GFA<usize, OptionalFields>) -> Self {
+        let mut tags = Self::default();
+        for segment in &gfa.segments {
+            let segment_tags = convert_tags(&segment.optional);
+            if !segment_tags.is_empty() {
+                tags.segments.insert(segment.name as u64, segment_tags);
+            }
+        }
+        for link in &gfa.links {
+            let overlap = String::from_utf8_lossy(link.overlap.as_ref()).into_owned();
+            let overlap = (!is_blunt(&overlap)).then_some(overlap);
+            let link_tags = convert_tags(&link.optional);
+            if overlap.is_none() && link_tags.is_empty() {
+                continue;
+            }
+            let from = Handle::new(link.from_segment as u64, link.from_orient);
+            let to = Handle::new(link.to_segment as u64, link.to_orient);
+            tags.link_ids.insert(edge_key(from, to), tags.links.len());
+            tags.links.push(GfaLink {
+                from,
+                to,
+                overlap,
+                tags: link_tags,
+            });
+        }
+        for path in &gfa.paths {
+            let overlaps = path
+                .overlaps
+                .iter()
+                .map(|overlap| overlap.as_ref().map_or("*".to_owned(), |c| c.to_string()))
+                .collect::<Vec<_>>();
+            let overlaps =
+                (!overlaps.iter().all(|overlap| is_blunt(overlap))).then(|| overlaps.join(","));
+            let path_tags = convert_tags(&path.optional);
+            if overlaps.is_none() && path_tags.is_empty() {
+                continue;
+            }
+            let name = String::from_utf8_lossy(path.path_name.as_ref()).into_owned();
+            tags.paths.insert(
+                name,
+                GfaPathTags {
+                    overlaps,
+                    tags: path_tags,
+                },
+            );
+        }
+        tags
+    }
+
+    pub fn segment_tags(&self, node_id: u64) -> &[GfaTag] {
+        self.segments.get(&node_id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn tagged_segments(&self) -> Vec<u64> {
+        let mut nodes = self.segments.keys().copied().collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes
+    }
+
+    pub fn links(&self) -> &[GfaLink] {
+        &self.links
+    }
+
+    /// The link between the two handles, in any of its two orientations
+    pub fn link(&self, from: Handle, to: Handle) -> Option<&GfaLink> {
+        self.link_ids
+            .get(&edge_key(from, to))
+            .map(|id| &self.links[*id])
+    }
+
+    pub fn path(&self, path_name: &str) -> Option<&GfaPathTags> {
+        self.paths.get(path_name)
+    }
+
+    pub fn tagged_paths(&self) -> Vec<&str> {
+        let mut paths = self.paths.keys().map(String::as_str).collect::<Vec<_>>();
+        paths.sort_unstable();
+        paths
+    }
+}
+
+fn is_blunt(overlap: &str) -> bool {
+    matches!(overlap, "" | "*" | "0M")
+}
+
+fn convert_tags(fields: &OptionalFields) -> Vec<GfaTag> {
+    fields
+        .iter()
+        .map(|field| {
+            let join = |values: Vec<String>| values.join(",");
+            let value = match &field.value {
+                OptFieldVal::Int(value) => GfaTagValue::Int(*value),
+                OptFieldVal::Float(value) => GfaTagValue::Float(*value),
+                OptFieldVal::A(value) => GfaTagValue::Text(char::from(*value).to_string()),
+                OptFieldVal::Z(value) | OptFieldVal::J(value) => {
+                    GfaTagValue::Text(String::from_utf8_lossy(value.as_ref()).into_owned())
+                }
+                OptFieldVal::H(values) => {
+                    GfaTagValue::Text(values.iter().map(|value| format!("{value:02X}")).collect())
+                }
+                OptFieldVal::BInt(values) => {
+                    GfaTagValue::Text(join(values.iter().map(ToString::to_string).collect()))
+                }
+                OptFieldVal::BFloat(values) => {
+                    GfaTagValue::Text(join(values.iter().map(ToString::to_string).collect()))
+                }
+            };
+            GfaTag {
+                name: String::from_utf8_lossy(&field.tag).into_owned(),
+                value,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_byte_array_tag() {
+        let storage = Storage::from_str("S\t1\tA\tXH:H:0A1B\n").unwrap();
+        assert_eq!(
+            storage.tags.segment_tags(1),
+            [GfaTag {
+                name: "XH".into(),
+                value: GfaTagValue::Text("0A1B".into()),
+            }]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::storage::error::GfaLoaderError;
+use crate::storage::gaf::edge_key;
+use crate::storage::gfa_loader::StorageGfaLoader;
+use crate::storage::Storage;
+use gbwt::{FullPathName, Orientation, GBZ};
+use handlegraph::handle::{Edge, Handle};
+use handlegraph::mutablehandlegraph::AdditiveHandleGraph;
+use handlegraph::packedgraph::PackedGraph;
+use handlegraph::pathhandlegraph::MutableGraphPaths;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Sample name of the generic (non-haplotype) paths in a GBZ file
+const REFERENCE_SAMPLE: &str = "_gbwt_ref";
+
+/// The serialization of a pangenome graph, guessed from the extension of its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// [GFA](https://github.com/GFA-spec/GFA-spec), possibly compressed
+    Gfa,
+    /// The `.gbz` files of [vg](https://github.com/vgteam/vg)
+    Gbz,
+}
+
+impl GraphFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gbz") => Self::Gbz,
+            _ => Self::Gfa,
+        }
+    }
+}
+
+impl StorageGfaLoader {
+    /// Loads the graph file at `path` in the format given by its extension
+    pub fn load_path(&self, path: &Path) -> Result<Storage, GfaLoaderError> {
+        match GraphFormat::from_path(path) {
+            GraphFormat::Gfa => self.load(std::fs::File::open(path)?),
+            GraphFormat::Gbz => self.load_gbz(path),
+        }
+    }
+
+    /// Builds the graph and its paths straight from the GBWT index, without any GFA text
+    fn load_gbz(&self, path: &Path) -> Result<Storage, GfaLoaderError> {
+        let gbz: GBZ = simple_sds::serialize::load_from(path)?;
+        let mut graph = PackedGraph::new();
+        let mut record_count = 0;
+        for node_id in gbz.node_iter() {
+            let sequence = gbz.sequence(node_id).unwrap_or_default();
+            graph.create_handle(sequence, node_id as u64);
+            record_count += 1;
+        }
+        let mut edges = HashSet::new();
+        for node_id in gbz.node_iter() {
+            for orientation in [Orientation::Forward, Orientation::Reverse] {
+                let Some(successors) = gbz.successors(node_id, orientation) else {
+                    continue;
+                };
+                let from = to_handle(node_id, orientation);
+                for (successor, successor_orientation) in successors {
+                    let to = to_handle(successor, successor_orientation);
+                    // Each edge is seen from both of its sides
+                    if edges.insert(edge_key(from, to)) {
+                        graph.create_edge(Edge(from, to));
+                        record_count += 1;
+                    }
+                }
+            }
+        }
+        for path_id in 0..gbz.paths() {
+            let Some(steps) = gbz.path(path_id, Orientation::Forward) else {
+                continue;
+            };
+            let Some(graph_path) = graph.create_path(path_name(&gbz, path_id).as_bytes(), false)
+            else {
+                continue;
+            };
+            for (node_id, orientation) in steps {
+                graph.path_append_step(graph_path, to_handle(node_id, orientation));
+            }
+            record_count += 1;
+        }
+        self.notify_progress(record_count);
+        Ok(Storage::from_graph(graph))
+    }
+}
+
+fn to_handle(node_id: usize, orientation: Orientation) -> Handle {
+    Handle::pack(node_id as u64, orientation == Orientation::Reverse)
+}
+
+/// The PanSN name (`sample#haplotype#contig`) of a GBZ path, generic paths only keep their contig name
+fn path_name(gbz: &GBZ, path_id: usize) -> String {
+    let Some(name) = gbz
+        .metadata()
+        .and_then(|metadata| FullPathName::from_metadata(metadata, path_id))
+    else {
+        return path_id.to_string();
+    };
+    let prefix = if name.sample == REFERENCE_SAMPLE {
+        name.contig
+    } else {
+        format!("{}#{}#{}", name.sample, name.haplotype, name.contig)
+    };
+    if name.fragment == 0 {
+        prefix
+    } else {
+        format!("{prefix}[{}]", name.fragment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::GraphSize;
+    use handlegraph::pathhandlegraph::GraphPathNames;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(name)
+    }
+
+    fn fixture_size() -> GraphSize {
+        StorageGfaLoader::new()
+            .load_path(&fixture("t.gfa"))
+            .unwrap()
+            .snapshot()
+            .graph_size()
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(
+            GraphFormat::from_path(Path::new("hprc.GBZ")),
+            GraphFormat::Gbz
+        );
+        assert_eq!(GraphFormat::from_path(Path::new("x.gfa")), GraphFormat::Gfa);
+        assert_eq!(
+            GraphFormat::from_path(Path::new("x.gfa.gz")),
+            GraphFormat::Gfa
+        );
+        assert_eq!(GraphFormat::from_path(Path::new("-")), GraphFormat::Gfa);
+    }
+
+    /// `t.gbz` is `t.gfa` converted by `vg gbwt -G t.gfa --gbz-format -g t.gbz`
+    #[test]
+    #[ignore = "t.gbz has to be generated with vg next to t.gfa"]
+    fn test_load_gbz() {
+        let storage = StorageGfaLoader::new()
+            .load_path(&fixture("t.gbz"))
+            .unwrap();
+        // GBZ graphs only keep the nodes and edges visited by a path
+        let size = storage.snapshot().graph_size();
+        let expected = fixture_size();
+        assert_eq!(size.paths, expected.paths);
+        assert_eq!(size.steps, expected.steps);
+        assert!(size.nodes <= expected.nodes);
+        assert!(storage.graph.get_path_id(b"x/a").is_some());
+    }
+}
@@ -11,23 +11,32 @@ use crate::storage::binary_encoder::{
     write_pos_quad, write_posg_quad, write_spo_quad, write_spog_quad, write_term, QuadEncoding,
     WRITTEN_TERM_MAX_SIZE,
 };
-pub use crate::storage::error::{CorruptionError, LoaderError, SerializerError, StorageError};
+pub use crate::storage::error::{
+    CorruptionError, GfaLoaderError, GfaParseError, LoaderError, SerializerError, StorageError,
+    ValidationError,
+};
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
 use crate::storage::numeric_encoder::Decoder;
 use crate::storage::numeric_encoder::{insert_term, EncodedQuad, EncodedTerm, StrHash, StrLookup};
 use backend::{ColumnFamily, ColumnFamilyDefinition, Db, Iter};
 
-use gfa::parser::GFAParser;
+use gfa::gfa::GFA;
+use gfa::optfields::OptionalFields;
 use handlegraph::handlegraph::HandleGraph;
 use handlegraph::path_position::PathPositionMap;
-use handlegraph::pathhandlegraph::GraphPaths;
+use handlegraph::pathhandlegraph::{GraphPathNames, GraphPaths};
 use handlegraph::{conversion::from_gfa, packedgraph::PackedGraph};
+use sparopt::{PatternTerm, Statistics};
 use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::collections::HashSet;
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
-use std::collections::{HashMap, HashSet};
+use std::collections::VecDeque;
 use std::error::Error;
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
 use std::mem::{swap, take};
@@ -38,24 +47,143 @@ use std::sync::Mutex;
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
 use std::{io, thread};
 
+use self::gaf::GafIndex;
+pub use self::gfa_loader::StorageGfaLoader;
+use self::gfa_tags::GfaTags;
+use self::occupancy::OccupancyIndex;
+pub use self::occupancy::{PathComparison, SimilarityMatrix};
+pub use self::storage_generator::ScanPartition;
 use self::storage_generator::StorageGenerator;
+pub use self::subgraph::{Subgraph, SubgraphRadius, SubgraphSeed};
+pub use self::validation::ValidationProblem;
+use self::validation::{validate_gfa, validate_graph, without_broken_records};
 
 mod backend;
 mod binary_encoder;
 mod error;
+mod gaf;
+mod gfa_loader;
+mod gfa_tags;
+#[cfg(not(target_family = "wasm"))]
+mod graph_formats;
+mod motif;
 pub mod numeric_encoder;
+mod occupancy;
 pub mod small_string;
 mod storage_generator;
-mod vg_vocab;
+mod subgraph;
+mod validation;
+pub(crate) mod vg_vocab;
 
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
 const DEFAULT_BULK_LOAD_BATCH_SIZE: usize = 1_000_000;
 
+/// The conventions followed by the IRIs of the nodes, paths, steps and positions of the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum RdfProfile {
+    /// IRIs under `https://example.org`, e.g. `https://example.org/node/1`
+    #[default]
+    Oxiqle,
+    /// IRIs identical to the materialised RDF of `vg view --rdf` and the biohackathon datasets,
+    /// e.g. `http://example.org/vg/node/1` or `http://example.org/vg/path/x/step/1`.
+    ///
+    /// The generated triples are the ones of the default profile with these IRIs.
+    Vg,
+}
+
+impl RdfProfile {
+    /// Looks for a profile from its name, `oxiqle` or `vg`
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("oxiqle") {
+            Some(Self::Oxiqle)
+        } else if name.eq_ignore_ascii_case("vg") {
+            Some(Self::Vg)
+        } else {
+            None
+        }
+    }
+
+    /// The prefix of all the IRIs minted for the graph, without trailing slash
+    pub fn base(self) -> &'static str {
+        match self {
+            Self::Oxiqle => "https://example.org",
+            Self::Vg => "http://example.org/vg",
+        }
+    }
+}
+
+/// The size of the graph, as returned by [`Store::statistics`](crate::store::Store::statistics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphStatistics {
+    /// The number of triples of the default graph.
+    pub triples: usize,
+    /// The number of nodes (`vg:Node`).
+    pub nodes: usize,
+    /// The number of paths (`vg:Path`).
+    pub paths: usize,
+    /// The number of edges between nodes (`vg:links` triples).
+    pub edges: usize,
+}
+
+/// The size of the graph known without generating its triples, as returned by [`Store::graph_size`](crate::store::Store::graph_size).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphSize {
+    /// The number of triples of the default graph, if it has already been counted.
+    pub triples: Option<usize>,
+    /// The number of nodes (`vg:Node`).
+    pub nodes: usize,
+    /// The number of paths (`vg:Path`).
+    pub paths: usize,
+    /// The number of edges between nodes (`vg:links` triples).
+    pub edges: usize,
+    /// The number of steps of all the paths (`vg:Step`).
+    pub steps: usize,
+}
+
+/// Number of steps of a scan, or of solutions of an operator, between two checks of the interruption of the evaluation
+pub const INTERRUPTION_CHECK_INTERVAL: u32 = 1024;
+
+/// The deadline and the cancellation flag of a query evaluation, checked by the scans of the graph
+#[derive(Clone, Default)]
+pub struct Interruption {
+    cancelled: Option<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+}
+
+impl Interruption {
+    pub fn new(cancelled: Option<Arc<AtomicBool>>, deadline: Option<Instant>) -> Self {
+        Self {
+            cancelled,
+            deadline,
+        }
+    }
+
+    /// If there is something to check at all
+    pub fn is_enabled(&self) -> bool {
+        self.cancelled.is_some() || self.deadline.is_some()
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.cancelled
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
 /// Low level storage primitives
 #[derive(Clone)]
 pub struct Storage {
     graph: PackedGraph,
     position_map: PathPositionMap,
+    alignments: Option<Arc<GafIndex>>,
+    tags: Arc<GfaTags>,
+    gfa_problems: Arc<Vec<ValidationProblem>>,
+    occupancy: Arc<OnceLock<OccupancyIndex>>,
+    triple_count: Arc<OnceLock<usize>>,
     base: String,
 }
 
@@ -65,49 +193,75 @@ impl Storage {
         Ok(Self {
             graph: graph.clone(),
             position_map: PathPositionMap::index_paths(&graph),
-            base: "https://example.org".to_owned(),
+            alignments: None,
+            tags: Arc::default(),
+            gfa_problems: Arc::default(),
+            occupancy: Arc::default(),
+            triple_count: Arc::default(),
+            base: RdfProfile::default().base().to_owned(),
         })
     }
 
-    pub fn from_str(gfa: &str) -> Result<Self, StorageError> {
-        let gfa_parser = GFAParser::new();
-        let gfa = gfa_parser
-            .parse_lines(gfa.lines().map(|s| s.as_bytes()))
-            .map_err(|err| StorageError::Other(Box::new(err)))?;
-        let graph = from_gfa::<PackedGraph, ()>(&gfa);
-        Ok(Self {
-            graph: graph.clone(),
+    fn from_parsed_gfa(gfa: &GFA<usize, OptionalFields>) -> Self {
+        // The records are checked first, the graph is only built from the consistent ones
+        let gfa_problems = validate_gfa(gfa);
+        let buildable = without_broken_records(gfa);
+        let graph = from_gfa::<PackedGraph, OptionalFields>(buildable.as_ref().unwrap_or(gfa));
+        Self {
             position_map: PathPositionMap::index_paths(&graph),
-            base: "https://example.org".to_owned(),
-        })
+            graph,
+            alignments: None,
+            tags: Arc::new(GfaTags::from_gfa(gfa)),
+            gfa_problems: Arc::new(gfa_problems),
+            occupancy: Arc::default(),
+            triple_count: Arc::default(),
+            base: RdfProfile::default().base().to_owned(),
+        }
+    }
+
+    /// A graph read from a binary format, without optional fields or GFA records to check
+    #[cfg(not(target_family = "wasm"))]
+    fn from_graph(graph: PackedGraph) -> Self {
+        Self {
+            position_map: PathPositionMap::index_paths(&graph),
+            graph,
+            alignments: None,
+            tags: Arc::default(),
+            gfa_problems: Arc::default(),
+            occupancy: Arc::default(),
+            triple_count: Arc::default(),
+            base: RdfProfile::default().base().to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_rdf_profile(mut self, profile: RdfProfile) -> Self {
+        self.base = profile.base().to_owned();
+        self
+    }
+
+    #[must_use]
+    pub fn with_base(mut self, base: &str) -> Self {
+        self.base = base.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn from_str(gfa: &str) -> Result<Self, StorageError> {
+        Ok(StorageGfaLoader::new().load(gfa.as_bytes())?)
     }
 
     #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
     pub fn open(path: &Path) -> Result<Self, StorageError> {
-        let gfa_parser = GFAParser::new();
-        let gfa = gfa_parser
-            .parse_file(path)
-            .map_err(|err| StorageError::Other(Box::new(err)))?;
-        let graph = from_gfa::<PackedGraph, ()>(&gfa);
-        Ok(Self {
-            graph: graph.clone(),
-            position_map: PathPositionMap::index_paths(&graph),
-            base: "https://example.org".to_owned(),
-        })
+        Ok(StorageGfaLoader::new().load_path(path)?)
     }
 
     #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
     pub fn open_secondary(primary_path: &Path) -> Result<Self, StorageError> {
-        let gfa_parser = GFAParser::new();
-        let gfa = gfa_parser
-            .parse_file(primary_path)
-            .map_err(|err| StorageError::Other(Box::new(err)))?;
-        let graph = from_gfa::<PackedGraph, ()>(&gfa);
-        Ok(Self {
-            graph: graph.clone(),
-            position_map: PathPositionMap::index_paths(&graph),
-            base: "https://example.org".to_owned(),
-        })
+        Ok(StorageGfaLoader::new().load_path(primary_path)?)
     }
 
     #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
@@ -115,30 +269,27 @@ impl Storage {
         primary_path: &Path,
         _secondary_path: &Path,
     ) -> Result<Self, StorageError> {
-        let gfa_parser = GFAParser::new();
-        let gfa = gfa_parser
-            .parse_file(primary_path)
-            .map_err(|err| StorageError::Other(Box::new(err)))?;
-        let graph = from_gfa::<PackedGraph, ()>(&gfa);
-        Ok(Self {
-            graph: graph.clone(),
-            position_map: PathPositionMap::index_paths(&graph),
-            base: "https://example.org".to_owned(),
-        })
+        Ok(StorageGfaLoader::new().load_path(primary_path)?)
     }
 
     #[cfg(not(target_family = "wasm"))]
     pub fn open_read_only(path: &Path) -> Result<Self, StorageError> {
-        let gfa_parser = GFAParser::new();
-        let gfa = gfa_parser
-            .parse_file(path)
-            .map_err(|err| StorageError::Other(Box::new(err)))?;
-        let graph = from_gfa::<PackedGraph, ()>(&gfa);
-        Ok(Self {
-            graph: graph.clone(),
-            position_map: PathPositionMap::index_paths(&graph),
-            base: "https://example.org".to_owned(),
-        })
+        Ok(StorageGfaLoader::new().load_path(path)?)
+    }
+
+    /// Indexes the read alignments of a GAF file so that they are exposed next to the graph
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_alignments(&mut self, path: &std::path::Path) -> Result<(), StorageError> {
+        let file = std::fs::File::open(path)?;
+        let index = GafIndex::parse(std::io::BufReader::new(file), &self.graph)?;
+        self.alignments = Some(Arc::new(index));
+        Ok(())
+    }
+
+    /// The index of the nodes visited by each path, built on first use
+    fn occupancy(&self) -> &OccupancyIndex {
+        self.occupancy
+            .get_or_init(|| OccupancyIndex::new(&self.graph))
     }
 
     pub fn snapshot(&self) -> StorageReader {
@@ -177,6 +328,7 @@ impl Storage {
     }
 }
 
+#[derive(Clone)]
 pub struct StorageReader {
     // reader: Reader,
     // storage: Storage,
@@ -189,6 +341,14 @@ impl StorageReader {
             generator: StorageGenerator::new(storage),
         }
     }
+
+    /// Stops the scans of this reader when the evaluation is interrupted
+    #[must_use]
+    pub fn with_interruption(mut self, interruption: Interruption) -> Self {
+        self.generator.interruption = interruption;
+        self
+    }
+
     pub fn len(&self) -> Result<usize, StorageError> {
         let node_triples = self.generator.storage.graph.node_count() * 2;
         let path_triples = self.generator.storage.graph.path_count();
@@ -225,10 +385,92 @@ impl StorageReader {
             .quads_for_pattern(subject, predicate, object, graph_name)
     }
 
+    /// The number of triples, nodes, paths and edges of the graph
+    ///
+    /// Counting the triples generates all of them, so it is only done once per graph.
+    pub fn statistics(&self) -> Result<GraphStatistics, StorageError> {
+        let storage = &self.generator.storage;
+        let triples = if let Some(triples) = storage.triple_count.get() {
+            *triples
+        } else {
+            let mut triples = 0;
+            for quad in self.quads_for_pattern(None, None, None, Some(&EncodedTerm::DefaultGraph)) {
+                quad?;
+                triples += 1;
+            }
+            *storage.triple_count.get_or_init(|| triples)
+        };
+        Ok(GraphStatistics {
+            triples,
+            nodes: storage.graph.node_count(),
+            paths: storage.graph.path_count(),
+            edges: storage.graph.edge_count(),
+        })
+    }
+
+    /// The numbers of nodes, paths, edges and steps, and the number of triples if it has already been counted
+    pub fn graph_size(&self) -> GraphSize {
+        let storage = &self.generator.storage;
+        GraphSize {
+            triples: storage.triple_count.get().copied(),
+            nodes: storage.graph.node_count(),
+            paths: storage.graph.path_count(),
+            edges: storage.graph.edge_count(),
+            steps: self.generator.step_count(),
+        }
+    }
+
+    /// Estimated number of triples of the default graph matching the pattern, without generating them
+    ///
+    /// Patterns the generator can not estimate fall back to the number of triples of the graph,
+    /// the estimated one if they have not been counted yet.
+    pub fn estimate_pattern_count(
+        &self,
+        subject: Option<TermRef<'_>>,
+        predicate: Option<TermRef<'_>>,
+        object: Option<TermRef<'_>>,
+    ) -> Result<usize, StorageError> {
+        let pattern =
+            |term: Option<TermRef<'_>>| term.map_or(PatternTerm::Free, PatternTerm::Constant);
+        if let Some(count) = self.generator.triple_pattern_cardinality(
+            pattern(subject),
+            pattern(predicate),
+            pattern(object),
+        ) {
+            return Ok(count);
+        }
+        if let Some(triples) = self.generator.storage.triple_count.get() {
+            return Ok(*triples);
+        }
+        Ok(self
+            .generator
+            .triple_pattern_cardinality(PatternTerm::Free, PatternTerm::Free, PatternTerm::Free)
+            .unwrap_or_default())
+    }
+
+    /// Splits the graph into `count` partitions that can be scanned in parallel
+    pub fn scan_partitions(&self, count: usize) -> Vec<ScanPartition> {
+        self.generator.scan_partitions(count)
+    }
+
+    pub fn quads_for_pattern_in_partition(
+        &self,
+        subject: Option<&EncodedTerm>,
+        predicate: Option<&EncodedTerm>,
+        object: Option<&EncodedTerm>,
+        graph_name: Option<&EncodedTerm>,
+        partition: &ScanPartition,
+    ) -> ChainedDecodingQuadIterator {
+        let graph_name = graph_name.expect("Graph name is given");
+        self.generator
+            .quads_for_pattern_in_partition(subject, predicate, object, graph_name, partition)
+    }
+
     pub fn quads(&self) -> ChainedDecodingQuadIterator {
         ChainedDecodingQuadIterator::new(DecodingQuadIterator {
             terms: Box::new(Vec::new().into_iter()),
             encoding: QuadEncoding::Spog,
+            interruption: None,
         })
         // ChainedDecodingQuadIterator::pair(self.dspo_quads(&[]), self.gspo_quads(&[]))
     }
@@ -253,17 +495,85 @@ impl StorageReader {
         Ok(true)
     }
 
-    /// Validates that all the storage invariants held in the data
-    #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
-    pub fn validate(&self) -> Result<(), StorageError> {
-        Ok(())
+    /// Compares each of the named paths with each other
+    pub fn path_similarity(&self, paths: &[&str]) -> Result<SimilarityMatrix, StorageError> {
+        let storage = &self.generator.storage;
+        let path_ids = paths
+            .iter()
+            .map(|name| {
+                storage.graph.get_path_id(name.as_bytes()).ok_or_else(|| {
+                    StorageError::Other(format!("The path {name} does not exist").into())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let occupancy = storage.occupancy();
+        let comparisons = path_ids
+            .iter()
+            .flat_map(|first| {
+                path_ids
+                    .iter()
+                    .filter_map(move |second| occupancy.compare(*first, *second))
+            })
+            .collect();
+        Ok(SimilarityMatrix::new(
+            paths.iter().map(|name| (*name).to_owned()).collect(),
+            comparisons,
+        ))
+    }
+
+    /// Compares the paths of two path IRIs
+    pub fn compare_paths(
+        &self,
+        first: &EncodedTerm,
+        second: &EncodedTerm,
+    ) -> Option<PathComparison> {
+        let first = self.generator.path_id(first)?;
+        let second = self.generator.path_id(second)?;
+        self.generator.storage.occupancy().compare(first, second)
+    }
+
+    /// The subgraph around a node or a path interval
+    pub fn subgraph(
+        &self,
+        seed: &SubgraphSeed,
+        radius: SubgraphRadius,
+    ) -> Result<Subgraph, StorageError> {
+        Subgraph::extract(&self.generator.storage, seed, radius)
+    }
+
+    /// The IDs of the nodes within `radius` of the node of a node IRI
+    pub fn neighbourhood(
+        &self,
+        seed: &EncodedTerm,
+        radius: SubgraphRadius,
+    ) -> Option<HashSet<u64>> {
+        let graph = &self.generator.storage.graph;
+        let seed = self
+            .generator
+            .node_id(seed)
+            .filter(|id| graph.has_node(*id))?;
+        Some(subgraph::neighbourhood(graph, &[seed], radius))
+    }
+
+    /// The ID of the node of a node IRI
+    pub fn node_id(&self, node: &EncodedTerm) -> Option<u64> {
+        self.generator.node_id(node)
+    }
+
+    /// The number of nodes only visited by the path of a path IRI
+    pub fn private_nodes(&self, path: &EncodedTerm) -> Option<usize> {
+        let path = self.generator.path_id(path)?;
+        self.generator.storage.occupancy().private_nodes(path)
     }
 
     /// Validates that all the storage invariants held in the data
-    #[cfg(any(target_family = "wasm", not(feature = "rocksdb")))]
-    #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
-    pub fn validate(&self) -> Result<(), StorageError> {
-        Ok(()) // TODO
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let problems = validate_graph(&self.generator.storage);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::Invalid(problems))
+        }
     }
 }
 
@@ -303,8 +613,11 @@ impl Iterator for ChainedDecodingQuadIterator {
 }
 
 pub struct DecodingQuadIterator {
-    terms: Box<dyn Iterator<Item = EncodedQuad>>,
+    /// Sendable, so that a scan might be resumed by another thread, e.g. for the next page of a fragment
+    terms: Box<dyn Iterator<Item = EncodedQuad> + Send>,
     encoding: QuadEncoding,
+    /// Checked once the terms are exhausted, as interrupted scans end early
+    interruption: Option<Interruption>,
 }
 
 impl Iterator for DecodingQuadIterator {
@@ -316,7 +629,13 @@ impl Iterator for DecodingQuadIterator {
         // }
         // let term = self.encoding.decode(self.iter.key()?);
         // self.iter.next();
-        self.terms.next().map(|x| Ok(x))
+        if let Some(quad) = self.terms.next() {
+            return Some(Ok(quad));
+        }
+        self.interruption
+            .take()?
+            .is_interrupted()
+            .then_some(Err(StorageError::Interrupted))
     }
 }
 
@@ -343,6 +662,18 @@ impl StrLookup for StorageReader {
     }
 }
 
+impl Statistics for StorageReader {
+    fn triple_pattern_cardinality(
+        &self,
+        subject: PatternTerm<'_>,
+        predicate: PatternTerm<'_>,
+        object: PatternTerm<'_>,
+    ) -> Option<usize> {
+        self.generator
+            .triple_pattern_cardinality(subject, predicate, object)
+    }
+}
+
 pub struct StorageWriter<'a> {
     buffer: Vec<u8>,
     transaction: Transaction<'a>,
@@ -0,0 +1,254 @@
+use gfa::gfa::Orientation;
+use handlegraph::handle::Handle;
+use handlegraph::handlegraph::{HandleGraph, IntoHandles, IntoSequences};
+use handlegraph::packedgraph::PackedGraph;
+use handlegraph::pathhandlegraph::{
+    GraphPathNames, GraphPaths, GraphPathsSteps, IntoPathIds, PathId,
+};
+
+/// Where a motif occurrence was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MotifTarget {
+    Path(String),
+    Node(u64),
+}
+
+/// An occurrence of a motif on one of the strands of a path or of a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MotifMatch {
+    pub target: MotifTarget,
+    /// 1-based position of the leftmost base of the occurrence on the forward strand
+    pub start: u64,
+    pub is_reverse: bool,
+}
+
+/// A DNA motif using the IUPAC nucleotide codes, e.g. `ACGTTGCA` or `TATAWAW`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Motif {
+    text: String,
+    forward: Vec<u8>,
+    reverse: Vec<u8>,
+}
+
+impl Motif {
+    /// Parses a motif, returns `None` if it is empty or contains non IUPAC characters.
+    pub fn parse(text: &str) -> Option<Self> {
+        let forward = text
+            .bytes()
+            .map(|b| b.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+        if forward.is_empty() || !forward.iter().all(|b| iupac_bases(*b) != 0) {
+            return None;
+        }
+        let reverse = forward
+            .iter()
+            .rev()
+            .map(|b| complement(*b))
+            .collect::<Vec<_>>();
+        Some(Self {
+            text: text.to_owned(),
+            forward,
+            reverse,
+        })
+    }
+
+    /// The motif as it was written, matching is case insensitive
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// All occurrences of the motif on both strands of the node sequences and of the paths.
+    ///
+    /// Path sequences are searched along their steps, so occurrences spanning node boundaries are found
+    /// without building the sequence of the whole path.
+    pub fn find_in_graph(&self, graph: &PackedGraph) -> Vec<MotifMatch> {
+        let mut matches = Vec::new();
+        let mut node_ids = graph
+            .handles()
+            .map(|handle| handle.unpack_number())
+            .collect::<Vec<_>>();
+        node_ids.sort_unstable();
+        for node_id in node_ids {
+            let sequence = graph.sequence_vec(Handle::new(node_id, Orientation::Forward));
+            matches.extend(self.find_in_sequence(&sequence).map(|(start, is_reverse)| {
+                MotifMatch {
+                    target: MotifTarget::Node(node_id),
+                    start,
+                    is_reverse,
+                }
+            }));
+        }
+        for path_id in graph.path_ids() {
+            let Some(path_name) = path_name(graph, path_id) else {
+                continue;
+            };
+            matches.extend(self.find_in_path(graph, path_id).into_iter().map(
+                |(start, is_reverse)| MotifMatch {
+                    target: MotifTarget::Path(path_name.clone()),
+                    start,
+                    is_reverse,
+                },
+            ));
+        }
+        matches
+    }
+
+    /// Checks whether the motif occurs at the given 1-based position of the target
+    pub fn matches_at(&self, graph: &PackedGraph, motif_match: &MotifMatch) -> bool {
+        let pattern = if motif_match.is_reverse {
+            &self.reverse
+        } else {
+            &self.forward
+        };
+        let Some(offset) = usize::try_from(motif_match.start)
+            .ok()
+            .and_then(|start| start.checked_sub(1))
+        else {
+            return false;
+        };
+        let Some(end) = offset.checked_add(pattern.len()) else {
+            return false;
+        };
+        let window = match &motif_match.target {
+            MotifTarget::Node(node_id) => {
+                if !graph.has_node(*node_id) {
+                    return false;
+                }
+                graph
+                    .sequence_vec(Handle::new(*node_id, Orientation::Forward))
+                    .get(offset..end)
+                    .map(<[u8]>::to_vec)
+            }
+            MotifTarget::Path(name) => graph
+                .get_path_id(name.as_bytes())
+                .and_then(|path_id| path_subsequence(graph, path_id, offset, end)),
+        };
+        window.is_some_and(|window| matches_window(pattern, &window))
+    }
+
+    /// Occurrences on a path, searched node after node keeping the end of the previous nodes
+    fn find_in_path(&self, graph: &PackedGraph, path_id: PathId) -> Vec<(u64, bool)> {
+        let overlap = self.forward.len() - 1;
+        let mut found = Vec::new();
+        let mut buffer = Vec::new();
+        // 0-based position on the path of the first base of the buffer
+        let mut buffer_start = 0;
+        let mut step = graph.path_first_step(path_id);
+        while let Some(current) = step {
+            if let Some(handle) = graph.path_handle_at_step(path_id, current) {
+                buffer.extend(graph.sequence_vec(handle));
+                found.extend(
+                    self.find_in_sequence(&buffer)
+                        .map(|(start, is_reverse)| (buffer_start + start, is_reverse)),
+                );
+                // The kept bases are shorter than the motif, they are not reported twice
+                let dropped = buffer.len() - overlap.min(buffer.len());
+                buffer.drain(..dropped);
+                buffer_start += dropped as u64;
+            }
+            step = graph.path_next_step(path_id, current);
+        }
+        found
+    }
+
+    fn find_in_sequence<'a>(
+        &'a self,
+        sequence: &'a [u8],
+    ) -> impl Iterator<Item = (u64, bool)> + 'a {
+        // A reverse complement palindrome is only reported once, on the forward strand
+        let is_palindrome = self.forward == self.reverse;
+        sequence
+            .windows(self.forward.len())
+            .enumerate()
+            .flat_map(move |(offset, window)| {
+                let start = offset as u64 + 1;
+                let forward = matches_window(&self.forward, window).then_some((start, false));
+                let reverse = (!is_palindrome && matches_window(&self.reverse, window))
+                    .then_some((start, true));
+                forward.into_iter().chain(reverse)
+            })
+    }
+}
+
+fn matches_window(pattern: &[u8], window: &[u8]) -> bool {
+    pattern.iter().zip(window).all(|(p, b)| {
+        // Ambiguous bases of the sequence only match if the motif allows all of them
+        let bases = iupac_bases(b.to_ascii_uppercase());
+        bases != 0 && bases & !iupac_bases(*p) == 0
+    })
+}
+
+fn path_name(graph: &PackedGraph, path_id: PathId) -> Option<String> {
+    let name = graph.get_path_name(path_id)?.collect::<Vec<_>>();
+    String::from_utf8(name).ok()
+}
+
+/// The bases from `start` (included) to `end` (excluded) of the path, `None` if the path is shorter
+fn path_subsequence(
+    graph: &PackedGraph,
+    path_id: PathId,
+    start: usize,
+    end: usize,
+) -> Option<Vec<u8>> {
+    let mut sequence = Vec::with_capacity(end - start);
+    let mut node_start = 0;
+    let mut step = graph.path_first_step(path_id);
+    while let Some(current) = step {
+        if node_start >= end {
+            break;
+        }
+        if let Some(handle) = graph.path_handle_at_step(path_id, current) {
+            let node_len = graph.node_len(handle);
+            let node_end = node_start + node_len;
+            if node_end > start {
+                let node_sequence = graph.sequence_vec(handle);
+                let from = start.saturating_sub(node_start);
+                let to = (end - node_start).min(node_len);
+                sequence.extend_from_slice(node_sequence.get(from..to)?);
+            }
+            node_start = node_end;
+        }
+        step = graph.path_next_step(path_id, current);
+    }
+    (sequence.len() == end - start).then_some(sequence)
+}
+
+/// The set of bases of an IUPAC code as a bit mask (A = 1, C = 2, G = 4, T = 8)
+fn iupac_bases(code: u8) -> u8 {
+    match code {
+        b'A' => 0b0001,
+        b'C' => 0b0010,
+        b'G' => 0b0100,
+        b'T' | b'U' => 0b1000,
+        b'R' => 0b0101,
+        b'Y' => 0b1010,
+        b'S' => 0b0110,
+        b'W' => 0b1001,
+        b'K' => 0b1100,
+        b'M' => 0b0011,
+        b'B' => 0b1110,
+        b'D' => 0b1101,
+        b'H' => 0b1011,
+        b'V' => 0b0111,
+        b'N' => 0b1111,
+        _ => 0,
+    }
+}
+
+fn complement(code: u8) -> u8 {
+    match code {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' | b'U' => b'A',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        other => other,
+    }
+}
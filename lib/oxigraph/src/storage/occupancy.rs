@@ -0,0 +1,170 @@
+use handlegraph::handlegraph::HandleGraph;
+use handlegraph::packedgraph::PackedGraph;
+use handlegraph::pathhandlegraph::{GraphPaths, IntoPathIds, PathId};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// The nodes visited by each path, so that paths are compared without joining their steps
+pub struct OccupancyIndex {
+    paths: HashMap<PathId, PathOccupancy>,
+    /// The number of distinct paths visiting each node
+    path_counts: HashMap<u64, usize>,
+    node_lengths: HashMap<u64, u64>,
+}
+
+struct PathOccupancy {
+    /// The distinct nodes of the path, sorted by ID
+    nodes: Vec<u64>,
+    bases: u64,
+}
+
+impl OccupancyIndex {
+    pub fn new(graph: &PackedGraph) -> Self {
+        let mut paths = HashMap::new();
+        let mut path_counts = HashMap::new();
+        let mut node_lengths = HashMap::new();
+        for path_id in graph.path_ids() {
+            let mut nodes = Vec::new();
+            let mut step = graph.path_first_step(path_id);
+            while let Some(current) = step {
+                if let Some(handle) = graph.path_handle_at_step(path_id, current) {
+                    let node = handle.unpack_number();
+                    nodes.push(node);
+                    node_lengths
+                        .entry(node)
+                        .or_insert_with(|| graph.node_len(handle) as u64);
+                }
+                step = graph.path_next_step(path_id, current);
+            }
+            nodes.sort_unstable();
+            nodes.dedup();
+            for node in &nodes {
+                *path_counts.entry(*node).or_insert(0) += 1;
+            }
+            let bases = nodes.iter().map(|node| node_lengths[node]).sum();
+            paths.insert(path_id, PathOccupancy { nodes, bases });
+        }
+        Self {
+            paths,
+            path_counts,
+            node_lengths,
+        }
+    }
+
+    /// The nodes and bases shared by two paths, each node is only counted once per path
+    pub fn compare(&self, first: PathId, second: PathId) -> Option<PathComparison> {
+        let first = self.paths.get(&first)?;
+        let second = self.paths.get(&second)?;
+        let mut shared_nodes = 0;
+        let mut shared_bases = 0;
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (first.nodes.get(i), second.nodes.get(j)) {
+            match a.cmp(b) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    shared_nodes += 1;
+                    shared_bases += self.node_lengths[a];
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        Some(PathComparison {
+            shared_nodes,
+            shared_bases,
+            union_nodes: first.nodes.len() + second.nodes.len() - shared_nodes,
+            union_bases: first.bases + second.bases - shared_bases,
+        })
+    }
+
+    /// The number of nodes visited by `path` and by no other path
+    pub fn private_nodes(&self, path: PathId) -> Option<usize> {
+        Some(
+            self.paths
+                .get(&path)?
+                .nodes
+                .iter()
+                .filter(|node| self.path_counts.get(node) == Some(&1))
+                .count(),
+        )
+    }
+}
+
+/// What two paths have in common, as returned by [`Store::path_similarity`](crate::store::Store::path_similarity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathComparison {
+    /// The number of distinct nodes visited by both paths.
+    pub shared_nodes: usize,
+    /// The total length of the nodes visited by both paths.
+    pub shared_bases: u64,
+    /// The number of distinct nodes visited by any of the paths.
+    pub union_nodes: usize,
+    /// The total length of the nodes visited by any of the paths.
+    pub union_bases: u64,
+}
+
+impl PathComparison {
+    /// The Jaccard similarity of the sequences of the paths, weighted by node length
+    pub fn jaccard(&self) -> f64 {
+        if self.union_bases == 0 {
+            return 0.;
+        }
+        self.shared_bases as f64 / self.union_bases as f64
+    }
+}
+
+/// The pairwise comparisons of a set of paths, see [`Store::path_similarity`](crate::store::Store::path_similarity).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarityMatrix {
+    paths: Vec<String>,
+    comparisons: Vec<PathComparison>,
+}
+
+impl SimilarityMatrix {
+    pub(super) fn new(paths: Vec<String>, comparisons: Vec<PathComparison>) -> Self {
+        Self { paths, comparisons }
+    }
+
+    /// The names of the paths, in the order of the rows and columns
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// The comparison of the paths of the row `i` and the column `j`
+    pub fn get(&self, i: usize, j: usize) -> Option<&PathComparison> {
+        if j >= self.paths.len() {
+            return None;
+        }
+        self.comparisons.get(i * self.paths.len() + j)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use handlegraph::pathhandlegraph::GraphPathNames;
+
+    #[test]
+    fn test_compare_paths() {
+        let storage = Storage::from_str(
+            "S\t1\tCAAATAAG\nS\t2\tA\nS\t3\tGG\nL\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t+\t0M\nP\tx\t1+,2+\t*\nP\ty\t1+,3+,1+\t*\n",
+        )
+        .unwrap();
+        let index = OccupancyIndex::new(&storage.graph);
+        let x = storage.graph.get_path_id(b"x").unwrap();
+        let y = storage.graph.get_path_id(b"y").unwrap();
+        assert_eq!(
+            index.compare(x, y),
+            Some(PathComparison {
+                shared_nodes: 1,
+                shared_bases: 8,
+                union_nodes: 3,
+                union_bases: 11,
+            })
+        );
+        assert_eq!(index.private_nodes(x), Some(1));
+        assert_eq!(index.private_nodes(y), Some(1));
+    }
+}
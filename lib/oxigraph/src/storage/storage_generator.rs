@@ -1,15 +1,18 @@
 #![allow(clippy::same_name_method)]
+use super::gaf::canonical_edge;
+use super::gfa_tags::{GfaTag, GfaTagValue};
+use super::motif::{Motif, MotifMatch, MotifTarget};
 use super::numeric_encoder::{StrHash, StrLookup};
-use super::{ChainedDecodingQuadIterator, Storage};
+use super::{ChainedDecodingQuadIterator, Interruption, Storage};
 use crate::model::vocab::rdf;
-use crate::model::{NamedNodeRef, Term};
+use crate::model::{NamedNodeRef, Term, TermRef};
 use crate::storage::binary_encoder::QuadEncoding;
 pub use crate::storage::error::StorageError;
 use crate::storage::numeric_encoder::Decoder;
 #[cfg(not(target_family = "wasm"))]
 use crate::storage::numeric_encoder::{EncodedQuad, EncodedTerm};
 use crate::storage::vg_vocab::{faldo, vg};
-use crate::storage::DecodingQuadIterator;
+use crate::storage::{DecodingQuadIterator, INTERRUPTION_CHECK_INTERVAL};
 use core::panic;
 use genawaiter::{
     rc::{gen, Gen},
@@ -25,12 +28,15 @@ use handlegraph::pathhandlegraph::{
     GraphPathNames, GraphPaths, GraphPathsSteps, PathId, PathSequences,
 };
 use handlegraph::{
-    handlegraph::IntoHandles, handlegraph::IntoNeighbors, handlegraph::IntoSequences,
+    handlegraph::HandleGraph, handlegraph::IntoHandles, handlegraph::IntoNeighbors,
+    handlegraph::IntoSequences,
 };
 use oxrdf::vocab::rdfs;
 use oxrdf::{Literal, NamedNode};
-use std::rc::Rc;
+use sparopt::PatternTerm;
+use std::ops::Range;
 use std::str;
+use std::sync::Arc;
 use std::vec::IntoIter;
 use time::format_description::well_known::Rfc2822;
 use time::macros::offset;
@@ -40,14 +46,101 @@ const LEN_OF_PATH_AND_SLASH: usize = 5;
 const FIRST_RANK: u64 = 1;
 const FIRST_POS: u64 = 1;
 
+/// A part of the graph that can be scanned independently of the others.
+///
+/// Nodes are split in ranges of IDs and paths are distributed round-robin.
+/// Patterns without subject are restricted to the nodes and paths of the partition,
+/// all the other ones are only answered by the first partition,
+/// so that the union of the answers of all partitions is the answer to the whole pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanPartition {
+    index: usize,
+    count: usize,
+    nodes: Range<u64>,
+}
+
+impl ScanPartition {
+    pub fn is_first(&self) -> bool {
+        self.index == 0
+    }
+
+    fn contains_node(&self, handle: Handle) -> bool {
+        self.nodes.contains(&handle.unpack_number())
+    }
+
+    fn contains_path(&self, path_id: PathId) -> bool {
+        path_id.0 % self.count as u64 == self.index as u64
+    }
+}
+
+#[derive(Clone)]
 pub struct StorageGenerator {
-    pub storage: Rc<Storage>,
+    pub storage: Arc<Storage>,
+    pub interruption: Interruption,
 }
 
 impl StorageGenerator {
     pub fn new(storage: Storage) -> Self {
         Self {
-            storage: Rc::new(storage),
+            storage: Arc::new(storage),
+            interruption: Interruption::default(),
+        }
+    }
+
+    /// Splits the graph into `count` partitions of similar sizes
+    pub fn scan_partitions(&self, count: usize) -> Vec<ScanPartition> {
+        let count = count.max(1);
+        let graph = &self.storage.graph;
+        let min = u64::from(graph.min_node_id());
+        let max = u64::from(graph.max_node_id());
+        let chunk = max.saturating_sub(min) / count as u64 + 1;
+        (0..count)
+            .map(|index| {
+                // The first and last partitions are open so that no node is left out
+                let start = if index == 0 {
+                    0
+                } else {
+                    min.saturating_add(chunk.saturating_mul(index as u64))
+                };
+                let end = if index + 1 == count {
+                    u64::MAX
+                } else {
+                    min.saturating_add(chunk.saturating_mul(index as u64 + 1))
+                };
+                ScanPartition {
+                    index,
+                    count,
+                    nodes: start..end,
+                }
+            })
+            .collect()
+    }
+
+    /// Same as [`quads_for_pattern`](Self::quads_for_pattern) restricted to one partition of the graph
+    pub fn quads_for_pattern_in_partition(
+        &self,
+        subject: Option<&EncodedTerm>,
+        predicate: Option<&EncodedTerm>,
+        object: Option<&EncodedTerm>,
+        graph_name: &EncodedTerm,
+        partition: &ScanPartition,
+    ) -> ChainedDecodingQuadIterator {
+        let iter = GraphIter::new(
+            Arc::clone(&self.storage),
+            subject,
+            predicate,
+            object,
+            graph_name,
+            Some(partition.clone()),
+            self.interruption.clone(),
+        );
+        ChainedDecodingQuadIterator {
+            first: DecodingQuadIterator {
+                terms: Box::new(iter),
+                encoding: QuadEncoding::Spog,
+                interruption: Some(self.interruption.clone()),
+            },
+            second: None,
         }
     }
 
@@ -58,16 +151,197 @@ impl StorageGenerator {
         object: Option<&EncodedTerm>,
         graph_name: &EncodedTerm,
     ) -> ChainedDecodingQuadIterator {
-        let iter = GraphIter::new(self.storage.clone(), subject, predicate, object, graph_name);
+        let iter = GraphIter::new(
+            Arc::clone(&self.storage),
+            subject,
+            predicate,
+            object,
+            graph_name,
+            None,
+            self.interruption.clone(),
+        );
         ChainedDecodingQuadIterator {
             first: DecodingQuadIterator {
                 terms: Box::new(iter),
                 encoding: QuadEncoding::Spog,
+                interruption: Some(self.interruption.clone()),
             },
             second: None,
         }
     }
 
+    /// Estimated number of triples matching the pattern, derived from the size of the graph.
+    ///
+    /// `None` is returned for the predicates and classes unknown to the generator.
+    pub fn triple_pattern_cardinality(
+        &self,
+        subject: PatternTerm<'_>,
+        predicate: PatternTerm<'_>,
+        object: PatternTerm<'_>,
+    ) -> Option<usize> {
+        let graph = &self.storage.graph;
+        let nodes = graph.node_count().max(1);
+        let edges = graph.edge_count();
+        let steps = self.step_count();
+        let subject_bound = subject.is_bound();
+        let object_bound = object.is_bound();
+        let predicate = match predicate {
+            PatternTerm::Constant(TermRef::NamedNode(predicate)) => predicate,
+            PatternTerm::Constant(_) => return Some(0),
+            PatternTerm::Bound | PatternTerm::Free => {
+                // A node has its type, its sequence and two triples per edge,
+                // a step about ten triples and its border five, a path nine.
+                let triples = 2 * nodes + 4 * edges + 15 * steps + 9 * graph.path_count();
+                let subjects = nodes + 2 * steps + graph.path_count();
+                let per_subject = (triples / subjects.max(1)).max(1);
+                return Some(match (subject_bound, object_bound) {
+                    (true, true) => 1,
+                    (true, false) | (false, true) => per_subject,
+                    (false, false) => triples,
+                });
+            }
+        };
+        if predicate == rdf::TYPE {
+            if subject_bound {
+                return Some(1);
+            }
+            let PatternTerm::Constant(TermRef::NamedNode(class)) = object else {
+                return Some(nodes + 2 * graph.path_count() + 2 * steps);
+            };
+            return Some(if class == vg::NODE {
+                nodes
+            } else if class == vg::PATH {
+                graph.path_count()
+            } else if class == vg::STEP {
+                steps
+            } else if class == faldo::REGION {
+                steps + graph.path_count()
+            } else if class == faldo::EXACT_POSITION || class == faldo::POSITION {
+                steps + graph.path_count()
+            } else if class == faldo::FORWARD_STRAND_POSITION
+                || class == faldo::REVERSE_STRAND_POSITION
+            {
+                steps
+            } else {
+                return None;
+            });
+        }
+        if predicate == vg::PATH_PRED {
+            if subject_bound {
+                return Some(1);
+            }
+            return Some(match object {
+                PatternTerm::Constant(TermRef::NamedNode(path)) => self
+                    .path_id_from_iri(path.as_str())
+                    .and_then(|path_id| graph.path_len(path_id))
+                    .unwrap_or(0),
+                PatternTerm::Bound => steps / graph.path_count().max(1),
+                _ => steps,
+            });
+        }
+        if predicate == vg::NODE_PRED || predicate == vg::REVERSE_OF_NODE {
+            return Some(match (subject_bound, object_bound) {
+                (true, _) => 1,
+                (false, true) => (steps / nodes).max(1),
+                (false, false) => steps,
+            });
+        }
+        if predicate == vg::RANK
+            || predicate == vg::POSITION
+            || predicate == vg::STEP_SEQUENCE
+            || predicate == vg::NEXT
+            || predicate == vg::PREVIOUS
+            || predicate == faldo::BEGIN
+            || predicate == faldo::END
+        {
+            return Some(if subject_bound || object_bound {
+                1
+            } else {
+                steps
+            });
+        }
+        if predicate == faldo::POSITION_PRED || predicate == faldo::REFERENCE {
+            return Some(if subject_bound { 1 } else { 2 * steps });
+        }
+        if predicate == vg::LINKS
+            || predicate == vg::LINKS_FORWARD_TO_FORWARD
+            || predicate == vg::LINKS_FORWARD_TO_REVERSE
+            || predicate == vg::LINKS_REVERSE_TO_FORWARD
+            || predicate == vg::LINKS_REVERSE_TO_REVERSE
+        {
+            return Some(match (subject_bound, object_bound) {
+                (true, true) => 1,
+                (true, false) | (false, true) => (edges / nodes).max(1),
+                (false, false) => edges,
+            });
+        }
+        if predicate == rdf::VALUE {
+            return Some(if subject_bound { 1 } else { nodes });
+        }
+        if predicate == rdfs::LABEL
+            || predicate == vg::LENGTH
+            || predicate == vg::STEP_COUNT
+            || predicate == vg::FIRST_STEP
+            || predicate == vg::LAST_STEP
+        {
+            return Some(if subject_bound { 1 } else { graph.path_count() });
+        }
+        if predicate == vg::MOTIF {
+            // Motif matches are only generated for a given motif
+            return Some(if subject_bound {
+                1
+            } else if object_bound {
+                graph.path_count().max(1)
+            } else {
+                0
+            });
+        }
+        if known_tag_predicate_iris().any(|tag| tag == predicate) {
+            let tags = &self.storage.tags;
+            return Some(if subject_bound {
+                1
+            } else {
+                tags.tagged_segments().len() + tags.tagged_paths().len() + tags.links().len()
+            });
+        }
+        None
+    }
+
+    pub fn step_count(&self) -> usize {
+        let graph = &self.storage.graph;
+        graph
+            .path_ids()
+            .filter_map(|path_id| graph.path_len(path_id))
+            .sum()
+    }
+
+    /// The node ID of a node IRI
+    pub fn node_id(&self, term: &EncodedTerm) -> Option<u64> {
+        let EncodedTerm::NamedNode { value, .. } = term else {
+            return None;
+        };
+        value
+            .strip_prefix(self.storage.base.as_str())?
+            .strip_prefix("/node/")?
+            .parse()
+            .ok()
+    }
+
+    /// The path of a path IRI
+    pub fn path_id(&self, term: &EncodedTerm) -> Option<PathId> {
+        match term {
+            EncodedTerm::NamedNode { value, .. } => self.path_id_from_iri(value),
+            _ => None,
+        }
+    }
+
+    fn path_id_from_iri(&self, iri: &str) -> Option<PathId> {
+        let path_name = iri
+            .strip_prefix(self.storage.base.as_str())?
+            .strip_prefix("/path/")?;
+        self.storage.graph.get_path_id(path_name.as_bytes())
+    }
+
     fn print_quad(&self, quad: &EncodedQuad) {
         let sub = match &quad.subject {
             EncodedTerm::NamedNode { iri_id: _, value } => value.clone(),
@@ -138,6 +412,8 @@ enum SubMode {
     StepNode,
     StepFaldoBegin,
     FaldoBeginPosition,
+    StepNeighbour,
+    Overlay,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -146,6 +422,8 @@ enum NodeState {
     Value,
     Edges,
     EdgesDirectional,
+    Coverage,
+    Tags(usize),
     Finished,
 }
 
@@ -158,6 +436,9 @@ enum StepState {
     Rank,
     Position,
     Path,
+    Next,
+    Previous,
+    Sequence,
     Begin,
     End,
     FaldoBegin(FaldoState),
@@ -170,6 +451,7 @@ enum FaldoState {
     Positon,
     Exact,
     Type,
+    Strand,
     Reference,
     Finished,
 }
@@ -177,11 +459,27 @@ enum FaldoState {
 #[derive(Debug)]
 struct StepInfos(StepPtr, u64, u64);
 
+/// Resources of the GAF alignment overlay, of the GFA tags and of motif searches,
+/// expanded lazily into their triples
+#[derive(Debug, Clone)]
+enum OverlayItem {
+    Alignment(usize),
+    NodeCoverage(u64),
+    /// An edge in canonical orientation
+    Edge(Handle, Handle),
+    NodeTags(u64),
+    PathTags(String),
+    MotifMatch(Arc<Motif>, MotifMatch),
+}
+
 struct GraphIter {
-    storage: Rc<Storage>,
+    storage: Arc<Storage>,
     subject: Option<EncodedTerm>,
     predicate: Option<EncodedTerm>,
     object: Option<EncodedTerm>,
+    /// Terms of the patterns answered by a scan, matched against the generated quads
+    subject_filter: Option<EncodedTerm>,
+    object_filter: Option<EncodedTerm>,
     graph_name: EncodedTerm,
     mode: IterMode,
     sub_mode: SubMode,
@@ -191,13 +489,36 @@ struct GraphIter {
     path_ids: IntoIter<PathId>,
     curr_path: Option<PathId>,
     step: Option<StepInfos>,
+    overlay: IntoIter<OverlayItem>,
+    overlay_quads: IntoIter<EncodedQuad>,
+    path_quads: IntoIter<EncodedQuad>,
+    partition: Option<ScanPartition>,
+    interruption: Interruption,
+    /// Steps of the scan since the last check of the interruption
+    steps_since_check: u32,
+    interrupted: bool,
 }
 
 impl Iterator for GraphIter {
     type Item = EncodedQuad;
 
     fn next(&mut self) -> Option<EncodedQuad> {
+        // The shortcuts might generate more than the pattern, e.g. the triples of a neighbouring step
+        loop {
+            let quad = self.next_generated()?;
+            if self.matches_pattern(&quad) {
+                return Some(quad);
+            }
+        }
+    }
+}
+
+impl GraphIter {
+    fn next_generated(&mut self) -> Option<EncodedQuad> {
         //println!("\nnext state: {:?}, {:?}", self.mode, self.sub_mode);
+        if self.is_interrupted() {
+            return None;
+        }
         let triple = match self.mode {
             IterMode::Uninitialized => None,
             IterMode::Invalid => None,
@@ -207,7 +528,7 @@ impl Iterator for GraphIter {
                     if self.subject.is_some() {
                         self.sub_mode = SubMode::SingleNode(NodeState::Type);
                     } else {
-                        self.handles = self.storage.graph.handles().collect::<Vec<_>>().into_iter();
+                        self.handles = self.partition_handles();
                         self.sub_mode = SubMode::AllNodes(NodeState::Type);
                     }
                     None
@@ -223,12 +544,35 @@ impl Iterator for GraphIter {
                     self.set_first_step();
                     self.next()
                 }),
-                SubMode::Step(_) => self.steps(),
+                SubMode::Step(_) => self.steps().or_else(|| {
+                    self.sub_mode = SubMode::Overlay;
+                    if self
+                        .partition
+                        .as_ref()
+                        .map_or(true, ScanPartition::is_first)
+                    {
+                        self.set_overlay();
+                    }
+                    self.next()
+                }),
+                SubMode::Overlay => self.overlay(),
                 _ => None,
             },
             IterMode::Single => match self.sub_mode {
-                SubMode::Start => panic!("SubMode::Start is only valid for ALL queries"),
-                SubMode::Path => self.paths(),
+                SubMode::Start => None,
+                SubMode::Path => self.paths().or_else(|| {
+                    // Paths are regions too, the steps come afterwards
+                    if self.subject.is_none()
+                        && (self.is_step_associated() || self.is_step_associated_type())
+                    {
+                        self.sub_mode = SubMode::Step(StepState::TypeStep);
+                        self.set_paths();
+                        self.set_first_step();
+                        self.next()
+                    } else {
+                        None
+                    }
+                }),
                 SubMode::Step(_) => self.steps(),
                 SubMode::AllNodes(_) => self.nodes(),
                 SubMode::SingleNode(_) => self.nodes(),
@@ -238,7 +582,9 @@ impl Iterator for GraphIter {
                 SubMode::ReverseLink => self.reverse_link(),
                 SubMode::StepNode => self.step_node(),
                 SubMode::StepFaldoBegin => self.step_faldo_begin(),
-                SubMode::FaldoBeginPosition => self.faldo_begin_position(),
+                SubMode::FaldoBeginPosition => self.path_quads.next(),
+                SubMode::StepNeighbour => self.step_neighbour(),
+                SubMode::Overlay => self.overlay(),
             },
         };
         // if triple.is_none() {
@@ -246,21 +592,23 @@ impl Iterator for GraphIter {
         // }
         triple
     }
-}
 
-impl GraphIter {
     pub fn new(
-        storage: Rc<Storage>,
+        storage: Arc<Storage>,
         subject: Option<&EncodedTerm>,
         predicate: Option<&EncodedTerm>,
         object: Option<&EncodedTerm>,
         graph_name: &EncodedTerm,
+        partition: Option<ScanPartition>,
+        interruption: Interruption,
     ) -> Self {
         let mut result = Self {
             storage,
             subject: subject.map(|s| s.to_owned()),
             predicate: predicate.map(|p| p.to_owned()),
             object: object.map(|o| o.to_owned()),
+            subject_filter: None,
+            object_filter: None,
             graph_name: graph_name.to_owned(),
             mode: IterMode::Uninitialized,
             sub_mode: SubMode::Start,
@@ -270,14 +618,33 @@ impl GraphIter {
             path_ids: Vec::new().into_iter(),
             curr_path: None,
             step: None,
+            overlay: Vec::new().into_iter(),
+            overlay_quads: Vec::new().into_iter(),
+            path_quads: Vec::new().into_iter(),
+            partition,
+            interruption,
+            steps_since_check: 0,
+            interrupted: false,
         };
         //result.iter = result.clone().quads_for_pattern();
         result.quads_for_pattern();
+        result.restrict_to_partition();
         // result.print_query(false);
         // println!("Set state: {:?}, {:?}", result.mode, result.sub_mode);
         result
     }
 
+    /// Checks every few steps if the evaluation has been interrupted, the scan then stops for good
+    fn is_interrupted(&mut self) -> bool {
+        if !self.interrupted && self.interruption.is_enabled() {
+            if self.steps_since_check == 0 {
+                self.interrupted = self.interruption.is_interrupted();
+            }
+            self.steps_since_check = (self.steps_since_check + 1) % INTERRUPTION_CHECK_INTERVAL;
+        }
+        self.interrupted
+    }
+
     fn print_query(&self, is_end: bool) {
         let sub = match &self.subject {
             Some(EncodedTerm::NamedNode { iri_id: _, value }) => value.clone(),
@@ -317,6 +684,32 @@ impl GraphIter {
         {
             // println!("OF: blanks");
             self.mode = IterMode::Invalid;
+        } else if self
+            .subject
+            .as_ref()
+            .is_some_and(|s| self.get_term_type(s) == Some(SubjectType::StepBorderIri))
+        {
+            // Borders are shared by two steps, they are generated along the steps of their path
+            self.mode = IterMode::Single;
+            self.subject_filter = self.subject.take();
+            self.set_border_path();
+            self.sub_mode = SubMode::Step(StepState::TypeStep);
+        } else if self.is_alignment_related() {
+            self.mode = IterMode::Single;
+            self.set_overlay();
+            self.sub_mode = SubMode::Overlay;
+        } else if self.is_motif_related() {
+            self.mode = IterMode::Single;
+            self.set_motif_matches();
+            self.sub_mode = SubMode::Overlay;
+        } else if self.is_path_related() {
+            self.mode = IterMode::Single;
+            self.set_paths();
+            self.sub_mode = SubMode::Path;
+        } else if self.is_tag_related() {
+            self.mode = IterMode::Single;
+            self.set_tag_items();
+            self.sub_mode = SubMode::Overlay;
         } else if self.is_vocab(self.predicate.as_ref(), rdf::TYPE) && self.object.is_some() {
             self.mode = IterMode::Single;
             self.type_triples();
@@ -334,15 +727,27 @@ impl GraphIter {
         } else if self.is_vocab(self.predicate.as_ref(), faldo::BEGIN) && self.object.is_some() {
             self.mode = IterMode::Single;
             self.sub_mode = SubMode::FaldoBeginPosition;
-            self.set_paths();
+            self.path_quads = self.faldo_begin_quads().into_iter();
+        } else if self.subject.is_none()
+            && self.object.is_some()
+            && (self.is_vocab(self.predicate.as_ref(), vg::NEXT)
+                || self.is_vocab(self.predicate.as_ref(), vg::PREVIOUS))
+        {
+            self.mode = IterMode::Single;
+            self.sub_mode = SubMode::StepNeighbour;
         } else if self.is_vocab(self.predicate.as_ref(), vg::LINKS) && self.object.is_some() {
+            let Some(node_id) = self
+                .object
+                .as_ref()
+                .filter(|o| self.is_node_iri_in_graph(o))
+                .and_then(|o| self.get_node_id(o))
+            else {
+                self.mode = IterMode::Invalid;
+                return;
+            };
             self.mode = IterMode::Single;
             self.sub_mode = SubMode::ReverseLink;
-            let handle = Handle::new(
-                    self.get_node_id(self.object.as_ref().unwrap())
-                        .expect("Subject is node"),
-                    Orientation::Forward,
-                );
+            let handle = Handle::new(node_id, Orientation::Forward);
             self.curr_handle = Some(handle);
             self.edges = self
                 .storage
@@ -360,7 +765,9 @@ impl GraphIter {
             self.sub_mode = SubMode::AllStepNodes;
         } else if self.is_node_related() {
             // println!("OF: nodes");
-            if self.subject.is_some() {
+            if self.subject.as_ref().is_some_and(|s| !self.is_node_iri_in_graph(s)) {
+                self.mode = IterMode::Invalid;
+            } else if self.subject.is_some() {
                 self.mode = IterMode::Single;
                 self.sub_mode = SubMode::SingleNode(NodeState::Type);
             } else {
@@ -372,23 +779,31 @@ impl GraphIter {
             // println!("OF: steps");
             self.mode = IterMode::Single;
             self.set_paths();
-            self.set_first_step();
-            self.sub_mode = SubMode::Step(StepState::TypeStep);
-        } else if self.is_vocab(self.predicate.as_ref(), rdfs::LABEL) {
-            // println!("OF: rdfs::label");
-            self.mode = IterMode::Single;
-            self.set_paths();
-            self.set_first_step();
-            self.sub_mode = SubMode::Step(StepState::TypeStep);
-        } else if self.subject.is_none() && self.predicate.is_none() && self.object.is_none() {
+            if self.subject.is_none()
+                && (self.is_vocab(self.predicate.as_ref(), faldo::BEGIN)
+                    || self.is_vocab(self.predicate.as_ref(), faldo::END))
+            {
+                self.sub_mode = SubMode::Path;
+            } else {
+                self.set_first_step();
+                self.sub_mode = SubMode::Step(StepState::TypeStep);
+            }
+        } else if self.subject.is_none() && self.predicate.is_none() {
             // println!("OF: triple none");
+            // Any resource might be the object, a bound one is only filtered against
+            self.object_filter = self.object.take();
             self.mode = IterMode::All;
             self.set_nodes();
             self.sub_mode = SubMode::AllNodes(NodeState::Type);
         } else if self.subject.is_some() {
             self.mode = IterMode::Single;
             self.sub_mode = match self.get_term_type(self.subject.as_ref().unwrap()) {
-                Some(SubjectType::NodeIri) => SubMode::SingleNode(NodeState::Type), // TODO: is this correct?
+                Some(SubjectType::NodeIri) => {
+                    if !self.is_node_iri_in_graph(self.subject.as_ref().unwrap()) {
+                        self.mode = IterMode::Invalid;
+                    }
+                    SubMode::SingleNode(NodeState::Type)
+                }
                 Some(SubjectType::PathIri) => {
                     self.set_paths();
                     SubMode::Path
@@ -399,12 +814,19 @@ impl GraphIter {
                     self.set_first_step();
                     SubMode::Step(StepState::TypeStep)
                 }
-                Some(SubjectType::StepBorderIri) => {
-                    self.set_paths();
-                    self.set_first_step();
-                    SubMode::Step(StepState::TypeStep)
+                Some(SubjectType::AlignmentIri | SubjectType::EdgeIri) => {
+                    self.set_overlay();
+                    SubMode::Overlay
+                }
+                Some(SubjectType::MotifMatchIri) => {
+                    self.set_motif_matches();
+                    SubMode::Overlay
+                }
+                Some(SubjectType::StepBorderIri) | None => {
+                    // Not a resource of the graph, the borders have been handled first
+                    self.mode = IterMode::Invalid;
+                    SubMode::Start
                 }
-                None => SubMode::Start,
             };
         } else {
             self.mode = IterMode::Invalid;
@@ -412,24 +834,78 @@ impl GraphIter {
     }
 
     fn set_nodes(&mut self) {
-        self.handles = self.storage.graph.handles().collect::<Vec<_>>().into_iter();
+        self.handles = self.partition_handles();
         self.curr_handle = self.handles.next();
     }
 
+    /// The partition the scans are restricted to, scans only happen if the subject is unbound
+    fn scan_partition(&self) -> Option<ScanPartition> {
+        self.partition.clone().filter(|_| self.subject.is_none())
+    }
+
+    fn partition_handles(&self) -> IntoIter<Handle> {
+        let partition = self.scan_partition();
+        self.storage
+            .graph
+            .handles()
+            .filter(|handle| {
+                partition
+                    .as_ref()
+                    .map_or(true, |p| p.contains_node(*handle))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Patterns that are not answered by scanning nodes or paths are left to the first partition
+    fn restrict_to_partition(&mut self) {
+        let Some(partition) = &self.partition else {
+            return;
+        };
+        let is_scan = self.mode == IterMode::All
+            || (self.subject.is_none()
+                && matches!(
+                    self.sub_mode,
+                    SubMode::AllNodes(_)
+                        | SubMode::Path
+                        | SubMode::Step(_)
+                        | SubMode::PathSteps(_)
+                        | SubMode::AllStepNodes
+                ));
+        if !is_scan && !partition.is_first() {
+            self.mode = IterMode::Invalid;
+        }
+    }
+
+    /// The kind of resource named by the IRI, `None` for the terms not minted for the graph
     fn get_term_type(&self, term: &EncodedTerm) -> Option<SubjectType> {
-        if let EncodedTerm::NamedNode { iri_id: _, value } = term {
-            let mut parts = value.split("/").collect::<Vec<_>>();
-            parts.reverse();
-            if parts[1] == "node" {
-                Some(SubjectType::NodeIri)
-            } else if parts.contains(&"path") && parts[1] == "step" {
+        let local = term
+            .get_named_node_value()?
+            .strip_prefix(self.storage.base.as_str())?;
+        let is_number = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+        if local.starts_with("/alignment/") {
+            Some(SubjectType::AlignmentIri)
+        } else if local.starts_with("/edge/") {
+            Some(SubjectType::EdgeIri)
+        } else if local.starts_with("/motif/") {
+            Some(SubjectType::MotifMatchIri)
+        } else if let Some(id) = local.strip_prefix("/node/") {
+            is_number(id).then_some(SubjectType::NodeIri)
+        } else if let Some(path) = local.strip_prefix("/path/") {
+            // Path names might contain slashes, only the end of the IRI tells the steps and borders apart
+            let mut parts = path.rsplit('/');
+            let last = parts.next();
+            let parent = parts.next();
+            let has_path_name = parts.next().is_some();
+            let is_numbered = has_path_name && last.is_some_and(is_number);
+            if is_numbered && parent == Some("step") {
                 Some(SubjectType::StepIri)
-            } else if parts.contains(&"path") && parts[1] == "position" {
+            } else if is_numbered && parent == Some("position") {
                 Some(SubjectType::StepBorderIri)
-            } else if parts.contains(&"path") {
-                Some(SubjectType::PathIri)
-            } else {
+            } else if path.is_empty() {
                 None
+            } else {
+                Some(SubjectType::PathIri)
             }
         } else {
             None
@@ -438,8 +914,13 @@ impl GraphIter {
 
     fn type_triples(&mut self) {
         let sm = if self.is_vocab(self.object.as_ref(), vg::NODE) {
-            match self.subject {
-                Some(_) => SubMode::SingleNode(NodeState::Type),
+            match self.subject.clone() {
+                Some(subject) => {
+                    if !self.is_node_iri_in_graph(&subject) {
+                        self.mode = IterMode::Invalid;
+                    }
+                    SubMode::SingleNode(NodeState::Type)
+                }
                 None => {
                     self.set_nodes();
                     SubMode::AllNodes(NodeState::Type)
@@ -448,20 +929,29 @@ impl GraphIter {
         } else if self.is_vocab(self.object.as_ref(), vg::PATH) {
             self.set_paths();
             SubMode::Path
+        } else if self.is_vocab(self.object.as_ref(), faldo::REGION) && self.subject.is_none() {
+            self.set_paths();
+            SubMode::Path
         } else if self.is_step_associated_type() {
             self.set_paths();
             self.set_first_step();
             SubMode::Step(StepState::TypeStep)
         } else {
-            panic!("There should always be a type submode!");
+            // No other class has instances in the graph
+            self.mode = IterMode::Invalid;
+            SubMode::Start
         };
         self.sub_mode = sm;
     }
 
     fn path_for_step(&mut self) -> Option<EncodedQuad> {
-        if let Some(StepType::Rank(path_name, _)) = self.get_step_iri_fields() {
+        self.mode = IterMode::Finished;
+        if let Some(StepType::Rank(path_name, rank)) = self.get_step_iri_fields() {
+            let path_id = self.storage.graph.get_path_id(path_name.as_bytes())?;
+            if !self.has_rank(path_id, rank) {
+                return None;
+            }
             let path_node = self.path_to_namednode(&path_name);
-            self.mode = IterMode::Finished;
             Some(EncodedQuad { 
                 subject: self.subject.clone().unwrap(),
                 predicate: vg::PATH_PRED.into(),
@@ -496,16 +986,13 @@ impl GraphIter {
                     graph_name: self.graph_name.clone(),
                 })
             } else {
-                panic!("ps2");
-            }
-        } else {
-            if self.path_ids.next().is_none() {
-                self.step = None;
                 self.mode = IterMode::Finished;
                 None
-            } else {
-                panic!("ps1");
             }
+        } else {
+            self.step = None;
+            self.mode = IterMode::Finished;
+            None
         }
     }
 
@@ -529,7 +1016,7 @@ impl GraphIter {
             if let Some(StepInfos(step, rank, _)) = self.step {
                 let path_name = self.get_path_name(path_id).unwrap();
                 let step_node = self.step_to_namednode(&path_name, rank);
-                let handle = self.storage.graph.path_handle_at_step(path_id, step).expect("All steps should have handles");
+                let handle = self.storage.graph.path_handle_at_step(path_id, step)?;
                 let node = self.handle_to_namednode(handle);
                 if let Some(next_step) = self.storage.graph.path_next_step(path_id, step) {
                     self.step = Some(StepInfos(next_step, rank + 1, 3));
@@ -544,7 +1031,8 @@ impl GraphIter {
                     graph_name: self.graph_name.clone(),
                 })
             } else {
-                panic!("ps2");
+                self.mode = IterMode::Finished;
+                None
             }
         } else {
             self.step = None;
@@ -570,38 +1058,55 @@ impl GraphIter {
                         graph_name: self.graph_name.clone(),
                     })
                 }
-                _ => unimplemented!("step node for position step"),
+                StepType::Position(..) => None,
             }
         } else {
             None
         }
     }
 
-    fn faldo_begin_position(&mut self) -> Option<EncodedQuad> {
-        let pos: i64 = match self.object.clone().unwrap() {
-            EncodedTerm::IntegerLiteral(pos) => pos.into(),
-            EncodedTerm::BigStringLiteral { value_id: _, value } => value.parse().ok()?,
-            EncodedTerm::SmallStringLiteral(pos) => pos.parse().ok()?,
-            _ => return None,
+    /// The path and the step starting at the position of the object
+    fn faldo_begin_quads(&self) -> Vec<EncodedQuad> {
+        let Some(StepType::Position(path_name, pos)) = self
+            .object
+            .as_ref()
+            .and_then(|object| self.get_step_iri_fields_of(object))
+        else {
+            return Vec::new();
         };
-        if let Some(path) = self.curr_path {
-            let mut step_ptr = self.storage.position_map.find_step_at_base(path, pos as usize);
-            while self.curr_path.is_some() {
-                self.curr_path = self.path_ids.next();
-                if step_ptr.is_some() {
-                    let path_name = self.get_path_name(path)?;
-                    let step_node = self.step_to_namednode(&path_name, step_ptr.unwrap().pack());
-                    return Some(EncodedQuad {
-                        subject: step_node.unwrap(),
-                        predicate: self.predicate.clone().unwrap(), 
-                        object: self.object.clone().unwrap(),
-                        graph_name: self.graph_name.clone()
-                    });
-                }
-                step_ptr = self.storage.position_map.find_step_at_base(self.curr_path?, pos as usize);
+        let Some(path_id) = self.storage.graph.get_path_id(path_name.as_bytes()) else {
+            return Vec::new();
+        };
+        let mut subjects = Vec::new();
+        if pos == FIRST_POS && self.has_rank(path_id, FIRST_RANK) {
+            subjects.extend(self.path_to_namednode(&path_name));
+        }
+        if let Some(step_ptr) = self
+            .storage
+            .position_map
+            .find_step_at_base(path_id, pos as usize)
+        {
+            // The step containing the position only starts there if it is its first base
+            if self
+                .storage
+                .position_map
+                .path_step_position(path_id, step_ptr)
+                .is_some_and(|begin| begin as u64 == pos)
+            {
+                subjects.extend(self.step_to_namednode(&path_name, step_ptr.pack()));
             }
         }
-        None
+        subjects
+            .into_iter()
+            .map(|subject| {
+                EncodedQuad::new(
+                    subject,
+                    faldo::BEGIN.into(),
+                    self.object.clone().unwrap(),
+                    self.graph_name.clone(),
+                )
+            })
+            .collect()
     }
 
     fn step_faldo_begin(&mut self) -> Option<EncodedQuad> {
@@ -612,16 +1117,18 @@ impl GraphIter {
                 StepType::Rank(path_name, rank) => {
                     let step_ptr = StepPtr::from_one_based(rank as usize);
                     let path_id = self.storage.graph.get_path_id(path_name.as_bytes())?;
+                    if !self.has_rank(path_id, rank) {
+                        return None;
+                    }
                     let pos = self.storage.position_map.path_step_position(path_id, step_ptr)?;
-                    let position_literal = EncodedTerm::IntegerLiteral((pos as i64).into());
                     Some(EncodedQuad {
                         subject: self.subject.clone().unwrap(),
                         predicate: faldo::BEGIN.into(),
-                        object: position_literal,
+                        object: self.get_faldo_border_namednode(pos as u64, &path_name)?,
                         graph_name: self.graph_name.clone(),
                     })
                 }
-                _ => unimplemented!("step node for position step"),
+                StepType::Position(..) => None,
             }
         } else {
             None
@@ -632,8 +1139,7 @@ impl GraphIter {
         let (triple, sm) = match self.sub_mode {
             SubMode::SingleNode(nts) => {
                 let handle = Handle::new(
-                    self.get_node_id(self.subject.as_ref().unwrap())
-                        .expect("Subject is node"),
+                    self.get_node_id(self.subject.as_ref()?)?,
                     Orientation::Forward,
                 );
                 let (triple, nnts) = self.node_triple(handle, nts);
@@ -696,7 +1202,15 @@ impl GraphIter {
                 if let Some(triple) = self.handle_to_edge_triples(handle, true) {
                     (Some(triple), NodeState::EdgesDirectional)
                 } else {
-                    (None, NodeState::Finished)
+                    (None, NodeState::Coverage)
+                }
+            }
+            NodeState::Coverage => (self.get_node_coverage(handle), NodeState::Tags(0)),
+            NodeState::Tags(index) => {
+                let tags = self.storage.tags.segment_tags(handle.unpack_number());
+                match tags.get(index) {
+                    Some(tag) => (self.get_node_tag(handle, tag), NodeState::Tags(index + 1)),
+                    None => (None, NodeState::Finished),
                 }
             }
             NodeState::Finished => (None, NodeState::Finished),
@@ -741,30 +1255,97 @@ impl GraphIter {
     }
 
     fn paths(&mut self) -> Option<EncodedQuad> {
-        if let Some(path_id) = self.curr_path {
-            self.curr_path = self.path_ids.next();
-            let Some(path_name) = self.storage.graph.get_path_name(path_id) else {
-                return self.paths();
-            };
-            let path_name = path_name.collect::<Vec<_>>();
-            let path_name = str::from_utf8(&path_name).unwrap();
-            let path_node = self.path_to_namednode(path_name);
-            if self.subject.is_none() || path_node == self.subject {
-                if (self.predicate.is_none() || self.is_vocab(self.predicate.as_ref(), rdf::TYPE))
-                    && (self.object.is_none() || self.is_vocab(self.object.as_ref(), vg::PATH))
-                {
-                    return Some(EncodedQuad::new(
-                        path_node.unwrap(),
-                        rdf::TYPE.into(),
-                        vg::PATH.into(),
-                        self.graph_name.clone(),
-                    ));
-                }
+        loop {
+            if let Some(quad) = self.path_quads.next() {
+                return Some(quad);
             }
-            self.paths()
-        } else {
-            None
+            if self.is_interrupted() {
+                return None;
+            }
+            let path_id = self.curr_path?;
+            self.curr_path = self.path_ids.next();
+            let quads = self
+                .path_to_triples(path_id)
+                .into_iter()
+                .filter(|quad| self.matches_pattern(quad))
+                .collect::<Vec<_>>();
+            self.path_quads = quads.into_iter();
+        }
+    }
+
+    fn path_to_triples(&self, path_id: PathId) -> Vec<EncodedQuad> {
+        let Some(path_name) = self.get_path_name(path_id) else {
+            return Vec::new();
+        };
+        let path_node = self.path_to_namednode(&path_name).unwrap();
+        if self.subject.as_ref().is_some_and(|s| s != &path_node) {
+            return Vec::new();
+        }
+        let mut pairs: Vec<(EncodedTerm, EncodedTerm)> = vec![
+            (rdf::TYPE.into(), vg::PATH.into()),
+            (rdf::TYPE.into(), faldo::REGION.into()),
+            (
+                rdfs::LABEL.into(),
+                Literal::new_simple_literal(&path_name).as_ref().into(),
+            ),
+        ];
+        let step_count = self.storage.graph.path_len(path_id).unwrap_or(0);
+        pairs.push((
+            vg::STEP_COUNT.into(),
+            EncodedTerm::IntegerLiteral((step_count as i64).into()),
+        ));
+        if let Some(length) = self.path_length(path_id, step_count) {
+            pairs.push((
+                vg::LENGTH.into(),
+                EncodedTerm::IntegerLiteral((length as i64).into()),
+            ));
+            pairs.push((
+                vg::FIRST_STEP.into(),
+                self.step_to_namednode(&path_name, FIRST_RANK).unwrap(),
+            ));
+            pairs.push((
+                vg::LAST_STEP.into(),
+                self.step_to_namednode(&path_name, step_count as u64)
+                    .unwrap(),
+            ));
+            pairs.push((
+                faldo::BEGIN.into(),
+                self.get_faldo_border_namednode(FIRST_POS, &path_name)
+                    .unwrap(),
+            ));
+            pairs.push((
+                faldo::END.into(),
+                self.get_faldo_border_namednode(FIRST_POS + length, &path_name)
+                    .unwrap(),
+            ));
+        }
+        pairs.extend(self.path_tag_pairs(&path_name));
+        pairs
+            .into_iter()
+            .map(|(predicate, object)| {
+                EncodedQuad::new(
+                    path_node.clone(),
+                    predicate,
+                    object,
+                    self.graph_name.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Length of the path in bases, `None` for paths without steps
+    fn path_length(&self, path_id: PathId, step_count: usize) -> Option<u64> {
+        if step_count == 0 {
+            return None;
         }
+        // Steps are stored in path order, the last one has the highest rank
+        let last_step = StepPtr::from_one_based(step_count);
+        let handle = self.storage.graph.path_handle_at_step(path_id, last_step)?;
+        let position = self
+            .storage
+            .position_map
+            .path_step_position(path_id, last_step)?;
+        Some(position as u64 + self.storage.graph.node_len(handle) as u64 - FIRST_POS)
     }
 
     fn set_paths(&mut self) {
@@ -783,6 +1364,37 @@ impl GraphIter {
                 .into_iter();
             self.curr_path = self.path_ids.next();
         }
+        if let Some(partition) = self.scan_partition() {
+            self.path_ids = self
+                .curr_path
+                .into_iter()
+                .chain(self.path_ids.by_ref())
+                .filter(|path_id| partition.contains_path(*path_id))
+                .collect::<Vec<_>>()
+                .into_iter();
+            self.curr_path = self.path_ids.next();
+        }
+    }
+
+    fn set_border_path(&mut self) {
+        let path_id = match self
+            .subject_filter
+            .as_ref()
+            .and_then(|border| self.get_step_iri_fields_of(border))
+        {
+            Some(StepType::Position(path_name, _)) => {
+                self.storage.graph.get_path_id(path_name.as_bytes())
+            }
+            _ => None,
+        };
+        let partition = self.scan_partition();
+        self.curr_path = path_id.filter(|path_id| {
+            partition
+                .as_ref()
+                .map_or(true, |p| p.contains_path(*path_id))
+        });
+        self.path_ids = Vec::new().into_iter();
+        self.set_first_step();
     }
 
     fn get_path_id_from_iri(&self, term: Option<&EncodedTerm>) -> Option<PathId> {
@@ -804,18 +1416,27 @@ impl GraphIter {
     fn set_first_step(&mut self) {
         if self.subject.is_none() {
             if let Some(path_id) = self.curr_path {
-                let step = self
+                self.step = self
                     .storage
                     .graph
                     .path_first_step(path_id)
-                    .expect("Every path should have at least one step");
-                self.step = Some(StepInfos(step, FIRST_RANK, FIRST_POS));
+                    .map(|step| StepInfos(step, FIRST_RANK, FIRST_POS));
             }
         } else if let Some(step_type) = self.get_step_iri_fields() {
             match step_type {
                 StepType::Rank(path_name, target_rank) => {
-                    if let Some(id) = self.storage.graph.get_path_id(path_name.as_bytes()) {
-                        if self.is_vocab(self.predicate.as_ref(), vg::NODE_PRED) {
+                    // Steps out of the path are not in the graph
+                    let path_id = self
+                        .storage
+                        .graph
+                        .get_path_id(path_name.as_bytes())
+                        .filter(|id| self.has_rank(*id, target_rank));
+                    if let Some(id) = path_id {
+                        // The position is only needed for the position and faldo triples
+                        if self.is_vocab(self.predicate.as_ref(), vg::NODE_PRED)
+                            || self.is_vocab(self.predicate.as_ref(), vg::NEXT)
+                            || self.is_vocab(self.predicate.as_ref(), vg::PREVIOUS)
+                        {
                             let step = StepPtr::from_one_based(target_rank as usize);
                             self.step = Some(StepInfos(step, target_rank, 3));
                         } else {
@@ -848,6 +1469,13 @@ impl GraphIter {
         }
     }
 
+    fn has_rank(&self, path_id: PathId, rank: u64) -> bool {
+        self.storage
+            .graph
+            .path_len(path_id)
+            .is_some_and(|len| (FIRST_RANK..=len as u64).contains(&rank))
+    }
+
     fn step_no_subject(&mut self) -> Option<EncodedQuad> {
         // print!(",");
         // if self.mode == IterMode::Single && self.sub_mode == SubMode::Step(StepState::NodeReverse) {
@@ -879,6 +1507,9 @@ impl GraphIter {
             // println!("SF: none self.subject");
             let mut triple = self.step_no_subject();
             while triple.is_none() {
+                if self.is_interrupted() {
+                    return None;
+                }
                 if let Some(path_id) = self.curr_path {
                     if let Some(StepInfos(step, rank, position)) = self.step {
                         let node_handle = self
@@ -932,11 +1563,8 @@ impl GraphIter {
                                 // println!("At path: {:?}, {}, {}", step, rank, position);
                             }
                             // print!("620 [ ");
-                            let node_handle = self
-                                .storage
-                                .graph
-                                .path_handle_at_step(path_id, step)
-                                .expect("There should always be a node to every step");
+                            let node_handle =
+                                self.storage.graph.path_handle_at_step(path_id, step)?;
                             // println!("] 620");
                             let triple = self.step_handle_to_triples(
                                 &path_name,
@@ -987,7 +1615,16 @@ impl GraphIter {
     }
 
     fn get_step_iri_fields(&self) -> Option<StepType> {
-        let term = self.subject.as_ref()?;
+        self.get_step_iri_fields_of(self.subject.as_ref()?)
+    }
+
+    fn get_step_iri_fields_of(&self, term: &EncodedTerm) -> Option<StepType> {
+        if !matches!(
+            self.get_term_type(term),
+            Some(SubjectType::StepIri | SubjectType::StepBorderIri)
+        ) {
+            return None;
+        }
         if let EncodedTerm::NamedNode { iri_id: _, value } = term {
             let mut parts = value.split("/").collect::<Vec<_>>();
             parts.reverse();
@@ -1081,26 +1718,39 @@ impl GraphIter {
                             })
                     }
                     StepState::Path => {
-                        self.sub_mode = SubMode::Step(StepState::Begin);
+                        self.sub_mode = SubMode::Step(StepState::Next);
                         let path_iri = self.path_to_namednode(path_name).unwrap();
                         self.get_step_path(step_iri, path_iri).or_else(|| {
                             self.step_handle_to_triples(path_name, node_handle, rank, position)
                         })
                     }
-                    StepState::Begin => {
-                        self.sub_mode = SubMode::Step(StepState::End);
-                        self.get_step_terminal(
-                            step_iri,
-                            position_literal,
-                            node_len,
-                            false,
-                            path_name,
-                            position,
-                        )
-                        .or_else(|| {
+                    StepState::Next => {
+                        self.sub_mode = SubMode::Step(StepState::Previous);
+                        self.get_step_neighbour(step_iri, path_name, rank, false)
+                            .or_else(|| {
+                                self.step_handle_to_triples(path_name, node_handle, rank, position)
+                            })
+                    }
+                    StepState::Previous => {
+                        self.sub_mode = SubMode::Step(StepState::Sequence);
+                        self.get_step_neighbour(step_iri, path_name, rank, true)
+                            .or_else(|| {
+                                self.step_handle_to_triples(path_name, node_handle, rank, position)
+                            })
+                    }
+                    StepState::Sequence => {
+                        self.sub_mode = SubMode::Step(StepState::Begin);
+                        self.get_step_sequence(step_iri, node_handle).or_else(|| {
                             self.step_handle_to_triples(path_name, node_handle, rank, position)
                         })
                     }
+                    StepState::Begin => {
+                        self.sub_mode = SubMode::Step(StepState::End);
+                        self.get_step_terminal(step_iri, node_len, false, path_name, position)
+                            .or_else(|| {
+                                self.step_handle_to_triples(path_name, node_handle, rank, position)
+                            })
+                    }
                     StepState::End => {
                         if self.subject.is_none() && self.needs_faldo_triple() {
                             self.sub_mode =
@@ -1108,25 +1758,26 @@ impl GraphIter {
                         } else {
                             self.sub_mode = SubMode::Step(StepState::Finished);
                         }
-                        self.get_step_terminal(
-                            step_iri,
-                            position_literal,
-                            node_len,
-                            true,
-                            path_name,
-                            position,
-                        )
-                        .or_else(|| {
-                            self.step_handle_to_triples(path_name, node_handle, rank, position)
-                        })
+                        self.get_step_terminal(step_iri, node_len, true, path_name, position)
+                            .or_else(|| {
+                                self.step_handle_to_triples(path_name, node_handle, rank, position)
+                            })
                     }
                     StepState::FaldoBegin(_) => {
                         let subject = self
                             .get_faldo_border_namednode(position, path_name)
                             .unwrap();
                         let path_iri = self.path_to_namednode(path_name).unwrap();
-                        self.faldo_for_step(position, path_iri, &subject)
+                        self.faldo_for_step(position, path_iri, &subject, node_handle.is_reverse())
                             .or_else(|| {
+                                // The end of a step is the begin of the next one, only the end of the path is left
+                                let is_last = self
+                                    .curr_path
+                                    .map_or(true, |path_id| !self.has_rank(path_id, rank + 1));
+                                if !is_last {
+                                    self.sub_mode = SubMode::Step(StepState::Finished);
+                                    return None;
+                                }
                                 self.sub_mode =
                                     SubMode::Step(StepState::FaldoEnd(FaldoState::Positon));
                                 self.step_handle_to_triples(path_name, node_handle, rank, position)
@@ -1137,7 +1788,8 @@ impl GraphIter {
                             .get_faldo_border_namednode(position + node_len, path_name)
                             .unwrap();
                         let path_iri = self.path_to_namednode(path_name).unwrap();
-                        self.faldo_for_step(position + node_len, path_iri, &subject)
+                        let is_reverse = node_handle.is_reverse();
+                        self.faldo_for_step(position + node_len, path_iri, &subject, is_reverse)
                             .or_else(|| {
                                 self.sub_mode = SubMode::Step(StepState::Finished);
                                 None
@@ -1260,16 +1912,96 @@ impl GraphIter {
         None
     }
 
-    fn get_step_terminal(
+    fn get_step_neighbour(
         &self,
         step_iri: EncodedTerm,
-        position_literal: EncodedTerm,
-        node_len: u64,
-        is_end: bool,
         path_name: &str,
-        position: u64,
+        rank: u64,
+        is_previous: bool,
     ) -> Option<EncodedQuad> {
-        let pred = match is_end {
+        let pred = if is_previous { vg::PREVIOUS } else { vg::NEXT };
+        if self.predicate.is_some() && !self.is_vocab(self.predicate.as_ref(), pred) {
+            return None;
+        }
+        let path_id = self.storage.graph.get_path_id(path_name.as_bytes())?;
+        let step = StepPtr::from_one_based(rank as usize);
+        let neighbour_rank = if is_previous {
+            self.storage.graph.path_prev_step(path_id, step)?;
+            rank - 1
+        } else {
+            self.storage.graph.path_next_step(path_id, step)?;
+            rank + 1
+        };
+        let neighbour_iri = self.step_to_namednode(path_name, neighbour_rank)?;
+        if self.object.is_some() && self.object.as_ref() != Some(&neighbour_iri) {
+            return None;
+        }
+        Some(EncodedQuad::new(
+            step_iri,
+            pred.into(),
+            neighbour_iri,
+            self.graph_name.clone(),
+        ))
+    }
+
+    /// Answers `?s vg:next <step>` and `?s vg:previous <step>` without going through the steps
+    fn step_neighbour(&mut self) -> Option<EncodedQuad> {
+        self.mode = IterMode::Finished;
+        let object = self.object.clone()?;
+        let Some(StepType::Rank(path_name, rank)) = self.get_step_iri_fields_of(&object) else {
+            return None;
+        };
+        let path_id = self.storage.graph.get_path_id(path_name.as_bytes())?;
+        let step_count = self.storage.graph.path_len(path_id)? as u64;
+        if rank < FIRST_RANK || rank > step_count {
+            return None;
+        }
+        let step = StepPtr::from_one_based(rank as usize);
+        // The subject of `vg:next` is the step before the object and vice versa
+        let is_previous = self.is_vocab(self.predicate.as_ref(), vg::PREVIOUS);
+        let subject_rank = if is_previous {
+            self.storage.graph.path_next_step(path_id, step)?;
+            rank + 1
+        } else {
+            self.storage.graph.path_prev_step(path_id, step)?;
+            rank - 1
+        };
+        Some(EncodedQuad::new(
+            self.step_to_namednode(&path_name, subject_rank)?,
+            self.predicate.clone()?,
+            object,
+            self.graph_name.clone(),
+        ))
+    }
+
+    /// The sequence as read along the step, reverse complemented for reverse handles
+    fn get_step_sequence(&self, step_iri: EncodedTerm, node_handle: Handle) -> Option<EncodedQuad> {
+        if self.predicate.is_some() && !self.is_vocab(self.predicate.as_ref(), vg::STEP_SEQUENCE) {
+            return None;
+        }
+        let sequence = self.storage.graph.sequence_vec(node_handle);
+        let sequence = str::from_utf8(&sequence).ok()?;
+        let sequence_literal: EncodedTerm = Literal::new_simple_literal(sequence).as_ref().into();
+        if self.object.is_some() && self.object.as_ref() != Some(&sequence_literal) {
+            return None;
+        }
+        Some(EncodedQuad::new(
+            step_iri,
+            vg::STEP_SEQUENCE.into(),
+            sequence_literal,
+            self.graph_name.clone(),
+        ))
+    }
+
+    fn get_step_terminal(
+        &self,
+        step_iri: EncodedTerm,
+        node_len: u64,
+        is_end: bool,
+        path_name: &str,
+        position: u64,
+    ) -> Option<EncodedQuad> {
+        let pred = match is_end {
             false => faldo::BEGIN,
             true => faldo::END,
         };
@@ -1278,12 +2010,13 @@ impl GraphIter {
             true => position + node_len,
         };
         if self.predicate.is_none() || self.is_vocab(self.predicate.as_ref(), pred) {
-            if self.object.is_none() || self.object.as_ref().unwrap().clone() == position_literal {
+            let border = self.get_faldo_border_namednode(pos, path_name)?;
+            if self.object.is_none() || self.object.as_ref() == Some(&border) {
                 // println!("SH: begin self.predicate");
                 return Some(EncodedQuad::new(
                     step_iri.clone(),
                     pred.into(),
-                    self.get_faldo_border_namednode(pos, path_name).unwrap(), // FIX
+                    border,
                     self.graph_name.clone(),
                 ));
             }
@@ -1308,6 +2041,7 @@ impl GraphIter {
         position: u64,
         path_iri: EncodedTerm,
         subject: &EncodedTerm,
+        is_reverse: bool,
     ) -> Option<EncodedQuad> {
         if let SubMode::Step(faldo) = self.sub_mode {
             let (triple, new_fst) = match faldo {
@@ -1316,7 +2050,11 @@ impl GraphIter {
                         (self.get_faldo_pos(subject, position), FaldoState::Exact)
                     }
                     FaldoState::Exact => (self.get_faldo_exact(subject), FaldoState::Type),
-                    FaldoState::Type => (self.get_faldo_type(subject), FaldoState::Reference),
+                    FaldoState::Type => (self.get_faldo_type(subject), FaldoState::Strand),
+                    FaldoState::Strand => (
+                        self.get_faldo_strand(subject, is_reverse),
+                        FaldoState::Reference,
+                    ),
                     FaldoState::Reference => (
                         self.get_faldo_reference(subject, path_iri.clone()),
                         FaldoState::Finished,
@@ -1336,7 +2074,7 @@ impl GraphIter {
             };
             triple.or_else(|| {
                 if new_fst != FaldoState::Finished {
-                    self.faldo_for_step(position, path_iri, subject)
+                    self.faldo_for_step(position, path_iri, subject, is_reverse)
                 } else {
                     None
                 }
@@ -1395,6 +2133,28 @@ impl GraphIter {
         }
     }
 
+    /// Borders are typed with the strand of the step they begin,
+    /// the end of a path with the strand of its last step.
+    fn get_faldo_strand(&self, subject: &EncodedTerm, is_reverse: bool) -> Option<EncodedQuad> {
+        let class = if is_reverse {
+            faldo::REVERSE_STRAND_POSITION
+        } else {
+            faldo::FORWARD_STRAND_POSITION
+        };
+        if (self.predicate.is_none() || self.is_vocab(self.predicate.as_ref(), rdf::TYPE))
+            && (self.object.is_none() || self.is_vocab(self.object.as_ref(), class))
+        {
+            Some(EncodedQuad::new(
+                subject.clone(),
+                rdf::TYPE.into(),
+                class.into(),
+                self.graph_name.clone(),
+            ))
+        } else {
+            None
+        }
+    }
+
     fn get_faldo_reference(
         &self,
         subject: &EncodedTerm,
@@ -1446,12 +2206,8 @@ impl GraphIter {
     ) -> Option<EncodedQuad> {
         if self.predicate.is_none() || self.is_node_related() {
             let neighbor = self.edges.next()?;
-            if self.object.is_none()
-                || self
-                    .get_node_id(self.object.as_ref().unwrap())
-                    .expect("Object has node id")
-                    == neighbor.unpack_number()
-            {
+            let object_id = self.object.as_ref().map(|o| self.get_node_id(o));
+            if object_id.map_or(true, |id| id == Some(neighbor.unpack_number())) {
                 Some(self.handles_to_edge_triple(handle, neighbor, is_directional))
             } else {
                 self.handle_to_edge_triples(handle, is_directional)
@@ -1547,6 +2303,513 @@ impl GraphIter {
         .into_iter()
     }
 
+    fn get_node_tag(&self, handle: Handle, tag: &GfaTag) -> Option<EncodedQuad> {
+        let (predicate, object) = self.tag_to_pair(tag);
+        if self.predicate.as_ref().is_some_and(|p| p != &predicate)
+            || self.object.as_ref().is_some_and(|o| o != &object)
+        {
+            return None;
+        }
+        Some(EncodedQuad::new(
+            self.handle_to_namednode(handle)?,
+            predicate,
+            object,
+            self.graph_name.clone(),
+        ))
+    }
+
+    fn path_tag_pairs(&self, path_name: &str) -> Vec<(EncodedTerm, EncodedTerm)> {
+        let Some(path_tags) = self.storage.tags.path(path_name) else {
+            return Vec::new();
+        };
+        let mut pairs = Vec::new();
+        if let Some(overlaps) = &path_tags.overlaps {
+            pairs.push((
+                vg::OVERLAP.into(),
+                Literal::new_simple_literal(overlaps).as_ref().into(),
+            ));
+        }
+        pairs.extend(path_tags.tags.iter().map(|tag| self.tag_to_pair(tag)));
+        pairs
+    }
+
+    /// Known tags get their own predicate, the other ones are exposed as `{base}/tag/{name}`
+    fn tag_to_pair(&self, tag: &GfaTag) -> (EncodedTerm, EncodedTerm) {
+        let predicate = match known_tag_predicate(&tag.name) {
+            Some(predicate) => predicate.into(),
+            None => {
+                let text = format!("{}/tag/{}", self.storage.base, tag.name);
+                EncodedTerm::NamedNode {
+                    iri_id: StrHash::new(&text),
+                    value: text,
+                }
+            }
+        };
+        let object = match &tag.value {
+            GfaTagValue::Int(value) => EncodedTerm::IntegerLiteral((*value).into()),
+            GfaTagValue::Float(value) => Literal::from(*value).as_ref().into(),
+            GfaTagValue::Text(value) => Literal::new_simple_literal(value).as_ref().into(),
+        };
+        (predicate, object)
+    }
+
+    fn get_node_coverage(&self, handle: Handle) -> Option<EncodedQuad> {
+        let alignments = self.storage.alignments.as_ref()?;
+        if self.predicate.is_none() || self.is_vocab(self.predicate.as_ref(), vg::COVERAGE) {
+            let coverage = alignments.node_coverage(handle.unpack_number());
+            let coverage_literal = EncodedTerm::IntegerLiteral((coverage as i64).into());
+            if self.object.is_none() || self.object.as_ref() == Some(&coverage_literal) {
+                return Some(EncodedQuad::new(
+                    self.handle_to_namednode(handle).unwrap(),
+                    vg::COVERAGE.into(),
+                    coverage_literal,
+                    self.graph_name.clone(),
+                ));
+            }
+        }
+        None
+    }
+
+    fn set_overlay(&mut self) {
+        let alignments = self.storage.alignments.clone();
+        let mut items = Vec::new();
+        if let Some(subject) = self.subject.as_ref() {
+            match self.get_term_type(subject) {
+                Some(SubjectType::AlignmentIri) => {
+                    if let Some(id) = self.get_alignment_id(subject) {
+                        if alignments.as_ref().is_some_and(|a| id < a.len()) {
+                            items.push(OverlayItem::Alignment(id));
+                        }
+                    }
+                }
+                Some(SubjectType::EdgeIri) => {
+                    if let Some((from, to)) = self.get_edge_handles(subject) {
+                        let in_alignments = alignments
+                            .as_ref()
+                            .is_some_and(|a| a.edge_id(from, to).is_some());
+                        if in_alignments || self.storage.tags.link(from, to).is_some() {
+                            let (from, to) = canonical_edge(from, to);
+                            items.push(OverlayItem::Edge(from, to));
+                        }
+                    }
+                }
+                Some(SubjectType::NodeIri) => {
+                    if let Some(id) = self.get_node_id(subject) {
+                        if self.storage.graph.has_node(id) {
+                            items.push(OverlayItem::NodeCoverage(id));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        } else {
+            let wants_types = self.is_vocab(self.predicate.as_ref(), rdf::TYPE);
+            let wants_alignments = self.predicate.is_none()
+                || (wants_types && self.is_vocab(self.object.as_ref(), vg::ALIGNMENT))
+                || [
+                    vg::READ_NAME,
+                    vg::READ_LENGTH,
+                    vg::MAPPING_QUALITY,
+                    vg::ALIGNED_TO,
+                    vg::WALK,
+                ]
+                .into_iter()
+                .any(|x| self.is_vocab(self.predicate.as_ref(), x));
+            let wants_edges = self.predicate.is_none()
+                || (wants_types && self.is_vocab(self.object.as_ref(), vg::EDGE))
+                || [vg::COVERAGE, vg::FROM_NODE, vg::TO_NODE]
+                    .into_iter()
+                    .any(|x| self.is_vocab(self.predicate.as_ref(), x));
+            // Without predicate, the node coverage is already part of the node triples
+            let wants_nodes = self.is_vocab(self.predicate.as_ref(), vg::COVERAGE);
+            if let Some(alignments) = alignments.as_ref().filter(|_| wants_alignments) {
+                let on_node = self
+                    .object
+                    .as_ref()
+                    .filter(|_| self.is_vocab(self.predicate.as_ref(), vg::ALIGNED_TO))
+                    .and_then(|o| self.get_node_id(o));
+                if let Some(node_id) = on_node {
+                    items.extend(
+                        alignments
+                            .alignments_on_node(node_id)
+                            .iter()
+                            .map(|id| OverlayItem::Alignment(*id)),
+                    );
+                } else {
+                    items.extend((0..alignments.len()).map(OverlayItem::Alignment));
+                }
+            }
+            if wants_nodes && alignments.is_some() {
+                items.extend(
+                    self.storage
+                        .graph
+                        .handles()
+                        .map(|handle| OverlayItem::NodeCoverage(handle.unpack_number())),
+                );
+            }
+            if wants_edges {
+                items.extend(self.edge_items());
+            }
+        }
+        self.overlay = items.into_iter();
+    }
+
+    /// Edges traversed by alignments or carrying GFA link fields
+    fn edge_items(&self) -> Vec<OverlayItem> {
+        let alignments = self.storage.alignments.as_ref();
+        let mut items = Vec::new();
+        if let Some(alignments) = alignments {
+            items.extend(
+                (0..alignments.edge_count())
+                    .filter_map(|id| alignments.edge(id))
+                    .map(|(from, to, _)| OverlayItem::Edge(from, to)),
+            );
+        }
+        items.extend(
+            self.storage
+                .tags
+                .links()
+                .iter()
+                .filter(|link| alignments.map_or(true, |a| a.edge_id(link.from, link.to).is_none()))
+                .map(|link| {
+                    let (from, to) = canonical_edge(link.from, link.to);
+                    OverlayItem::Edge(from, to)
+                }),
+        );
+        items
+    }
+
+    fn set_tag_items(&mut self) {
+        let tags = self.storage.tags.clone();
+        let mut items = Vec::new();
+        if let Some(subject) = self.subject.as_ref() {
+            // Paths and edges are handled with the rest of their triples
+            if let Some(node_id) = self.get_node_id(subject) {
+                items.push(OverlayItem::NodeTags(node_id));
+            }
+        } else {
+            items.extend(
+                tags.tagged_segments()
+                    .into_iter()
+                    .map(OverlayItem::NodeTags),
+            );
+            items.extend(
+                tags.tagged_paths()
+                    .into_iter()
+                    .map(|path_name| OverlayItem::PathTags(path_name.to_owned())),
+            );
+            items.extend(tags.links().iter().map(|link| {
+                let (from, to) = canonical_edge(link.from, link.to);
+                OverlayItem::Edge(from, to)
+            }));
+        }
+        self.overlay = items.into_iter();
+    }
+
+    fn overlay(&mut self) -> Option<EncodedQuad> {
+        loop {
+            if let Some(quad) = self.overlay_quads.next() {
+                return Some(quad);
+            }
+            if self.is_interrupted() {
+                return None;
+            }
+            let item = self.overlay.next()?;
+            let quads = self
+                .overlay_item_to_triples(item)
+                .into_iter()
+                .filter(|quad| self.matches_pattern(quad))
+                .collect::<Vec<_>>();
+            self.overlay_quads = quads.into_iter();
+        }
+    }
+
+    fn set_motif_matches(&mut self) {
+        let mut items = Vec::new();
+        if let Some(subject) = self.subject.as_ref() {
+            if let Some((motif, motif_match)) = self.get_motif_match(subject) {
+                if motif.matches_at(&self.storage.graph, &motif_match) {
+                    items.push(OverlayItem::MotifMatch(Arc::new(motif), motif_match));
+                }
+            }
+        } else if self.is_vocab(self.predicate.as_ref(), vg::MOTIF) {
+            // Matches can only be listed for a given motif
+            let motif = self
+                .object
+                .as_ref()
+                .and_then(|o| match o {
+                    EncodedTerm::SmallStringLiteral(value) => Motif::parse(value.as_str()),
+                    EncodedTerm::BigStringLiteral { value, .. } => Motif::parse(value),
+                    _ => None,
+                })
+                .map(Arc::new);
+            if let Some(motif) = motif {
+                items.extend(
+                    motif
+                        .find_in_graph(&self.storage.graph)
+                        .into_iter()
+                        .map(|motif_match| {
+                            OverlayItem::MotifMatch(Arc::clone(&motif), motif_match)
+                        }),
+                );
+            }
+        }
+        self.overlay = items.into_iter();
+    }
+
+    fn overlay_item_to_triples(&self, item: OverlayItem) -> Vec<EncodedQuad> {
+        let alignments = self.storage.alignments.as_ref();
+        let mut pairs: Vec<(EncodedTerm, EncodedTerm)> = Vec::new();
+        let subject = match item {
+            OverlayItem::Alignment(id) => {
+                let Some(alignment) = alignments.and_then(|a| a.alignment(id)) else {
+                    return Vec::new();
+                };
+                pairs.push((rdf::TYPE.into(), vg::ALIGNMENT.into()));
+                pairs.push((
+                    vg::READ_NAME.into(),
+                    Literal::new_simple_literal(&alignment.read_name)
+                        .as_ref()
+                        .into(),
+                ));
+                pairs.push((
+                    vg::READ_LENGTH.into(),
+                    EncodedTerm::IntegerLiteral((alignment.read_length as i64).into()),
+                ));
+                if let Some(quality) = alignment.mapping_quality {
+                    pairs.push((
+                        vg::MAPPING_QUALITY.into(),
+                        EncodedTerm::IntegerLiteral(i64::from(quality).into()),
+                    ));
+                }
+                pairs.push((
+                    vg::WALK.into(),
+                    Literal::new_simple_literal(alignment.walk_text())
+                        .as_ref()
+                        .into(),
+                ));
+                let mut nodes = alignment
+                    .walk
+                    .iter()
+                    .map(|handle| handle.unpack_number())
+                    .collect::<Vec<_>>();
+                nodes.sort_unstable();
+                nodes.dedup();
+                for node in nodes {
+                    let handle = Handle::new(node, Orientation::Forward);
+                    pairs.push((
+                        vg::ALIGNED_TO.into(),
+                        self.handle_to_namednode(handle).unwrap(),
+                    ));
+                }
+                self.alignment_to_namednode(id)
+            }
+            OverlayItem::NodeCoverage(node_id) => {
+                let Some(coverage) = alignments.map(|a| a.node_coverage(node_id)) else {
+                    return Vec::new();
+                };
+                pairs.push((
+                    vg::COVERAGE.into(),
+                    EncodedTerm::IntegerLiteral((coverage as i64).into()),
+                ));
+                let handle = Handle::new(node_id, Orientation::Forward);
+                self.handle_to_namednode(handle).unwrap()
+            }
+            OverlayItem::Edge(from, to) => {
+                pairs.push((rdf::TYPE.into(), vg::EDGE.into()));
+                pairs.push((
+                    vg::FROM_NODE.into(),
+                    self.handle_to_namednode(from).unwrap(),
+                ));
+                pairs.push((vg::TO_NODE.into(), self.handle_to_namednode(to).unwrap()));
+                let coverage = alignments.and_then(|a| a.edge(a.edge_id(from, to)?));
+                if let Some((_, _, coverage)) = coverage {
+                    pairs.push((
+                        vg::COVERAGE.into(),
+                        EncodedTerm::IntegerLiteral((coverage as i64).into()),
+                    ));
+                }
+                if let Some(link) = self.storage.tags.link(from, to) {
+                    if let Some(overlap) = &link.overlap {
+                        pairs.push((
+                            vg::OVERLAP.into(),
+                            Literal::new_simple_literal(overlap).as_ref().into(),
+                        ));
+                    }
+                    pairs.extend(link.tags.iter().map(|tag| self.tag_to_pair(tag)));
+                }
+                self.edge_to_namednode(from, to)
+            }
+            OverlayItem::NodeTags(node_id) => {
+                pairs.extend(
+                    self.storage
+                        .tags
+                        .segment_tags(node_id)
+                        .iter()
+                        .map(|tag| self.tag_to_pair(tag)),
+                );
+                self.handle_to_namednode(Handle::new(node_id, Orientation::Forward))
+                    .unwrap()
+            }
+            OverlayItem::PathTags(path_name) => {
+                pairs.extend(self.path_tag_pairs(&path_name));
+                self.path_to_namednode(&path_name).unwrap()
+            }
+            OverlayItem::MotifMatch(motif, motif_match) => {
+                pairs.push((rdf::TYPE.into(), vg::MOTIF_MATCH.into()));
+                pairs.push((
+                    vg::MOTIF.into(),
+                    Literal::new_simple_literal(motif.as_str()).as_ref().into(),
+                ));
+                match &motif_match.target {
+                    MotifTarget::Path(path_name) => pairs.push((
+                        vg::PATH_PRED.into(),
+                        self.path_to_namednode(path_name).unwrap(),
+                    )),
+                    MotifTarget::Node(node_id) => pairs.push((
+                        vg::NODE_PRED.into(),
+                        self.handle_to_namednode(Handle::new(*node_id, Orientation::Forward))
+                            .unwrap(),
+                    )),
+                }
+                pairs.push((
+                    vg::POSITION.into(),
+                    EncodedTerm::IntegerLiteral((motif_match.start as i64).into()),
+                ));
+                let orientation = if motif_match.is_reverse { "-" } else { "+" };
+                pairs.push((
+                    vg::ORIENTATION.into(),
+                    Literal::new_simple_literal(orientation).as_ref().into(),
+                ));
+                self.motif_match_to_namednode(&motif, &motif_match)
+            }
+        };
+        pairs
+            .into_iter()
+            .map(|(predicate, object)| {
+                EncodedQuad::new(subject.clone(), predicate, object, self.graph_name.clone())
+            })
+            .collect()
+    }
+
+    fn matches_pattern(&self, quad: &EncodedQuad) -> bool {
+        let matches = |pattern: &Option<EncodedTerm>, term: &EncodedTerm| {
+            pattern.as_ref().map_or(true, |pattern| pattern == term)
+        };
+        matches(&self.subject, &quad.subject)
+            && matches(&self.predicate, &quad.predicate)
+            && matches(&self.object, &quad.object)
+            && matches(&self.subject_filter, &quad.subject)
+            && matches(&self.object_filter, &quad.object)
+    }
+
+    fn alignment_to_namednode(&self, id: usize) -> EncodedTerm {
+        let text = format!("{}/alignment/{}", self.storage.base, id + 1);
+        EncodedTerm::NamedNode {
+            iri_id: StrHash::new(&text),
+            value: text,
+        }
+    }
+
+    fn edge_to_namednode(&self, from: Handle, to: Handle) -> EncodedTerm {
+        let orientation = |handle: Handle| if handle.is_reverse() { '-' } else { '+' };
+        let text = format!(
+            "{}/edge/{}{}{}{}",
+            self.storage.base,
+            from.unpack_number(),
+            orientation(from),
+            to.unpack_number(),
+            orientation(to)
+        );
+        EncodedTerm::NamedNode {
+            iri_id: StrHash::new(&text),
+            value: text,
+        }
+    }
+
+    fn motif_match_to_namednode(&self, motif: &Motif, motif_match: &MotifMatch) -> EncodedTerm {
+        let orientation = if motif_match.is_reverse { '-' } else { '+' };
+        let target = match &motif_match.target {
+            MotifTarget::Path(path_name) => format!("path/{}", path_name),
+            MotifTarget::Node(node_id) => format!("node/{}", node_id),
+        };
+        let text = format!(
+            "{}/motif/{}/{}{}/{}",
+            self.storage.base,
+            motif.as_str(),
+            motif_match.start,
+            orientation,
+            target
+        );
+        EncodedTerm::NamedNode {
+            iri_id: StrHash::new(&text),
+            value: text,
+        }
+    }
+
+    fn get_motif_match(&self, term: &EncodedTerm) -> Option<(Motif, MotifMatch)> {
+        let value = term.get_named_node_value()?;
+        let text = value
+            .strip_prefix(self.storage.base.as_str())?
+            .strip_prefix("/motif/")?;
+        let (motif, rest) = text.split_once('/')?;
+        let (location, target) = rest.split_once('/')?;
+        let is_reverse = match location.chars().last()? {
+            '+' => false,
+            '-' => true,
+            _ => return None,
+        };
+        let start = location[..location.len() - 1].parse().ok()?;
+        let target = if let Some(path_name) = target.strip_prefix("path/") {
+            MotifTarget::Path(path_name.to_owned())
+        } else {
+            MotifTarget::Node(target.strip_prefix("node/")?.parse().ok()?)
+        };
+        Some((
+            Motif::parse(motif)?,
+            MotifMatch {
+                target,
+                start,
+                is_reverse,
+            },
+        ))
+    }
+
+    fn get_alignment_id(&self, term: &EncodedTerm) -> Option<usize> {
+        let value = term.get_named_node_value()?;
+        let rank: usize = value
+            .strip_prefix(self.storage.base.as_str())?
+            .strip_prefix("/alignment/")?
+            .parse()
+            .ok()?;
+        rank.checked_sub(1)
+    }
+
+    fn get_edge_handles(&self, term: &EncodedTerm) -> Option<(Handle, Handle)> {
+        let value = term.get_named_node_value()?;
+        let text = value
+            .strip_prefix(self.storage.base.as_str())?
+            .strip_prefix("/edge/")?;
+        let to_handle = |id: &str, orientation: char| {
+            let orientation = match orientation {
+                '+' => Orientation::Forward,
+                '-' => Orientation::Backward,
+                _ => return None,
+            };
+            Some(Handle::new(id.parse::<u64>().ok()?, orientation))
+        };
+        let split = text.find(['+', '-'])?;
+        let (from_id, rest) = text.split_at(split);
+        let from_orientation = rest.chars().next()?;
+        let rest = &rest[1..];
+        let to_orientation = rest.chars().last()?;
+        let to_id = &rest[..rest.len() - to_orientation.len_utf8()];
+        Some((
+            to_handle(from_id, from_orientation)?,
+            to_handle(to_id, to_orientation)?,
+        ))
+    }
+
     fn handle_to_namednode(&self, handle: Handle) -> Option<EncodedTerm> {
         let id = handle.unpack_number();
         let text = format!("{}/node/{}", self.storage.base, id);
@@ -1586,6 +2849,80 @@ impl GraphIter {
         }
     }
 
+    fn is_alignment_related(&self) -> bool {
+        let predicates = [
+            vg::READ_NAME,
+            vg::READ_LENGTH,
+            vg::MAPPING_QUALITY,
+            vg::ALIGNED_TO,
+            vg::WALK,
+            vg::COVERAGE,
+            vg::FROM_NODE,
+            vg::TO_NODE,
+        ];
+        let types = [vg::ALIGNMENT, vg::EDGE];
+        predicates
+            .into_iter()
+            .any(|x| self.is_vocab(self.predicate.as_ref(), x))
+            || (self.is_vocab(self.predicate.as_ref(), rdf::TYPE)
+                && types
+                    .into_iter()
+                    .any(|x| self.is_vocab(self.object.as_ref(), x)))
+            || self
+                .subject
+                .as_ref()
+                .is_some_and(|s| self.is_overlay_iri(s))
+    }
+
+    fn is_motif_related(&self) -> bool {
+        self.is_vocab(self.predicate.as_ref(), vg::MOTIF)
+            || (self.is_vocab(self.predicate.as_ref(), rdf::TYPE)
+                && self.is_vocab(self.object.as_ref(), vg::MOTIF_MATCH))
+            || self.subject.as_ref().is_some_and(|s| {
+                s.get_named_node_value()
+                    .and_then(|value| value.strip_prefix(self.storage.base.as_str()))
+                    .is_some_and(|local| local.starts_with("/motif/"))
+            })
+    }
+
+    fn is_overlay_iri(&self, term: &EncodedTerm) -> bool {
+        term.get_named_node_value()
+            .and_then(|value| value.strip_prefix(self.storage.base.as_str()))
+            .is_some_and(|local| local.starts_with("/alignment/") || local.starts_with("/edge/"))
+    }
+
+    fn is_path_related(&self) -> bool {
+        let predicates = [
+            rdfs::LABEL,
+            vg::LENGTH,
+            vg::STEP_COUNT,
+            vg::FIRST_STEP,
+            vg::LAST_STEP,
+        ];
+        predicates
+            .into_iter()
+            .any(|x| self.is_vocab(self.predicate.as_ref(), x))
+            || self.subject.as_ref().is_some_and(|s| {
+                s.get_named_node_value()
+                    .is_some_and(|value| value.starts_with(&format!("{}/path/", self.storage.base)))
+                    && self.get_path_id_from_iri(Some(s)).is_some()
+            })
+    }
+
+    fn is_tag_related(&self) -> bool {
+        let Some(predicate) = self
+            .predicate
+            .as_ref()
+            .and_then(|p| p.get_named_node_value())
+        else {
+            return false;
+        };
+        known_tag_predicate_iris().any(|x| x.as_str() == predicate)
+            || predicate
+                .strip_prefix(self.storage.base.as_str())
+                .is_some_and(|local| local.starts_with("/tag/"))
+    }
+
     fn is_node_related(&self) -> bool {
         let predicates = [
             vg::LINKS,
@@ -1609,6 +2946,8 @@ impl GraphIter {
             faldo::REGION,
             faldo::EXACT_POSITION,
             faldo::POSITION,
+            faldo::FORWARD_STRAND_POSITION,
+            faldo::REVERSE_STRAND_POSITION,
             vg::STEP,
         ];
         if self.object.is_none() {
@@ -1625,6 +2964,9 @@ impl GraphIter {
         let predicates = [
             vg::RANK,
             vg::POSITION,
+            vg::NEXT,
+            vg::PREVIOUS,
+            vg::STEP_SEQUENCE,
             vg::PATH_PRED,
             vg::NODE_PRED,
             vg::REVERSE_OF_NODE,
@@ -1656,6 +2998,9 @@ impl GraphIter {
     }
 
     fn is_node_iri_in_graph(&self, term: &EncodedTerm) -> bool {
+        if self.get_term_type(term) != Some(SubjectType::NodeIri) {
+            return false;
+        }
         match self.get_node_id(term) {
             Some(id) => self.storage.graph.has_node(id),
             None => false,
@@ -1699,6 +3044,27 @@ impl GraphIter {
     }
 }
 
+/// The predicate of the optional GFA fields that are part of the vocabulary
+fn known_tag_predicate(name: &str) -> Option<NamedNodeRef<'static>> {
+    match name {
+        "LN" => Some(vg::SEGMENT_LENGTH),
+        "RC" => Some(vg::READ_COUNT),
+        "FC" => Some(vg::FRAGMENT_COUNT),
+        "KC" => Some(vg::KMER_COUNT),
+        "SN" => Some(vg::STABLE_SEQUENCE_NAME),
+        "SO" => Some(vg::STABLE_SEQUENCE_OFFSET),
+        "SR" => Some(vg::STABLE_SEQUENCE_RANK),
+        _ => None,
+    }
+}
+
+fn known_tag_predicate_iris() -> impl Iterator<Item = NamedNodeRef<'static>> {
+    ["LN", "RC", "FC", "KC", "SN", "SO", "SR"]
+        .into_iter()
+        .filter_map(known_tag_predicate)
+        .chain([vg::OVERLAP])
+}
+
 impl StrLookup for GraphIter {
     fn get_str(&self, key: &StrHash) -> Result<Option<String>, StorageError> {
         self.get_str(key)
@@ -1716,13 +3082,18 @@ enum SubjectType {
     StepBorderIri,
     NodeIri,
     StepIri,
+    AlignmentIri,
+    EdgeIri,
+    MotifMatchIri,
 }
 
 #[cfg(test)]
 mod tests {
     use std::{path::Path, str::FromStr};
 
+    use crate::io::{RdfFormat, RdfParser};
     use crate::storage::small_string::SmallString;
+    use crate::storage::RdfProfile;
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
@@ -1739,6 +3110,14 @@ mod tests {
         StorageGenerator::new(storage)
     }
 
+    fn get_alignment_test_generator(file_name: &str, gaf_name: &str) -> StorageGenerator {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(file_name);
+        let mut storage = Storage::open(&path).unwrap();
+        let gaf_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(gaf_name);
+        storage.load_alignments(&gaf_path).unwrap();
+        StorageGenerator::new(storage)
+    }
+
     fn print_quad(quad: &EncodedQuad) {
         let sub = match &quad.subject {
             EncodedTerm::NamedNode { iri_id: _, value } => value.clone(),
@@ -1975,11 +3354,11 @@ mod tests {
         let count_pos1 = count_subjects(&get_position("x/a", 1), &step_triples);
         let count_pos9 = count_subjects(&get_position("x/a", 9), &step_triples);
         let count_pos10 = count_subjects(&get_position("x/a", 10), &step_triples);
-        assert_eq!(count_step1, 8, "Number of step 1 triples");
-        assert_eq!(count_step2, 8, "Number of step 2 triples");
-        assert_eq!(count_pos1, 4, "Number of pos 1 triples");
-        assert_eq!(count_pos9, 8, "Number of pos 9 triples");
-        assert_eq!(count_pos10, 4, "Number of pos 10 triples");
+        assert_eq!(count_step1, 10, "Number of step 1 triples");
+        assert_eq!(count_step2, 10, "Number of step 2 triples");
+        assert_eq!(count_pos1, 5, "Number of pos 1 triples");
+        assert_eq!(count_pos9, 5, "Number of pos 9 triples");
+        assert_eq!(count_pos10, 5, "Number of pos 10 triples");
     }
 
     #[test]
@@ -1997,7 +3376,7 @@ mod tests {
         for triple in &step_triples {
             print_quad(triple);
         }
-        assert_eq!(step_triples.len(), 8, "Number of step 1 triples");
+        assert_eq!(step_triples.len(), 10, "Number of step 1 triples");
     }
 
     #[ignore]
@@ -2034,62 +3413,281 @@ mod tests {
     }
 
     #[test]
-    fn test_step_node() {
-        let gen = get_odgi_test_file_generator("t.gfa");
-        let step_triples: Vec<_> = gen
+    fn test_step_sequence_reverse() {
+        let gen = get_odgi_test_file_generator("t_reverse.gfa");
+        let sequence_triples: Vec<_> = gen
             .quads_for_pattern(
                 None,
-                Some(&vg::NODE_PRED.into()),
+                Some(&vg::STEP_SEQUENCE.into()),
                 None,
                 &EncodedTerm::DefaultGraph,
             )
             .flat_map(|x| x)
             .collect();
-        for triple in &step_triples {
+        for triple in &sequence_triples {
             print_quad(triple);
         }
-        let quad = EncodedQuad::new(
-            get_step("x/a", 6),
-            vg::NODE_PRED.into(),
-            get_node(9),
-            EncodedTerm::DefaultGraph,
-        );
-        print_quad(&quad);
-        assert_eq!(step_triples.len(), 10, "Number of node_pred triples");
-        assert!(step_triples.contains(&quad));
+        let sequence_quad = |rank: u64, sequence: &str| {
+            EncodedQuad::new(
+                get_step("x/a", rank),
+                vg::STEP_SEQUENCE.into(),
+                Literal::new_simple_literal(sequence).as_ref().into(),
+                EncodedTerm::DefaultGraph,
+            )
+        };
+        assert_eq!(sequence_triples.len(), 3, "Number of step sequences");
+        assert!(sequence_triples.contains(&sequence_quad(1, "CAAATAAG")));
+        assert!(sequence_triples.contains(&sequence_quad(2, "AC")));
+        assert!(sequence_triples.contains(&sequence_quad(3, "A")));
     }
 
-    #[ignore]
     #[test]
-    fn test_paths() {
-        let gen = get_odgi_test_file_generator("t.gfa");
-        let generic_triples: Vec<_> = gen
-            .quads_for_pattern(None, None, None, &EncodedTerm::DefaultGraph)
-            .flat_map(|x| x)
-            .collect();
-        let specific_triples: Vec<_> = gen
+    fn test_faldo_strand() {
+        let gen = get_odgi_test_file_generator("t_reverse.gfa");
+        let reverse_triples: Vec<_> = gen
             .quads_for_pattern(
-                Some(&get_path("x/a")),
+                None,
                 Some(&rdf::TYPE.into()),
-                Some(&vg::PATH.into()),
+                Some(&faldo::REVERSE_STRAND_POSITION.into()),
                 &EncodedTerm::DefaultGraph,
             )
             .flat_map(|x| x)
             .collect();
-        for triple in &generic_triples {
-            print_quad(triple)
+        for triple in &reverse_triples {
+            print_quad(triple);
         }
-        let quad = EncodedQuad::new(
-            get_path("x/a"),
-            rdf::TYPE.into(),
-            vg::PATH.into(),
-            EncodedTerm::DefaultGraph,
+        let subjects = reverse_triples
+            .iter()
+            .map(|quad| quad.subject.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subjects,
+            vec![get_position("x/a", 9)],
+            "Border beginning the reverse step"
         );
-        assert_eq!(generic_triples, specific_triples);
-        assert_eq!(generic_triples.len(), 1);
+        let forward_subjects = gen
+            .quads_for_pattern(
+                None,
+                Some(&rdf::TYPE.into()),
+                Some(&faldo::FORWARD_STRAND_POSITION.into()),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .map(|quad| quad.subject)
+            .collect::<Vec<_>>();
+        assert_eq!(forward_subjects.len(), 3, "Borders of the forward steps");
+        assert!(forward_subjects.contains(&get_position("x/a", 1)));
+        assert!(forward_subjects.contains(&get_position("x/a", 11)));
+        assert!(forward_subjects.contains(&get_position("x/a", 12)));
+    }
+
+    #[test]
+    fn test_step_next() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let next_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::NEXT.into()),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        assert_eq!(next_triples.len(), 9, "Number of next triples");
+        let quad = EncodedQuad::new(
+            get_step("x/a", 5),
+            vg::NEXT.into(),
+            get_step("x/a", 6),
+            EncodedTerm::DefaultGraph,
+        );
+        assert!(next_triples.contains(&quad));
+        let previous_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::NEXT.into()),
+                Some(&get_step("x/a", 6)),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        assert_eq!(previous_triples, vec![quad]);
+        let first_triples = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::NEXT.into()),
+                Some(&get_step("x/a", 1)),
+                &EncodedTerm::DefaultGraph,
+            )
+            .count();
+        assert_eq!(first_triples, 0);
+    }
+
+    #[test]
+    fn test_step_node() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let step_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::NODE_PRED.into()),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &step_triples {
+            print_quad(triple);
+        }
+        let quad = EncodedQuad::new(
+            get_step("x/a", 6),
+            vg::NODE_PRED.into(),
+            get_node(9),
+            EncodedTerm::DefaultGraph,
+        );
+        print_quad(&quad);
+        assert_eq!(step_triples.len(), 10, "Number of node_pred triples");
+        assert!(step_triples.contains(&quad));
+    }
+
+    #[test]
+    fn test_triple_pattern_cardinality() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let path = format!("{}/path/x/a", BASE);
+        let path = NamedNodeRef::new_unchecked(&path);
+        let node_pred = PatternTerm::Constant(vg::NODE_PRED.into());
+        assert_eq!(
+            gen.triple_pattern_cardinality(PatternTerm::Free, node_pred, PatternTerm::Free),
+            Some(10)
+        );
+        assert_eq!(
+            gen.triple_pattern_cardinality(PatternTerm::Bound, node_pred, PatternTerm::Free),
+            Some(1)
+        );
+        assert_eq!(
+            gen.triple_pattern_cardinality(
+                PatternTerm::Free,
+                PatternTerm::Constant(vg::PATH_PRED.into()),
+                PatternTerm::Constant(path.into())
+            ),
+            Some(10)
+        );
+        assert_eq!(
+            gen.triple_pattern_cardinality(
+                PatternTerm::Free,
+                PatternTerm::Constant(rdf::TYPE.into()),
+                PatternTerm::Constant(vg::NODE.into())
+            ),
+            Some(15)
+        );
+        assert_eq!(
+            gen.triple_pattern_cardinality(
+                PatternTerm::Free,
+                PatternTerm::Constant(rdfs::COMMENT.into()),
+                PatternTerm::Free
+            ),
+            None
+        );
+        assert_eq!(
+            gen.triple_pattern_cardinality(
+                PatternTerm::Free,
+                PatternTerm::Constant(rdf::TYPE.into()),
+                PatternTerm::Constant(rdfs::COMMENT.into())
+            ),
+            None
+        );
+        let free =
+            gen.triple_pattern_cardinality(PatternTerm::Free, PatternTerm::Free, PatternTerm::Free);
+        let subject_bound = gen.triple_pattern_cardinality(
+            PatternTerm::Bound,
+            PatternTerm::Free,
+            PatternTerm::Free,
+        );
+        assert!(free.is_some_and(|free| free > 100));
+        assert!(subject_bound.is_some_and(|bound| (1..10).contains(&bound)));
+    }
+
+    #[ignore]
+    #[test]
+    fn test_paths() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let generic_triples: Vec<_> = gen
+            .quads_for_pattern(None, None, None, &EncodedTerm::DefaultGraph)
+            .flat_map(|x| x)
+            .collect();
+        let specific_triples: Vec<_> = gen
+            .quads_for_pattern(
+                Some(&get_path("x/a")),
+                Some(&rdf::TYPE.into()),
+                Some(&vg::PATH.into()),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &generic_triples {
+            print_quad(triple)
+        }
+        let quad = EncodedQuad::new(
+            get_path("x/a"),
+            rdf::TYPE.into(),
+            vg::PATH.into(),
+            EncodedTerm::DefaultGraph,
+        );
+        assert_eq!(generic_triples, specific_triples);
+        assert_eq!(generic_triples.len(), 1);
         assert!(generic_triples.contains(&quad));
     }
 
+    #[test]
+    fn test_path_derived_s() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let triples: Vec<_> = gen
+            .quads_for_pattern(
+                Some(&get_path("x/a")),
+                None,
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &triples {
+            print_quad(triple)
+        }
+        let path_quad = |predicate: NamedNodeRef<'_>, object: EncodedTerm| {
+            EncodedQuad::new(
+                get_path("x/a"),
+                predicate.into(),
+                object,
+                EncodedTerm::DefaultGraph,
+            )
+        };
+        assert_eq!(triples.len(), 10);
+        assert!(triples.contains(&path_quad(
+            vg::LENGTH,
+            EncodedTerm::IntegerLiteral(50.into())
+        )));
+        assert!(triples.contains(&path_quad(
+            vg::STEP_COUNT,
+            EncodedTerm::IntegerLiteral(10.into())
+        )));
+        assert!(triples.contains(&path_quad(vg::LAST_STEP, get_step("x/a", 10))));
+        assert!(triples.contains(&path_quad(faldo::END, get_position("x/a", 51))));
+    }
+
+    #[test]
+    fn test_path_length_p() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::LENGTH.into()),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        assert_eq!(triples.len(), 1);
+        assert_eq!(triples[0].object, EncodedTerm::IntegerLiteral(50.into()));
+    }
+
     #[ignore]
     #[test]
     fn test_full() {
@@ -2100,4 +3698,454 @@ mod tests {
         }
         assert_eq!(1, 2);
     }
+
+    /// The triples of a checked-in materialised N-Triples file
+    fn read_materialised(file_name: &str) -> Vec<EncodedQuad> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(file_name);
+        RdfParser::from_format(RdfFormat::NTriples)
+            .parse_read(std::fs::File::open(path).unwrap())
+            .map(|quad| EncodedQuad::from(quad.unwrap().as_ref()))
+            .collect()
+    }
+
+    fn profile_quads(name: &str, profile: RdfProfile) -> Vec<EncodedQuad> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("{name}.gfa"));
+        let storage = Storage::open(&path).unwrap().with_rdf_profile(profile);
+        StorageGenerator::new(storage)
+            .quads_for_pattern(None, None, None, &EncodedTerm::DefaultGraph)
+            .flat_map(|x| x)
+            .collect()
+    }
+
+    /// The IRIs minted for the graph moved from the default profile to the vg one
+    fn to_vg_profile(term: &EncodedTerm) -> EncodedTerm {
+        if let EncodedTerm::NamedNode { value, .. } = term {
+            if let Some(local) = value.strip_prefix(RdfProfile::Oxiqle.base()) {
+                let text = format!("{}{local}", RdfProfile::Vg.base());
+                return EncodedTerm::NamedNode {
+                    iri_id: StrHash::new(&text),
+                    value: text,
+                };
+            }
+        }
+        term.clone()
+    }
+
+    /// The vocabulary of the materialised RDF of vg, the other triples are extensions of the generator
+    fn is_materialised_vocabulary(quad: &EncodedQuad) -> bool {
+        if quad.predicate == EncodedTerm::from(rdf::TYPE) {
+            [
+                vg::NODE,
+                vg::PATH,
+                vg::STEP,
+                faldo::EXACT_POSITION,
+                faldo::POSITION,
+            ]
+            .into_iter()
+            .any(|class| quad.object == EncodedTerm::from(class))
+        } else {
+            [
+                rdf::VALUE,
+                vg::LINKS,
+                vg::LINKS_FORWARD_TO_FORWARD,
+                vg::LINKS_FORWARD_TO_REVERSE,
+                vg::LINKS_REVERSE_TO_FORWARD,
+                vg::LINKS_REVERSE_TO_REVERSE,
+                vg::NODE_PRED,
+                vg::REVERSE_OF_NODE,
+                vg::RANK,
+                vg::POSITION,
+                vg::PATH_PRED,
+                faldo::BEGIN,
+                faldo::END,
+                faldo::POSITION_PRED,
+                faldo::REFERENCE,
+            ]
+            .into_iter()
+            .any(|predicate| quad.predicate == EncodedTerm::from(predicate))
+        }
+    }
+
+    #[test]
+    fn test_vg_profile_only_changes_iris() {
+        for name in ["t", "t_red", "t_double", "t_step", "t_reverse"] {
+            let actual = profile_quads(name, RdfProfile::Vg);
+            let expected: Vec<_> = profile_quads(name, RdfProfile::Oxiqle)
+                .iter()
+                .map(|quad| {
+                    EncodedQuad::new(
+                        to_vg_profile(&quad.subject),
+                        to_vg_profile(&quad.predicate),
+                        to_vg_profile(&quad.object),
+                        quad.graph_name.clone(),
+                    )
+                })
+                .collect();
+            assert_eq!(actual.len(), expected.len(), "{name}");
+            for quad in &expected {
+                assert!(actual.contains(quad), "{name}: missing {quad:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_vg_profile_materialised() {
+        for name in ["t_red", "t_double", "t_step"] {
+            let expected = read_materialised(&format!("{name}.vg.nt"));
+            let actual: Vec<_> = profile_quads(name, RdfProfile::Vg)
+                .into_iter()
+                .filter(is_materialised_vocabulary)
+                .collect();
+            for quad in &expected {
+                assert!(actual.contains(quad), "{name}: missing {quad:?}");
+            }
+            for quad in &actual {
+                assert!(expected.contains(quad), "{name}: unexpected {quad:?}");
+            }
+        }
+    }
+
+    fn get_alignment(id: i64) -> EncodedTerm {
+        let text = format!("{}/alignment/{}", BASE, id);
+        EncodedTerm::NamedNode {
+            iri_id: StrHash::new(&text),
+            value: text,
+        }
+    }
+
+    #[test]
+    fn test_alignment_coverage() {
+        let gen = get_alignment_test_generator("t_step.gfa", "t_step.gaf");
+        let coverage_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::COVERAGE.into()),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &coverage_triples {
+            print_quad(triple);
+        }
+        let node_quad = EncodedQuad::new(
+            get_node(1),
+            vg::COVERAGE.into(),
+            EncodedTerm::IntegerLiteral(3.into()),
+            EncodedTerm::DefaultGraph,
+        );
+        let edge_text = format!("{}/edge/1+2+", BASE);
+        let edge_quad = EncodedQuad::new(
+            EncodedTerm::NamedNode {
+                iri_id: StrHash::new(&edge_text),
+                value: edge_text,
+            },
+            vg::COVERAGE.into(),
+            EncodedTerm::IntegerLiteral(2.into()),
+            EncodedTerm::DefaultGraph,
+        );
+        assert_eq!(coverage_triples.len(), 3, "Number of coverage triples");
+        assert!(coverage_triples.contains(&node_quad));
+        assert!(coverage_triples.contains(&edge_quad));
+    }
+
+    #[test]
+    fn test_alignment_s() {
+        let gen = get_alignment_test_generator("t_step.gfa", "t_step.gaf");
+        let alignment_triples: Vec<_> = gen
+            .quads_for_pattern(
+                Some(&get_alignment(1)),
+                None,
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &alignment_triples {
+            print_quad(triple);
+        }
+        let quality_quad = EncodedQuad::new(
+            get_alignment(1),
+            vg::MAPPING_QUALITY.into(),
+            EncodedTerm::IntegerLiteral(60.into()),
+            EncodedTerm::DefaultGraph,
+        );
+        assert_eq!(alignment_triples.len(), 7, "Number of alignment triples");
+        assert!(alignment_triples.contains(&quality_quad));
+    }
+
+    #[test]
+    fn test_aligned_to_o() {
+        let gen = get_alignment_test_generator("t_step.gfa", "t_step.gaf");
+        let alignment_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::ALIGNED_TO.into()),
+                Some(&get_node(2)),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &alignment_triples {
+            print_quad(triple);
+        }
+        assert_eq!(alignment_triples.len(), 2, "Number of alignments on node 2");
+    }
+
+    fn get_tag_predicate(name: &str) -> EncodedTerm {
+        let text = format!("{}/tag/{}", BASE, name);
+        EncodedTerm::NamedNode {
+            iri_id: StrHash::new(&text),
+            value: text,
+        }
+    }
+
+    #[test]
+    fn test_tags_s() {
+        let gen = get_odgi_test_file_generator("t_tags.gfa");
+        let node_triples: Vec<_> = gen
+            .quads_for_pattern(Some(&get_node(1)), None, None, &EncodedTerm::DefaultGraph)
+            .flat_map(|x| x)
+            .collect();
+        for triple in &node_triples {
+            print_quad(triple);
+        }
+        let length_quad = EncodedQuad::new(
+            get_node(1),
+            vg::SEGMENT_LENGTH.into(),
+            EncodedTerm::IntegerLiteral(8.into()),
+            EncodedTerm::DefaultGraph,
+        );
+        let name_quad = EncodedQuad::new(
+            get_node(1),
+            vg::STABLE_SEQUENCE_NAME.into(),
+            Literal::new_simple_literal("chr1").as_ref().into(),
+            EncodedTerm::DefaultGraph,
+        );
+        let custom_quad = EncodedQuad::new(
+            get_node(1),
+            get_tag_predicate("XY"),
+            Literal::from(0.5_f32).as_ref().into(),
+            EncodedTerm::DefaultGraph,
+        );
+        assert!(node_triples.contains(&length_quad));
+        assert!(node_triples.contains(&name_quad));
+        assert!(node_triples.contains(&custom_quad));
+    }
+
+    #[test]
+    fn test_tags_p() {
+        let gen = get_odgi_test_file_generator("t_tags.gfa");
+        let tag_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::READ_COUNT.into()),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &tag_triples {
+            print_quad(triple);
+        }
+        let count_quad = EncodedQuad::new(
+            get_node(2),
+            vg::READ_COUNT.into(),
+            EncodedTerm::IntegerLiteral(12.into()),
+            EncodedTerm::DefaultGraph,
+        );
+        assert_eq!(tag_triples, vec![count_quad]);
+        let path_tag_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&get_tag_predicate("PT")),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        assert_eq!(path_tag_triples.len(), 1, "Number of path tag triples");
+        assert_eq!(path_tag_triples[0].subject, get_path("x/a"));
+    }
+
+    #[test]
+    fn test_overlap_p() {
+        let gen = get_odgi_test_file_generator("t_tags.gfa");
+        let overlap_triples: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::OVERLAP.into()),
+                None,
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        for triple in &overlap_triples {
+            print_quad(triple);
+        }
+        let edge_text = format!("{}/edge/1+2+", BASE);
+        let link_quad = EncodedQuad::new(
+            EncodedTerm::NamedNode {
+                iri_id: StrHash::new(&edge_text),
+                value: edge_text,
+            },
+            vg::OVERLAP.into(),
+            Literal::new_simple_literal("1M").as_ref().into(),
+            EncodedTerm::DefaultGraph,
+        );
+        let path_quad = EncodedQuad::new(
+            get_path("x/a"),
+            vg::OVERLAP.into(),
+            Literal::new_simple_literal("8M,1M").as_ref().into(),
+            EncodedTerm::DefaultGraph,
+        );
+        assert_eq!(overlap_triples.len(), 2, "Number of overlap triples");
+        assert!(overlap_triples.contains(&link_quad));
+        assert!(overlap_triples.contains(&path_quad));
+    }
+
+    fn get_motif_match_iri(motif: &str, location: &str, target: &str) -> EncodedTerm {
+        let text = format!("{}/motif/{}/{}/{}", BASE, motif, location, target);
+        EncodedTerm::NamedNode {
+            iri_id: StrHash::new(&text),
+            value: text,
+        }
+    }
+
+    #[test]
+    fn test_motif_across_nodes() {
+        let gen = get_odgi_test_file_generator("t_step.gfa");
+        let motif: EncodedTerm = Literal::new_simple_literal("AAGA").as_ref().into();
+        let matches: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::MOTIF.into()),
+                Some(&motif),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .collect();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            matches[0].subject,
+            get_motif_match_iri("AAGA", "6+", "path/x/a")
+        );
+    }
+
+    #[test]
+    fn test_motif_reverse_strand() {
+        let gen = get_odgi_test_file_generator("t_step.gfa");
+        let motif: EncodedTerm = Literal::new_simple_literal("TTTG").as_ref().into();
+        let matches: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::MOTIF.into()),
+                Some(&motif),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .map(|quad| quad.subject)
+            .collect();
+        assert_eq!(matches.len(), 2);
+        assert!(matches.contains(&get_motif_match_iri("TTTG", "1-", "node/1")));
+        assert!(matches.contains(&get_motif_match_iri("TTTG", "1-", "path/x/a")));
+    }
+
+    #[test]
+    fn test_motif_match_s() {
+        let gen = get_odgi_test_file_generator("t_step.gfa");
+        let motif_match = get_motif_match_iri("AAGA", "6+", "path/x/a");
+        let triples: Vec<_> = gen
+            .quads_for_pattern(Some(&motif_match), None, None, &EncodedTerm::DefaultGraph)
+            .flat_map(|x| x)
+            .collect();
+        for triple in &triples {
+            print_quad(triple);
+        }
+        assert_eq!(triples.len(), 5);
+        assert!(triples.contains(&EncodedQuad::new(
+            motif_match.clone(),
+            vg::POSITION.into(),
+            EncodedTerm::IntegerLiteral(6.into()),
+            EncodedTerm::DefaultGraph,
+        )));
+        for (location, target) in [
+            ("5+", "path/x/a"),
+            ("8+", "path/x/a"),
+            ("18446744073709551615+", "path/x/a"),
+            ("18446744073709551615+", "node/1"),
+        ] {
+            let wrong_match = get_motif_match_iri("AAGA", location, target);
+            let triples = gen
+                .quads_for_pattern(Some(&wrong_match), None, None, &EncodedTerm::DefaultGraph)
+                .count();
+            assert_eq!(triples, 0, "{location} on {target}");
+        }
+    }
+
+    #[test]
+    fn test_motif_spanning_several_nodes() {
+        let gen = get_odgi_test_file_generator("t.gfa");
+        let motif: EncodedTerm = Literal::new_simple_literal("AAGGCT").as_ref().into();
+        let matches: Vec<_> = gen
+            .quads_for_pattern(
+                None,
+                Some(&vg::MOTIF.into()),
+                Some(&motif),
+                &EncodedTerm::DefaultGraph,
+            )
+            .flat_map(|x| x)
+            .map(|quad| quad.subject)
+            .collect();
+        let motif_match = get_motif_match_iri("AAGGCT", "6+", "path/x/a");
+        assert_eq!(matches, vec![motif_match.clone()]);
+        let triples = gen
+            .quads_for_pattern(Some(&motif_match), None, None, &EncodedTerm::DefaultGraph)
+            .count();
+        assert_eq!(triples, 5);
+    }
+
+    #[test]
+    fn test_partitions_cover_scans() {
+        let gen = get_alignment_test_generator("t_step.gfa", "t_step.gaf");
+        let all: Vec<_> = gen
+            .quads_for_pattern(None, None, None, &EncodedTerm::DefaultGraph)
+            .flat_map(|x| x)
+            .collect();
+        let mut patterns = vec![(None, None, None), (Some(get_node(1)), None, None)];
+        for quad in &all {
+            let pattern = (None, Some(quad.predicate.clone()), None);
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+        for (subject, predicate, object) in patterns {
+            let expected: Vec<_> = gen
+                .quads_for_pattern(
+                    subject.as_ref(),
+                    predicate.as_ref(),
+                    object.as_ref(),
+                    &EncodedTerm::DefaultGraph,
+                )
+                .flat_map(|x| x)
+                .collect();
+            let mut actual = Vec::new();
+            for partition in gen.scan_partitions(3) {
+                actual.extend(
+                    gen.quads_for_pattern_in_partition(
+                        subject.as_ref(),
+                        predicate.as_ref(),
+                        object.as_ref(),
+                        &EncodedTerm::DefaultGraph,
+                        &partition,
+                    )
+                    .flat_map(|x| x),
+                );
+            }
+            assert_eq!(actual.len(), expected.len(), "{predicate:?}");
+            for quad in &expected {
+                assert!(actual.contains(quad), "missing {quad:?}");
+            }
+        }
+    }
 }
@@ -0,0 +1,495 @@
+use crate::io::{RdfFormat, RdfSerializer};
+use crate::model::vocab::{rdf, xsd};
+use crate::model::{Literal, NamedNode, Triple};
+use crate::storage::gaf::canonical_edge;
+use crate::storage::vg_vocab::{faldo, vg};
+use crate::storage::{Storage, StorageError};
+use handlegraph::handle::{Direction, Handle};
+use handlegraph::handlegraph::{HandleGraph, IntoNeighbors, IntoSequences};
+use handlegraph::packedgraph::PackedGraph;
+use handlegraph::pathhandlegraph::{GraphPathNames, GraphPaths, IntoPathIds, PathId};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Where a [`Subgraph`] is centred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubgraphSeed {
+    /// A single node, by ID.
+    Node(u64),
+    /// The nodes overlapping the bases `start..=end` of a path, counted from 1 like the `vg:position` of steps.
+    PathInterval { path: String, start: u64, end: u64 },
+}
+
+/// How far a [`Subgraph`] extends from its seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubgraphRadius {
+    /// The number of edges between the seed and the furthest node.
+    Steps(u64),
+    /// The number of bases from the seed to the first base of the furthest node,
+    /// the neighbours of the seed are one base away.
+    Bases(u64),
+}
+
+/// A subgraph induced by the nodes around a seed, as returned by [`Store::subgraph`](crate::store::Store::subgraph).
+///
+/// It contains the nodes, the edges between them and the fragments of the paths walking through them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subgraph {
+    base: String,
+    nodes: Vec<(u64, String)>,
+    /// The edges in the orientation of the GFA link, with its overlap if it is not blunt
+    edges: Vec<(Handle, Handle, Option<String>)>,
+    fragments: Vec<PathFragment>,
+}
+
+/// Consecutive steps of a path, all on nodes of the subgraph
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PathFragment {
+    path: String,
+    first_rank: u64,
+    first_position: u64,
+    steps: Vec<Handle>,
+    /// The strand of the step after the fragment, or of its last step at the end of the path
+    end_is_reverse: bool,
+}
+
+impl Subgraph {
+    pub(super) fn extract(
+        storage: &Storage,
+        seed: &SubgraphSeed,
+        radius: SubgraphRadius,
+    ) -> Result<Self, StorageError> {
+        let graph = &storage.graph;
+        let seeds = seed_nodes(graph, seed)?;
+        let included = neighbourhood(graph, &seeds, radius);
+        let mut nodes = included
+            .iter()
+            .map(|id| {
+                let sequence = graph.sequence_vec(Handle::pack(*id, false));
+                (*id, String::from_utf8_lossy(&sequence).into_owned())
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+        let mut edges = HashSet::new();
+        for (id, _) in &nodes {
+            let handle = Handle::pack(*id, false);
+            for from in [handle, handle.flip()] {
+                for to in graph.neighbors(from, Direction::Right) {
+                    if included.contains(&to.unpack_number()) {
+                        edges.insert(canonical_edge(from, to));
+                    }
+                }
+            }
+        }
+        let mut edges = edges
+            .into_iter()
+            .map(|(from, to)| {
+                if let Some(link) = storage.tags.link(from, to) {
+                    (link.from, link.to, link.overlap.clone())
+                } else {
+                    (from, to, None)
+                }
+            })
+            .collect::<Vec<_>>();
+        edges.sort_unstable_by_key(|(from, to, _)| (from.as_integer(), to.as_integer()));
+        let mut fragments = Vec::new();
+        for path_id in graph.path_ids() {
+            path_fragments(graph, path_id, &included, &mut fragments);
+        }
+        Ok(Self {
+            base: storage.base.clone(),
+            nodes,
+            edges,
+            fragments,
+        })
+    }
+
+    /// The IDs of the nodes of the subgraph, in increasing order
+    pub fn nodes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.nodes.iter().map(|(id, _)| *id)
+    }
+
+    /// The subgraph in the vg vocabulary, with the same IRIs as the store it has been extracted from
+    ///
+    /// The steps have their faldo begin and end positions,
+    /// the paths are not described as faldo regions because they extend beyond the subgraph.
+    pub fn triples(&self) -> Vec<Triple> {
+        let mut triples = Vec::new();
+        for (id, sequence) in &self.nodes {
+            let node = self.node(*id);
+            triples.push(Triple::new(node.clone(), rdf::TYPE, vg::NODE));
+            triples.push(Triple::new(
+                node,
+                rdf::VALUE,
+                Literal::new_simple_literal(sequence),
+            ));
+        }
+        for (from, to, _) in &self.edges {
+            let predicate = match (from.is_reverse(), to.is_reverse()) {
+                (false, false) => vg::LINKS_FORWARD_TO_FORWARD,
+                (false, true) => vg::LINKS_FORWARD_TO_REVERSE,
+                (true, false) => vg::LINKS_REVERSE_TO_FORWARD,
+                (true, true) => vg::LINKS_REVERSE_TO_REVERSE,
+            };
+            let from = self.node(from.unpack_number());
+            let to = self.node(to.unpack_number());
+            triples.push(Triple::new(from.clone(), vg::LINKS, to.clone()));
+            triples.push(Triple::new(from, predicate, to));
+        }
+        let mut paths = HashSet::new();
+        for fragment in &self.fragments {
+            let path = NamedNode::new_unchecked(format!("{}/path/{}", self.base, fragment.path));
+            if paths.insert(path.clone()) {
+                triples.push(Triple::new(path.clone(), rdf::TYPE, vg::PATH));
+            }
+            let mut position = fragment.first_position;
+            for (rank, handle) in (fragment.first_rank..).zip(&fragment.steps) {
+                let end = position + self.node_len(*handle);
+                let step = NamedNode::new_unchecked(format!("{}/step/{rank}", path.as_str()));
+                let node_predicate = if handle.is_reverse() {
+                    vg::REVERSE_OF_NODE
+                } else {
+                    vg::NODE_PRED
+                };
+                triples.extend([
+                    Triple::new(step.clone(), rdf::TYPE, vg::STEP),
+                    Triple::new(
+                        step.clone(),
+                        node_predicate,
+                        self.node(handle.unpack_number()),
+                    ),
+                    Triple::new(step.clone(), vg::RANK, integer(rank)),
+                    Triple::new(step.clone(), vg::POSITION, integer(position)),
+                    Triple::new(step.clone(), vg::PATH_PRED, path.clone()),
+                    Triple::new(step.clone(), faldo::BEGIN, border(&path, position)),
+                    Triple::new(step, faldo::END, border(&path, end)),
+                ]);
+                triples.extend(border_triples(&path, position, handle.is_reverse()));
+                position = end;
+            }
+            triples.extend(border_triples(&path, position, fragment.end_is_reverse));
+        }
+        triples
+    }
+
+    /// Writes the [`triples`](Self::triples) of the subgraph in an RDF format
+    pub fn write_rdf<W: Write>(&self, format: RdfFormat, write: W) -> io::Result<W> {
+        let mut writer = RdfSerializer::from_format(format).serialize_to_write(write);
+        for triple in self.triples() {
+            writer.write_triple(&triple)?;
+        }
+        writer.finish()
+    }
+
+    /// Writes the subgraph as GFA 1.0.
+    ///
+    /// Path fragments are named like odgi does, `<path>:<start>-<end>` with 0-based half-open positions.
+    pub fn write_gfa<W: Write>(&self, mut write: W) -> io::Result<W> {
+        writeln!(write, "H\tVN:Z:1.0")?;
+        for (id, sequence) in &self.nodes {
+            writeln!(write, "S\t{id}\t{sequence}")?;
+        }
+        for (from, to, overlap) in &self.edges {
+            writeln!(
+                write,
+                "L\t{}\t{}\t{}\t{}\t{}",
+                from.unpack_number(),
+                orientation(*from),
+                to.unpack_number(),
+                orientation(*to),
+                overlap.as_deref().unwrap_or("0M")
+            )?;
+        }
+        for fragment in &self.fragments {
+            let start = fragment.first_position - 1;
+            let length = fragment
+                .steps
+                .iter()
+                .map(|handle| self.node_len(*handle))
+                .sum::<u64>();
+            let steps = fragment
+                .steps
+                .iter()
+                .map(|handle| format!("{}{}", handle.unpack_number(), orientation(*handle)))
+                .collect::<Vec<_>>();
+            writeln!(
+                write,
+                "P\t{}:{start}-{}\t{}\t*",
+                fragment.path,
+                start + length,
+                steps.join(",")
+            )?;
+        }
+        Ok(write)
+    }
+
+    fn node(&self, id: u64) -> NamedNode {
+        NamedNode::new_unchecked(format!("{}/node/{id}", self.base))
+    }
+
+    fn node_len(&self, handle: Handle) -> u64 {
+        self.nodes
+            .binary_search_by_key(&handle.unpack_number(), |(id, _)| *id)
+            .map_or(0, |i| self.nodes[i].1.len() as u64)
+    }
+}
+
+/// The nodes within `radius` of the `seeds`, the seeds included
+pub fn neighbourhood(graph: &PackedGraph, seeds: &[u64], radius: SubgraphRadius) -> HashSet<u64> {
+    let (limit, counts_bases) = match radius {
+        SubgraphRadius::Steps(steps) => (steps, false),
+        SubgraphRadius::Bases(bases) => (bases, true),
+    };
+    let mut distances = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    for seed in seeds {
+        distances.insert(*seed, 0);
+        queue.push(Reverse((0, *seed)));
+    }
+    while let Some(Reverse((distance, id))) = queue.pop() {
+        if distances.get(&id).is_some_and(|d| *d < distance) {
+            continue;
+        }
+        let handle = Handle::pack(id, false);
+        // Only the seeds are at distance 0, their own bases are not on the way to the first base of their neighbours
+        let cost = if !counts_bases || distance == 0 {
+            1
+        } else {
+            graph.node_len(handle) as u64
+        };
+        let next = distance.saturating_add(cost);
+        if next > limit {
+            continue;
+        }
+        for direction in [Direction::Left, Direction::Right] {
+            for neighbour in graph.neighbors(handle, direction) {
+                let neighbour = neighbour.unpack_number();
+                if distances.get(&neighbour).map_or(true, |d| next < *d) {
+                    distances.insert(neighbour, next);
+                    queue.push(Reverse((next, neighbour)));
+                }
+            }
+        }
+    }
+    distances.into_keys().collect()
+}
+
+/// The nodes a seed stands for
+pub fn seed_nodes(graph: &PackedGraph, seed: &SubgraphSeed) -> Result<Vec<u64>, StorageError> {
+    match seed {
+        SubgraphSeed::Node(id) => {
+            if graph.has_node(*id) {
+                Ok(vec![*id])
+            } else {
+                Err(StorageError::Other(
+                    format!("The node {id} does not exist").into(),
+                ))
+            }
+        }
+        SubgraphSeed::PathInterval { path, start, end } => {
+            if start > end {
+                return Err(StorageError::Other(
+                    format!("The interval {start}-{end} of the path {path} is empty").into(),
+                ));
+            }
+            let path_id = graph.get_path_id(path.as_bytes()).ok_or_else(|| {
+                StorageError::Other(format!("The path {path} does not exist").into())
+            })?;
+            let mut nodes = Vec::new();
+            let mut position = 1;
+            let mut step = graph.path_first_step(path_id);
+            while let Some(current) = step {
+                step = graph.path_next_step(path_id, current);
+                let Some(handle) = graph.path_handle_at_step(path_id, current) else {
+                    continue;
+                };
+                let length = graph.node_len(handle) as u64;
+                if position <= *end && position + length > *start {
+                    nodes.push(handle.unpack_number());
+                }
+                position += length;
+            }
+            Ok(nodes)
+        }
+    }
+}
+
+/// Adds the maximal runs of steps of the path that are on `included` nodes
+fn path_fragments(
+    graph: &PackedGraph,
+    path_id: PathId,
+    included: &HashSet<u64>,
+    fragments: &mut Vec<PathFragment>,
+) {
+    let Some(name) = graph.get_path_name(path_id) else {
+        return;
+    };
+    let Ok(path) = String::from_utf8(name.collect()) else {
+        return;
+    };
+    let mut current_fragment: Option<PathFragment> = None;
+    let mut rank = 0;
+    let mut position = 1;
+    let mut step = graph.path_first_step(path_id);
+    while let Some(current) = step {
+        rank += 1;
+        step = graph.path_next_step(path_id, current);
+        let Some(handle) = graph.path_handle_at_step(path_id, current) else {
+            continue;
+        };
+        if included.contains(&handle.unpack_number()) {
+            current_fragment
+                .get_or_insert_with(|| PathFragment {
+                    path: path.clone(),
+                    first_rank: rank,
+                    first_position: position,
+                    steps: Vec::new(),
+                    end_is_reverse: false,
+                })
+                .steps
+                .push(handle);
+        } else if let Some(mut fragment) = current_fragment.take() {
+            fragment.end_is_reverse = handle.is_reverse();
+            fragments.push(fragment);
+        }
+        position += graph.node_len(handle) as u64;
+    }
+    if let Some(mut fragment) = current_fragment {
+        fragment.end_is_reverse = fragment
+            .steps
+            .last()
+            .is_some_and(|handle| handle.is_reverse());
+        fragments.push(fragment);
+    }
+}
+
+fn orientation(handle: Handle) -> char {
+    if handle.is_reverse() {
+        '-'
+    } else {
+        '+'
+    }
+}
+
+fn integer(value: u64) -> Literal {
+    Literal::new_typed_literal(value.to_string(), xsd::INTEGER)
+}
+
+fn border(path: &NamedNode, position: u64) -> NamedNode {
+    NamedNode::new_unchecked(format!("{}/position/{position}", path.as_str()))
+}
+
+/// The faldo description of a position of a path, typed with the strand of the step it begins
+fn border_triples(path: &NamedNode, position: u64, is_reverse: bool) -> [Triple; 5] {
+    let border = border(path, position);
+    let strand = if is_reverse {
+        faldo::REVERSE_STRAND_POSITION
+    } else {
+        faldo::FORWARD_STRAND_POSITION
+    };
+    [
+        Triple::new(border.clone(), rdf::TYPE, faldo::EXACT_POSITION),
+        Triple::new(border.clone(), rdf::TYPE, faldo::POSITION),
+        Triple::new(border.clone(), rdf::TYPE, strand),
+        Triple::new(border.clone(), faldo::POSITION_PRED, integer(position)),
+        Triple::new(border, faldo::REFERENCE, path.clone()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GFA: &str = "S\t1\tCAAATAAG\nS\t2\tA\nS\t3\tG\nS\t4\tTTTT\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t3\t+\t4\t+\t0M\nP\tx\t1+,2+,3+,4+\t*\n";
+
+    #[test]
+    fn test_neighbourhood_radius() {
+        let storage = Storage::from_str(GFA).unwrap();
+        let nodes = |radius| {
+            let mut nodes = neighbourhood(&storage.graph, &[2], radius)
+                .into_iter()
+                .collect::<Vec<_>>();
+            nodes.sort_unstable();
+            nodes
+        };
+        assert_eq!(nodes(SubgraphRadius::Steps(0)), [2]);
+        assert_eq!(nodes(SubgraphRadius::Steps(1)), [1, 2, 3]);
+        assert_eq!(nodes(SubgraphRadius::Bases(0)), [2]);
+        assert_eq!(nodes(SubgraphRadius::Bases(1)), [1, 2, 3]);
+        assert_eq!(nodes(SubgraphRadius::Bases(2)), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_empty_path_interval() {
+        let storage = Storage::from_str(GFA).unwrap();
+        let seed = |start, end| SubgraphSeed::PathInterval {
+            path: "x".into(),
+            start,
+            end,
+        };
+        assert_eq!(seed_nodes(&storage.graph, &seed(9, 9)).unwrap(), [2]);
+        seed_nodes(&storage.graph, &seed(10, 9)).unwrap_err();
+    }
+
+    #[test]
+    fn test_subgraph_gfa_overlaps() {
+        let storage = Storage::from_str(
+            "S\t1\tACG\nS\t2\tCGT\nS\t3\tA\nL\t2\t-\t1\t-\t2M\nL\t2\t+\t3\t+\t0M\n",
+        )
+        .unwrap();
+        let subgraph =
+            Subgraph::extract(&storage, &SubgraphSeed::Node(2), SubgraphRadius::Steps(1)).unwrap();
+        let gfa = String::from_utf8(subgraph.write_gfa(Vec::new()).unwrap()).unwrap();
+        // The links keep their orientation and their overlap
+        assert_eq!(
+            gfa,
+            "H\tVN:Z:1.0\nS\t1\tACG\nS\t2\tCGT\nS\t3\tA\nL\t2\t+\t3\t+\t0M\nL\t2\t-\t1\t-\t2M\n"
+        );
+    }
+
+    #[test]
+    fn test_subgraph_gfa() {
+        let storage = Storage::from_str(GFA).unwrap();
+        let subgraph = Subgraph::extract(
+            &storage,
+            &SubgraphSeed::PathInterval {
+                path: "x".into(),
+                start: 9,
+                end: 10,
+            },
+            SubgraphRadius::Steps(0),
+        )
+        .unwrap();
+        let gfa = String::from_utf8(subgraph.write_gfa(Vec::new()).unwrap()).unwrap();
+        assert_eq!(
+            gfa,
+            "H\tVN:Z:1.0\nS\t2\tA\nS\t3\tG\nL\t2\t+\t3\t+\t0M\nP\tx:8-10\t2+,3+\t*\n"
+        );
+        let triples = subgraph.triples();
+        assert!(triples.contains(&Triple::new(
+            NamedNode::new_unchecked("https://example.org/path/x/step/2"),
+            vg::POSITION,
+            integer(9),
+        )));
+
+        // The steps have the faldo triples of the store
+        let path = NamedNode::new_unchecked("https://example.org/path/x");
+        let step = |rank| NamedNode::new_unchecked(format!("{}/step/{rank}", path.as_str()));
+        for triple in [
+            Triple::new(step(2), faldo::BEGIN, border(&path, 9)),
+            Triple::new(step(2), faldo::END, border(&path, 10)),
+            Triple::new(step(3), faldo::BEGIN, border(&path, 10)),
+            Triple::new(step(3), faldo::END, border(&path, 11)),
+            Triple::new(border(&path, 9), rdf::TYPE, faldo::EXACT_POSITION),
+            Triple::new(border(&path, 10), rdf::TYPE, faldo::FORWARD_STRAND_POSITION),
+            Triple::new(border(&path, 11), faldo::POSITION_PRED, integer(11)),
+            Triple::new(border(&path, 11), faldo::REFERENCE, path.clone()),
+        ] {
+            assert!(triples.contains(&triple), "{triple} is missing");
+        }
+        assert!(!triples
+            .iter()
+            .any(|triple| triple.subject == border(&path, 12).into()));
+    }
+}
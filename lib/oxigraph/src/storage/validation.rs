@@ -0,0 +1,357 @@
+use crate::storage::Storage;
+use gfa::gfa::{Link, Orientation, Path as GfaPath, GFA};
+use gfa::optfields::OptionalFields;
+use handlegraph::handle::{Direction, Handle};
+use handlegraph::handlegraph::{HandleGraph, IntoNeighbors};
+use handlegraph::pathhandlegraph::{
+    GraphPathNames, GraphPaths, GraphPathsSteps, IntoPathIds, PathId,
+};
+use std::collections::HashSet;
+use std::fmt;
+
+/// A broken invariant of a pangenome graph, reported by [`Store::validate`](crate::store::Store::validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationProblem {
+    /// A link between two segments of which one does not exist.
+    LinkToMissingSegment { from: String, to: String },
+    /// A path step on a segment that does not exist.
+    StepOnMissingSegment { path: String, rank: u64, node: u64 },
+    /// Two consecutive path steps that are not connected by a link.
+    StepWithoutLink {
+        path: String,
+        rank: u64,
+        from: String,
+        to: String,
+    },
+    /// Several paths sharing the same name.
+    DuplicatePathName { path: String },
+    /// Several segments sharing the same name, only the first one is kept.
+    DuplicateSegment { node: u64 },
+    /// A segment without sequence.
+    EmptySequence { node: u64 },
+    /// A segment sequence with a character that is not one of `ACGTN`, only the first one is reported.
+    InvalidBase {
+        node: u64,
+        offset: usize,
+        base: char,
+    },
+    /// A step position of the path index that does not match the lengths of the previous segments.
+    PositionMismatch {
+        path: String,
+        rank: u64,
+        expected: usize,
+        actual: Option<usize>,
+    },
+}
+
+impl fmt::Display for ValidationProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LinkToMissingSegment { from, to } => {
+                write!(f, "The link {from} -> {to} references a missing segment")
+            }
+            Self::StepOnMissingSegment { path, rank, node } => write!(
+                f,
+                "The step {rank} of path {path} references the missing segment {node}"
+            ),
+            Self::StepWithoutLink {
+                path,
+                rank,
+                from,
+                to,
+            } => write!(
+                f,
+                "The steps {rank} and {} of path {path} are not linked ({from} -> {to})",
+                rank + 1
+            ),
+            Self::DuplicatePathName { path } => write!(f, "The path name {path} is not unique"),
+            Self::DuplicateSegment { node } => write!(f, "The segment {node} is defined several times"),
+            Self::EmptySequence { node } => write!(f, "The segment {node} has an empty sequence"),
+            Self::InvalidBase { node, offset, base } => write!(
+                f,
+                "The segment {node} has the invalid base {base:?} at offset {offset}"
+            ),
+            Self::PositionMismatch {
+                path,
+                rank,
+                expected,
+                actual,
+            } => match actual {
+                Some(actual) => write!(
+                    f,
+                    "The step {rank} of path {path} is indexed at position {actual} instead of {expected}"
+                ),
+                None => write!(
+                    f,
+                    "The step {rank} of path {path} is missing from the position index"
+                ),
+            },
+        }
+    }
+}
+
+/// Checks the records of a GFA file, before they are turned into a graph that might not keep the problems
+pub fn validate_gfa(gfa: &GFA<usize, OptionalFields>) -> Vec<ValidationProblem> {
+    let mut problems = Vec::new();
+    let mut segments = HashSet::new();
+    for segment in &gfa.segments {
+        let node = segment.name as u64;
+        if !segments.insert(segment.name) {
+            problems.push(ValidationProblem::DuplicateSegment { node });
+            continue;
+        }
+        let sequence: &[u8] = segment.sequence.as_ref();
+        if sequence.is_empty() || sequence == b"*" {
+            problems.push(ValidationProblem::EmptySequence { node });
+        } else if let Some(offset) = sequence
+            .iter()
+            .position(|base| !matches!(base.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T' | b'N'))
+        {
+            problems.push(ValidationProblem::InvalidBase {
+                node,
+                offset: offset + 1,
+                base: char::from(sequence[offset]),
+            });
+        }
+    }
+    for link in &gfa.links {
+        if !segments.contains(&link.from_segment) || !segments.contains(&link.to_segment) {
+            problems.push(ValidationProblem::LinkToMissingSegment {
+                from: oriented_segment(link.from_segment, link.from_orient),
+                to: oriented_segment(link.to_segment, link.to_orient),
+            });
+        }
+    }
+    let mut path_names = HashSet::new();
+    for path in &gfa.paths {
+        let name = String::from_utf8_lossy(path.path_name.as_ref()).into_owned();
+        for (rank, (segment, _)) in (1..).zip(path.iter()) {
+            if !segments.contains(&segment) {
+                problems.push(ValidationProblem::StepOnMissingSegment {
+                    path: name.clone(),
+                    rank,
+                    node: segment as u64,
+                });
+            }
+        }
+        if !path_names.insert(name.clone()) {
+            problems.push(ValidationProblem::DuplicatePathName { path: name });
+        }
+    }
+    problems
+}
+
+/// The records of `gfa` a graph can be built from, or `None` if it is the case of all of them.
+///
+/// The links and the path steps on missing segments are dropped, and only the first definition of a segment is kept.
+pub fn without_broken_records(
+    gfa: &GFA<usize, OptionalFields>,
+) -> Option<GFA<usize, OptionalFields>> {
+    let mut segments = HashSet::new();
+    let has_duplicates = !gfa
+        .segments
+        .iter()
+        .all(|segment| segments.insert(segment.name));
+    let is_broken_link = |link: &Link<usize, OptionalFields>| {
+        !segments.contains(&link.from_segment) || !segments.contains(&link.to_segment)
+    };
+    let is_broken_path = |path: &GfaPath<usize, OptionalFields>| {
+        path.iter().any(|(segment, _)| !segments.contains(&segment))
+    };
+    if !has_duplicates
+        && !gfa.links.iter().any(is_broken_link)
+        && !gfa.paths.iter().any(is_broken_path)
+    {
+        return None;
+    }
+    let mut buildable = gfa.clone();
+    let mut kept = HashSet::new();
+    buildable
+        .segments
+        .retain(|segment| kept.insert(segment.name));
+    buildable.links.retain(|link| !is_broken_link(link));
+    for path in &mut buildable.paths {
+        if !is_broken_path(path) {
+            continue;
+        }
+        let steps = path
+            .iter()
+            .filter(|(segment, _)| segments.contains(segment))
+            .map(|(segment, orientation)| oriented_segment(segment, orientation))
+            .collect::<Vec<_>>();
+        path.segment_names = steps.join(",").into_bytes().into();
+        // The overlaps are between the steps that are not there anymore
+        path.overlaps.clear();
+    }
+    Some(buildable)
+}
+
+/// Checks the invariants of the graph of `storage` and returns all broken ones,
+/// including the ones found in its GFA file
+pub fn validate_graph(storage: &Storage) -> Vec<ValidationProblem> {
+    let mut problems = storage.gfa_problems.to_vec();
+    validate_paths(storage, &mut problems);
+    problems
+}
+
+fn validate_paths(storage: &Storage, problems: &mut Vec<ValidationProblem>) {
+    let graph = &storage.graph;
+    let mut names = HashSet::new();
+    for path_id in graph.path_ids() {
+        let Some(path) = path_name(storage, path_id) else {
+            continue;
+        };
+        if !names.insert(path.clone()) {
+            // Already reported from the GFA records, the path index only knows one of them
+            continue;
+        }
+        let mut expected = 1;
+        let mut previous: Option<Handle> = None;
+        let mut rank = 0;
+        let mut step = graph.path_first_step(path_id);
+        while let Some(current) = step {
+            rank += 1;
+            step = graph.path_next_step(path_id, current);
+            let Some(handle) = graph.path_handle_at_step(path_id, current) else {
+                continue;
+            };
+            if !graph.has_node(handle.id()) {
+                // Already reported from the GFA records
+                previous = None;
+                continue;
+            }
+            if let Some(previous) = previous {
+                if !graph
+                    .neighbors(previous, Direction::Right)
+                    .any(|neighbor| neighbor == handle)
+                {
+                    problems.push(ValidationProblem::StepWithoutLink {
+                        path: path.clone(),
+                        rank: rank - 1,
+                        from: oriented_name(previous),
+                        to: oriented_name(handle),
+                    });
+                }
+            }
+            let actual = storage.position_map.path_step_position(path_id, current);
+            if actual != Some(expected) {
+                problems.push(ValidationProblem::PositionMismatch {
+                    path: path.clone(),
+                    rank,
+                    expected,
+                    actual,
+                });
+            }
+            expected += graph.node_len(handle);
+            previous = Some(handle);
+        }
+    }
+}
+
+fn path_name(storage: &Storage, path_id: PathId) -> Option<String> {
+    let name = storage.graph.get_path_name(path_id)?.collect::<Vec<_>>();
+    String::from_utf8(name).ok()
+}
+
+/// The handle in the GFA walk notation, e.g. `12+`
+fn oriented_name(handle: Handle) -> String {
+    let orientation = if handle.is_reverse() { '-' } else { '+' };
+    format!("{}{orientation}", handle.unpack_number())
+}
+
+fn oriented_segment(segment: usize, orientation: Orientation) -> String {
+    let orientation = match orientation {
+        Orientation::Forward => '+',
+        Orientation::Backward => '-',
+    };
+    format!("{segment}{orientation}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_graph() {
+        let storage =
+            Storage::from_str("S\t1\tCAAATAAG\nS\t2\tA\nP\tx/a\t1+,2+\t*\nL\t1\t+\t2\t+\t0M\n")
+                .unwrap();
+        assert_eq!(validate_graph(&storage), Vec::new());
+    }
+
+    #[test]
+    fn test_invalid_graph() {
+        let storage = Storage::from_str(
+            "S\t1\tCAXATAAG\nS\t2\tA\nS\t3\tG\nP\tx/a\t1+,3+\t*\nL\t1\t+\t2\t+\t0M\n",
+        )
+        .unwrap();
+        let problems = validate_graph(&storage);
+        assert!(problems.contains(&ValidationProblem::InvalidBase {
+            node: 1,
+            offset: 3,
+            base: 'X'
+        }));
+        assert!(problems.contains(&ValidationProblem::StepWithoutLink {
+            path: "x/a".to_owned(),
+            rank: 1,
+            from: "1+".to_owned(),
+            to: "3+".to_owned()
+        }));
+        assert_eq!(problems.len(), 2, "Number of problems");
+    }
+
+    #[test]
+    fn test_missing_segments() {
+        let storage = Storage::from_str("S\t1\tA\nL\t1\t+\t2\t+\t0M\nP\tx\t1+,2+\t*\n").unwrap();
+        let problems = validate_graph(&storage);
+        assert!(problems.contains(&ValidationProblem::LinkToMissingSegment {
+            from: "1+".to_owned(),
+            to: "2+".to_owned()
+        }));
+        assert!(problems.contains(&ValidationProblem::StepOnMissingSegment {
+            path: "x".to_owned(),
+            rank: 2,
+            node: 2
+        }));
+        assert_eq!(problems.len(), 2, "Number of problems");
+    }
+
+    #[test]
+    fn test_duplicate_segments() {
+        let storage = Storage::from_str("S\t1\tA\nS\t1\tCC\n").unwrap();
+        assert_eq!(
+            validate_graph(&storage),
+            vec![ValidationProblem::DuplicateSegment { node: 1 }]
+        );
+        assert_eq!(storage.graph.node_len(Handle::pack(1, false)), 1);
+    }
+
+    #[test]
+    fn test_empty_sequence() {
+        let storage = Storage::from_str("S\t1\t*\n").unwrap();
+        assert_eq!(
+            validate_graph(&storage),
+            vec![ValidationProblem::EmptySequence { node: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_position_mismatch() {
+        let mut storage =
+            Storage::from_str("S\t1\tCAAATAAG\nS\t2\tA\nP\tx\t1+,2+\t*\nL\t1\t+\t2\t+\t0M\n")
+                .unwrap();
+        let shorter =
+            Storage::from_str("S\t1\tC\nS\t2\tA\nP\tx\t1+,2+\t*\nL\t1\t+\t2\t+\t0M\n").unwrap();
+        storage.position_map = shorter.position_map;
+        assert_eq!(
+            validate_graph(&storage),
+            vec![ValidationProblem::PositionMismatch {
+                path: "x".to_owned(),
+                rank: 2,
+                expected: 9,
+                actual: Some(2)
+            }]
+        );
+    }
+}
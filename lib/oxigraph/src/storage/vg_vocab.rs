@@ -39,6 +39,108 @@ pub mod vg {
 
     pub const NODE_PRED: NamedNodeRef<'_> =
         NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#node");
+
+    pub const ALIGNMENT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#Alignment");
+
+    pub const EDGE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#Edge");
+
+    pub const READ_NAME: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#readName");
+
+    pub const READ_LENGTH: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#readLength");
+
+    pub const MAPPING_QUALITY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#mappingQuality");
+
+    pub const ALIGNED_TO: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#alignedTo");
+
+    pub const WALK: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#walk");
+
+    pub const COVERAGE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#coverage");
+
+    pub const FROM_NODE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#fromNode");
+
+    pub const TO_NODE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#toNode");
+
+    pub const NEXT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#next");
+
+    pub const PREVIOUS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#previous");
+
+    pub const LENGTH: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#length");
+
+    pub const STEP_COUNT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#stepCount");
+
+    pub const STEP_SEQUENCE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#stepSequence");
+
+    pub const FIRST_STEP: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#firstStep");
+
+    pub const LAST_STEP: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#lastStep");
+
+    pub const SEGMENT_LENGTH: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#segmentLength");
+
+    pub const READ_COUNT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#readCount");
+
+    pub const FRAGMENT_COUNT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#fragmentCount");
+
+    pub const KMER_COUNT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#kmerCount");
+
+    pub const STABLE_SEQUENCE_NAME: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#stableSequenceName");
+
+    pub const STABLE_SEQUENCE_OFFSET: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#stableSequenceOffset");
+
+    pub const STABLE_SEQUENCE_RANK: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#stableSequenceRank");
+
+    pub const OVERLAP: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#overlap");
+
+    pub const MOTIF_MATCH: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#MotifMatch");
+
+    pub const MOTIF: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#motif");
+
+    pub const ORIENTATION: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#orientation");
+
+    pub const SHARED_NODES: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#sharedNodes");
+
+    pub const SHARED_BASES: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#sharedBases");
+
+    pub const JACCARD: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#jaccard");
+
+    pub const PRIVATE_NODES: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#privateNodes");
+
+    pub const WITHIN_STEPS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#withinSteps");
+
+    pub const WITHIN_BASES: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/vg#withinBases");
 }
 
 pub mod faldo {
@@ -53,6 +155,12 @@ pub mod faldo {
     pub const POSITION: NamedNodeRef<'_> =
         NamedNodeRef::new_unchecked("http://biohackathon.org/resource/faldo#Position");
 
+    pub const FORWARD_STRAND_POSITION: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/faldo#ForwardStrandPosition");
+
+    pub const REVERSE_STRAND_POSITION: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://biohackathon.org/resource/faldo#ReverseStrandPosition");
+
     pub const BEGIN: NamedNodeRef<'_> =
         NamedNodeRef::new_unchecked("http://biohackathon.org/resource/faldo#begin");
 
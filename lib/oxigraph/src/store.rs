@@ -37,9 +37,14 @@ use crate::storage::numeric_encoder::{Decoder, EncodedQuad, EncodedTerm};
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
 use crate::storage::StorageBulkLoader;
 use crate::storage::{
-    ChainedDecodingQuadIterator, DecodingGraphIterator, Storage, StorageReader, StorageWriter,
+    ChainedDecodingQuadIterator, DecodingGraphIterator, Storage, StorageGfaLoader, StorageReader,
+    StorageWriter,
+};
+pub use crate::storage::{
+    CorruptionError, GfaLoaderError, GfaParseError, GraphSize, GraphStatistics, LoaderError,
+    PathComparison, RdfProfile, SerializerError, SimilarityMatrix, StorageError, Subgraph,
+    SubgraphRadius, SubgraphSeed, ValidationError, ValidationProblem,
 };
-pub use crate::storage::{CorruptionError, LoaderError, SerializerError, StorageError};
 use std::error::Error;
 use std::io::{Read, Write};
 #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
@@ -100,6 +105,8 @@ impl Store {
     /// Only one read-write [`Store`] can exist at the same time.
     /// If you want to have extra [`Store`] instance opened on a same data
     /// use [`Store::open_secondary`] or [`Store::open_read_only`].
+    ///
+    /// The graph format is chosen by the file extension, see [`GfaLoader::load_from_path`].
     #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
     pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
         Ok(Self {
@@ -156,6 +163,93 @@ impl Store {
         })
     }
 
+    /// Creates a [`GfaLoader`] reading a [GFA](https://github.com/GFA-spec/GFA-spec) file into a new [`Store`].
+    ///
+    /// Unlike [`Store::open`], it reads from any [`Read`] implementation,
+    /// reports progress and might skip invalid records.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    ///
+    /// let file = b"S\t1\tCAAATAAG\nS\tx\tA\nS\t2\tA\nL\t1\t+\t2\t+\t0M\n";
+    /// let store = Store::gfa_loader()
+    ///     .on_parse_error(|e| {
+    ///         assert_eq!(e.line(), 2);
+    ///         Ok(())
+    ///     })
+    ///     .load_from_read(file.as_ref())?;
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn gfa_loader() -> GfaLoader {
+        GfaLoader {
+            storage: StorageGfaLoader::new(),
+        }
+    }
+
+    /// Names the resources of the graph following the conventions of `profile`.
+    ///
+    /// [`RdfProfile::Vg`] gives the same IRIs as the materialised RDF of vg,
+    /// so that existing queries and federated endpoints keep working.
+    /// Only the IRIs change, the triples the materialised RDF lacks are still generated.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::model::*;
+    /// use oxigraph::store::{RdfProfile, Store};
+    ///
+    /// let store = Store::gfa_loader()
+    ///     .load_from_read(b"S\t1\tCAAATAAG\n".as_ref())?
+    ///     .with_rdf_profile(RdfProfile::Vg);
+    /// let node = NamedNodeRef::new("http://example.org/vg/node/1")?;
+    /// assert_eq!(store.quads_for_pattern(Some(node.into()), None, None, None).count(), 2);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[must_use]
+    pub fn with_rdf_profile(self, profile: RdfProfile) -> Self {
+        Self {
+            storage: self.storage.with_rdf_profile(profile),
+        }
+    }
+
+    /// Names the nodes, paths and steps of the graph under an other IRI than the one of its [`RdfProfile`].
+    ///
+    /// A trailing slash of the IRI is ignored.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::model::*;
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::gfa_loader()
+    ///     .load_from_read(b"S\t1\tCAAATAAG\n".as_ref())?
+    ///     .with_base_iri("http://example.com/hprc/");
+    /// assert_eq!(store.base_iri(), "http://example.com/hprc");
+    /// let node = NamedNodeRef::new("http://example.com/hprc/node/1")?;
+    /// assert_eq!(store.quads_for_pattern(Some(node.into()), None, None, None).count(), 2);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[must_use]
+    pub fn with_base_iri(self, base_iri: &str) -> Self {
+        Self {
+            storage: self.storage.with_base(base_iri),
+        }
+    }
+
+    /// The IRI under which the nodes, paths and steps of the graph are named, e.g. `https://example.org`.
+    pub fn base_iri(&self) -> &str {
+        self.storage.base()
+    }
+
+    /// Adds the read alignments of a [GAF](https://github.com/lh3/gfatools/blob/master/doc/rGFA.md#the-graph-alignment-format-gaf) file to the [`Store`].
+    ///
+    /// Each alignment is exposed as a `vg:Alignment` with its read name, mapping quality and walk.
+    /// Nodes and traversed edges get a `vg:coverage` count of the alignments going through them.
+    #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
+    pub fn load_alignments(&mut self, path: impl AsRef<Path>) -> Result<(), StorageError> {
+        self.storage.load_alignments(path.as_ref())
+    }
+
     /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/).
     ///
     /// Usage example:
@@ -1021,11 +1115,172 @@ impl Store {
         }
     }
 
-    /// Validates that all the store invariants held in the data
-    #[doc(hidden)]
-    pub fn validate(&self) -> Result<(), StorageError> {
+    /// Validates that the graph is a consistent pangenome.
+    ///
+    /// It looks for links and path steps referencing missing segments, consecutive path steps without link,
+    /// duplicate path names, empty sequences, characters other than `ACGTN`
+    /// and step positions that do not match the segment lengths.
+    /// All the problems found are returned in [`ValidationError::Invalid`].
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::{Store, ValidationError, ValidationProblem};
+    ///
+    /// let store = Store::gfa_loader().load_from_read(b"S\t1\tCAAXTAAG\n".as_ref())?;
+    /// let Err(ValidationError::Invalid(problems)) = store.validate() else {
+    ///     panic!("The graph should be invalid");
+    /// };
+    /// assert_eq!(
+    ///     problems,
+    ///     [ValidationProblem::InvalidBase {
+    ///         node: 1,
+    ///         offset: 4,
+    ///         base: 'X'
+    ///     }]
+    /// );
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
         self.storage.snapshot().validate()
     }
+
+    /// Compares each of the given paths with each other, e.g. to cluster haplotypes.
+    ///
+    /// The comparisons count the nodes and bases shared by the paths from an index of the nodes visited by each path,
+    /// built on first use, instead of joining their steps.
+    /// The same numbers are available in SPARQL with the `vg:sharedNodes`, `vg:sharedBases`, `vg:jaccard`
+    /// and `vg:privateNodes` functions of the path IRIs.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::gfa_loader().load_from_read(
+    ///     b"S\t1\tCAAATAAG\nS\t2\tA\nS\t3\tGG\nP\tx\t1+,2+\t*\nP\ty\t1+,3+\t*\n".as_ref(),
+    /// )?;
+    /// let matrix = store.path_similarity(&["x", "y"])?;
+    /// let comparison = matrix.get(0, 1).unwrap();
+    /// assert_eq!(comparison.shared_nodes, 1);
+    /// assert_eq!(comparison.shared_bases, 8);
+    /// assert_eq!(comparison.jaccard(), 8. / 11.);
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn path_similarity(&self, paths: &[&str]) -> Result<SimilarityMatrix, StorageError> {
+        self.storage.snapshot().path_similarity(paths)
+    }
+
+    /// Extracts the subgraph induced by the nodes within `radius` of a node or of a path interval.
+    ///
+    /// The subgraph contains these nodes, the edges between them and the fragments of the paths walking through them.
+    /// It is computed directly on the graph and can be written as vg triples with [`Subgraph::write_rdf`] or as GFA with [`Subgraph::write_gfa`].
+    /// In SPARQL, `vg:withinSteps(?node, ?seed, ?steps)` and `vg:withinBases(?node, ?seed, ?bases)`
+    /// test if a node IRI is in the neighbourhood of a seed node IRI.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::{Store, SubgraphRadius, SubgraphSeed};
+    ///
+    /// let store = Store::gfa_loader().load_from_read(
+    ///     b"S\t1\tCAAATAAG\nS\t2\tA\nS\t3\tG\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\n".as_ref(),
+    /// )?;
+    /// let subgraph = store.subgraph(&SubgraphSeed::Node(3), SubgraphRadius::Steps(1))?;
+    /// assert_eq!(subgraph.nodes().collect::<Vec<_>>(), [2, 3]);
+    /// assert_eq!(
+    ///     subgraph.write_gfa(Vec::new())?,
+    ///     b"H\tVN:Z:1.0\nS\t2\tA\nS\t3\tG\nL\t2\t+\t3\t+\t0M\n"
+    /// );
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn subgraph(
+        &self,
+        seed: &SubgraphSeed,
+        radius: SubgraphRadius,
+    ) -> Result<Subgraph, StorageError> {
+        self.storage.snapshot().subgraph(seed, radius)
+    }
+
+    /// Returns the number of triples, nodes, paths and edges of the graph.
+    ///
+    /// The triple count requires generating all the triples once, it is cached for the following calls.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::model::GraphNameRef;
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::gfa_loader().load_from_read(
+    ///     b"S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tx\t1+,2+\t*\n".as_ref(),
+    /// )?;
+    /// let statistics = store.statistics()?;
+    /// assert_eq!(statistics.nodes, 2);
+    /// assert_eq!(statistics.paths, 1);
+    /// assert_eq!(statistics.edges, 1);
+    /// assert_eq!(
+    ///     statistics.triples,
+    ///     store
+    ///         .quads_for_pattern(None, None, None, Some(GraphNameRef::DefaultGraph))
+    ///         .count()
+    /// );
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn statistics(&self) -> Result<GraphStatistics, StorageError> {
+        self.storage.snapshot().statistics()
+    }
+
+    /// Returns the number of nodes, paths, edges and steps of the graph, without generating its triples.
+    ///
+    /// The number of triples is only given once it has been counted by [`Store::statistics`].
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::gfa_loader().load_from_read(
+    ///     b"S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tx\t1+,2+\t*\n".as_ref(),
+    /// )?;
+    /// let size = store.graph_size();
+    /// assert_eq!((size.nodes, size.paths, size.edges, size.steps), (2, 1, 1, 2));
+    /// assert_eq!(size.triples, None);
+    /// assert_eq!(store.graph_size().triples, Some(store.statistics()?.triples));
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn graph_size(&self) -> GraphSize {
+        self.storage.snapshot().graph_size()
+    }
+
+    /// Estimates the number of triples of the default graph matching a pattern, without enumerating them.
+    ///
+    /// Patterns on the vg and faldo predicates are estimated from the size of the graph,
+    /// the other ones fall back to the number of triples of the graph.
+    /// It is the one given by [`Store::statistics`] if it has already been counted, an estimation otherwise,
+    /// so that this method never generates the triples.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::model::vocab::rdf;
+    /// use oxigraph::model::NamedNodeRef;
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::gfa_loader().load_from_read(b"S\t1\tA\nS\t2\tC\n".as_ref())?;
+    /// let node = NamedNodeRef::new("http://biohackathon.org/resource/vg#Node")?;
+    /// assert_eq!(
+    ///     store.estimate_pattern_count(None, Some(rdf::TYPE), Some(node.into()))?,
+    ///     2
+    /// );
+    /// # Result::<_, Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn estimate_pattern_count(
+        &self,
+        subject: Option<SubjectRef<'_>>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<TermRef<'_>>,
+    ) -> Result<usize, StorageError> {
+        self.storage.snapshot().estimate_pattern_count(
+            subject.map(Into::into),
+            predicate.map(Into::into),
+            object,
+        )
+    }
 }
 
 impl fmt::Display for Store {
@@ -1936,6 +2191,56 @@ impl BulkLoader {
     }
 }
 
+/// A loader building a [`Store`] from a [GFA](https://github.com/GFA-spec/GFA-spec) file.
+///
+/// The file is read line by line and might be compressed with gzip or zstd,
+/// the compression is detected from the first bytes.
+///
+/// Invalid records make the loading fail with a [`GfaParseError`] giving their line and column,
+/// unless an [`on_parse_error`](Self::on_parse_error) callback chooses to skip them.
+#[must_use]
+pub struct GfaLoader {
+    storage: StorageGfaLoader,
+}
+
+impl GfaLoader {
+    /// Adds a `callback` evaluated from time to time with the number of parsed records.
+    pub fn on_progress(mut self, callback: impl Fn(u64) + 'static) -> Self {
+        self.storage = self.storage.on_progress(callback);
+        self
+    }
+
+    /// Adds a `callback` catching all parse errors and choosing if the parsing should continue
+    /// by returning `Ok` or fail by returning `Err`.
+    ///
+    /// By default the parsing fails.
+    pub fn on_parse_error(
+        mut self,
+        callback: impl Fn(GfaParseError) -> Result<(), GfaParseError> + 'static,
+    ) -> Self {
+        self.storage = self.storage.on_parse_error(callback);
+        self
+    }
+
+    /// Loads a GFA file, possibly compressed, from a [`Read`] implementation like stdin.
+    pub fn load_from_read(&self, read: impl Read) -> Result<Store, GfaLoaderError> {
+        Ok(Store {
+            storage: self.storage.load(read)?,
+        })
+    }
+
+    /// Loads a graph file from the file system.
+    ///
+    /// The format is chosen by the file extension: `.gbz` files of vg are read directly,
+    /// anything else is read as a GFA file, possibly compressed.
+    #[cfg(all(not(target_family = "wasm"), feature = "rocksdb"))]
+    pub fn load_from_path(&self, path: impl AsRef<Path>) -> Result<Store, GfaLoaderError> {
+        Ok(Store {
+            storage: self.storage.load_path(path.as_ref())?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::panic_in_result_fn)]
@@ -2155,4 +2460,104 @@ mod tests {
 
         Ok(())
     }
+
+    /// The solutions of a query as sorted text, to compare them whatever their order
+    fn sorted_solutions(
+        store: &Store,
+        query: &str,
+        options: QueryOptions,
+    ) -> Result<Vec<String>, EvaluationError> {
+        let QueryResults::Solutions(solutions) = store.query_opt(query, options)? else {
+            return Ok(Vec::new());
+        };
+        let mut solutions = solutions
+            .map(|solution| {
+                Ok(solution?
+                    .iter()
+                    .map(|(variable, term)| format!("{variable}={term}"))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            })
+            .collect::<Result<Vec<_>, EvaluationError>>()?;
+        solutions.sort();
+        Ok(solutions)
+    }
+
+    #[test]
+    fn parallel_aggregates() -> Result<(), Box<dyn Error>> {
+        let store = Store::gfa_loader().load_from_read(include_str!("../../t.gfa").as_bytes())?;
+        for query in [
+            "PREFIX vg: <http://biohackathon.org/resource/vg#> \
+             SELECT ?path (COUNT(?step) AS ?steps) (SUM(?rank) AS ?sum) (MIN(?rank) AS ?min) (MAX(?rank) AS ?max) \
+             WHERE { ?step vg:path ?path ; vg:rank ?rank } GROUP BY ?path",
+            "PREFIX vg: <http://biohackathon.org/resource/vg#> \
+             SELECT (COUNT(*) AS ?c) WHERE { ?node a vg:Node }",
+            "PREFIX vg: <http://biohackathon.org/resource/vg#> \
+             SELECT ?node (COUNT(?other) AS ?c) WHERE { ?node vg:links ?other } GROUP BY ?node",
+            // No solution at all, in any partition
+            "SELECT (COUNT(*) AS ?c) (MIN(?o) AS ?min) WHERE { ?s <http://example.com/p> ?o }",
+            // Not combinable from partial aggregates, evaluated on the calling thread
+            "PREFIX vg: <http://biohackathon.org/resource/vg#> \
+             SELECT (AVG(?rank) AS ?avg) (COUNT(DISTINCT ?path) AS ?paths) WHERE { ?step vg:path ?path ; vg:rank ?rank }",
+        ] {
+            let expected = sorted_solutions(&store, query, QueryOptions::default())?;
+            assert!(!expected.is_empty(), "No solution for {query}");
+            for num_threads in [2, 4, 7] {
+                assert_eq!(
+                    sorted_solutions(
+                        &store,
+                        query,
+                        QueryOptions::default().with_num_threads(num_threads)
+                    )?,
+                    expected,
+                    "{query} on {num_threads} threads"
+                );
+            }
+        }
+
+        // The explanation describes the partitioned evaluation that ran
+        let query = "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p ?o }";
+        let (_, explanation) =
+            store.explain_query_opt(query, QueryOptions::default().with_num_threads(4), true)?;
+        assert!(format!("{explanation:?}").contains("PartitionedAggregate(partitions = "));
+        let (_, explanation) = store.explain_query_opt(query, QueryOptions::default(), true)?;
+        assert!(!format!("{explanation:?}").contains("PartitionedAggregate"));
+        Ok(())
+    }
+
+    #[test]
+    fn path_similarity_functions() -> Result<(), Box<dyn Error>> {
+        let store = Store::gfa_loader().load_from_read(
+            b"S\t1\tCAAATAAG\nS\t2\tA\nS\t3\tGG\nP\tx\t1+,2+\t*\nP\ty\t1+,3+\t*\nP\tz\t3+,3-\t*\n"
+                .as_ref(),
+        )?;
+        assert_eq!(
+            sorted_solutions(
+                &store,
+                "PREFIX vg: <http://biohackathon.org/resource/vg#> \
+                 PREFIX path: <https://example.org/path/> \
+                 SELECT ?a ?b (STR(vg:sharedNodes(?a, ?b)) AS ?nodes) (STR(vg:sharedBases(?a, ?b)) AS ?bases) (STR(vg:privateNodes(?a)) AS ?private) \
+                 WHERE { VALUES (?a ?b) { (path:x path:y) (path:y path:z) (path:z path:z) (path:x <https://example.org/node/1>) } }",
+                QueryOptions::default()
+            )?,
+            [
+                // Not a path
+                "?a=<https://example.org/path/x> ?b=<https://example.org/node/1> ?private=\"1\"",
+                "?a=<https://example.org/path/x> ?b=<https://example.org/path/y> ?nodes=\"1\" ?bases=\"8\" ?private=\"1\"",
+                "?a=<https://example.org/path/y> ?b=<https://example.org/path/z> ?nodes=\"1\" ?bases=\"2\" ?private=\"0\"",
+                // The nodes visited twice are only counted once
+                "?a=<https://example.org/path/z> ?b=<https://example.org/path/z> ?nodes=\"1\" ?bases=\"2\" ?private=\"0\"",
+            ]
+        );
+        let QueryResults::Boolean(jaccard) = store.query(
+            "PREFIX vg: <http://biohackathon.org/resource/vg#> \
+             PREFIX path: <https://example.org/path/> \
+             ASK { FILTER(vg:jaccard(path:x, path:y) = 8e0 / 11e0 && vg:jaccard(path:y, path:z) = 0.2e0 && COALESCE(vg:jaccard(path:x, path:w), -1) = -1) }",
+        )?
+        else {
+            return Err("ASK query without a boolean result".into());
+        };
+        assert!(jaccard);
+        Ok(())
+    }
 }
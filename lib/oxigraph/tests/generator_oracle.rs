@@ -0,0 +1,171 @@
+#![cfg(test)]
+#![allow(clippy::panic_in_result_fn)]
+
+//! Differential tests of the triples generated from GFA files.
+//!
+//! The full scan of each fixture is materialised into a [`Dataset`] that is then used as an oracle:
+//! every pattern built from the data, or from terms that are not in it,
+//! must return exactly the quads of the dataset matching it.
+
+use oxigraph::model::*;
+use oxigraph::store::Store;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+const FIXTURES: [&str; 6] = [
+    "t_red.gfa",
+    "t_double.gfa",
+    "t_step.gfa",
+    "t_reverse.gfa",
+    "t_tags.gfa",
+    "t.gfa",
+];
+
+type Pattern = (Option<Subject>, Option<NamedNode>, Option<Term>);
+
+fn load_fixture(name: &str) -> Result<Store, Box<dyn Error>> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(name);
+    Ok(Store::gfa_loader().load_from_read(File::open(path)?)?)
+}
+
+/// Terms that do not appear in any fixture, some of them looking like the IRIs of the graph
+fn missing_terms() -> Vec<Term> {
+    vec![
+        NamedNode::new_unchecked("http://example.com/missing").into(),
+        NamedNode::new_unchecked("http://example.com/node/1").into(),
+        NamedNode::new_unchecked("http://example.com/path/x/a/position/1").into(),
+        NamedNode::new_unchecked("https://example.org").into(),
+        NamedNode::new_unchecked("https://example.org/node/999").into(),
+        NamedNode::new_unchecked("https://example.org/node/1/x").into(),
+        NamedNode::new_unchecked("https://example.org/edge/1+2é").into(),
+        NamedNode::new_unchecked("https://example.org/path/missing").into(),
+        NamedNode::new_unchecked("https://example.org/path/missing/step/1").into(),
+        NamedNode::new_unchecked("https://example.org/path/x/a/step/999").into(),
+        NamedNode::new_unchecked("https://example.org/path/x/a/position/999").into(),
+        NamedNode::new_unchecked("http://biohackathon.org/resource/vg#missing").into(),
+        Literal::new_simple_literal("missing").into(),
+        Literal::from(-1).into(),
+    ]
+}
+
+/// All the bound/unbound combinations of the quads of `dataset` and of the missing terms
+fn patterns(dataset: &Dataset) -> HashSet<Pattern> {
+    let mut patterns = HashSet::new();
+    for quad in dataset {
+        let subject = quad.subject.into_owned();
+        let predicate = quad.predicate.into_owned();
+        let object = quad.object.into_owned();
+        for mask in 0..8 {
+            patterns.insert((
+                (mask & 1 != 0).then(|| subject.clone()),
+                (mask & 2 != 0).then(|| predicate.clone()),
+                (mask & 4 != 0).then(|| object.clone()),
+            ));
+        }
+        for missing in missing_terms() {
+            patterns.insert((
+                Some(subject.clone()),
+                Some(predicate.clone()),
+                Some(missing.clone()),
+            ));
+            patterns.insert((Some(subject.clone()), None, Some(missing.clone())));
+            patterns.insert((None, Some(predicate.clone()), Some(missing.clone())));
+            if let Term::NamedNode(missing) = &missing {
+                patterns.insert((Some(missing.clone().into()), Some(predicate.clone()), None));
+                patterns.insert((Some(subject.clone()), Some(missing.clone()), None));
+                patterns.insert((None, Some(missing.clone()), Some(object.clone())));
+            }
+        }
+    }
+    for missing in missing_terms() {
+        patterns.insert((None, None, Some(missing.clone())));
+        if let Term::NamedNode(missing) = missing {
+            patterns.insert((Some(missing.clone().into()), None, None));
+            patterns.insert((None, Some(missing), None));
+        }
+    }
+    patterns
+}
+
+fn matches(quad: QuadRef<'_>, (subject, predicate, object): &Pattern) -> bool {
+    subject
+        .as_ref()
+        .map_or(true, |s| s.as_ref() == quad.subject)
+        && predicate
+            .as_ref()
+            .map_or(true, |p| p.as_ref() == quad.predicate)
+        && object.as_ref().map_or(true, |o| o.as_ref() == quad.object)
+}
+
+/// Describes how the answer of `store` to `pattern` differs from the one of `dataset`
+fn compare(
+    store: &Store,
+    dataset: &Dataset,
+    pattern: &Pattern,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let (subject, predicate, object) = pattern;
+    let actual = store
+        .quads_for_pattern(
+            subject.as_ref().map(Subject::as_ref),
+            predicate.as_ref().map(NamedNode::as_ref),
+            object.as_ref().map(Term::as_ref),
+            Some(GraphNameRef::DefaultGraph),
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    let actual_set = actual.iter().cloned().collect::<HashSet<_>>();
+    let expected = dataset
+        .iter()
+        .filter(|quad| matches(*quad, pattern))
+        .map(QuadRef::into_owned)
+        .collect::<HashSet<_>>();
+    let mut problems = Vec::new();
+    if actual.len() != actual_set.len() {
+        problems.push(format!("{} duplicates", actual.len() - actual_set.len()));
+    }
+    for quad in expected.difference(&actual_set) {
+        problems.push(format!("missing {quad}"));
+    }
+    for quad in actual_set.difference(&expected) {
+        problems.push(format!("unexpected {quad}"));
+    }
+    Ok((!problems.is_empty()).then(|| {
+        format!(
+            "{} {} {}: {}",
+            subject
+                .as_ref()
+                .map_or("?s".to_owned(), ToString::to_string),
+            predicate
+                .as_ref()
+                .map_or("?p".to_owned(), ToString::to_string),
+            object.as_ref().map_or("?o".to_owned(), ToString::to_string),
+            problems.join(", ")
+        )
+    }))
+}
+
+#[test]
+fn test_generator_against_materialised_dataset() -> Result<(), Box<dyn Error>> {
+    let mut failures = Vec::new();
+    for fixture in FIXTURES {
+        let store = load_fixture(fixture)?;
+        let mut dataset = Dataset::new();
+        for quad in store.iter() {
+            dataset.insert(&quad?);
+        }
+        assert!(!dataset.is_empty(), "{fixture} has no triples");
+        for pattern in patterns(&dataset) {
+            if let Some(failure) = compare(&store, &dataset, &pattern)? {
+                failures.push(format!("{fixture}: {failure}"));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} patterns differ from the materialised dataset:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+    Ok(())
+}
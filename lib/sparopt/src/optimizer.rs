@@ -1,6 +1,7 @@
 use crate::algebra::{
     Expression, GraphPattern, JoinAlgorithm, LeftJoinAlgorithm, MinusAlgorithm, OrderExpression,
 };
+use crate::statistics::{NoStatistics, PatternTerm, Statistics};
 use crate::type_inference::{
     infer_expression_type, infer_graph_pattern_types, VariableType, VariableTypes,
 };
@@ -13,8 +14,17 @@ pub struct Optimizer;
 
 impl Optimizer {
     pub fn optimize_graph_pattern(pattern: GraphPattern) -> GraphPattern {
+        Self::optimize_graph_pattern_with_statistics(pattern, &NoStatistics)
+    }
+
+    /// Same as [`optimize_graph_pattern`](Self::optimize_graph_pattern) but joins are ordered
+    /// using the cardinalities provided by `statistics` when available.
+    pub fn optimize_graph_pattern_with_statistics(
+        pattern: GraphPattern,
+        statistics: &dyn Statistics,
+    ) -> GraphPattern {
         let pattern = Self::normalize_pattern(pattern, &VariableTypes::default());
-        let pattern = Self::reorder_joins(pattern, &VariableTypes::default());
+        let pattern = Self::reorder_joins(pattern, &VariableTypes::default(), statistics);
         Self::push_filters(pattern, Vec::new(), &VariableTypes::default())
     }
 
@@ -479,7 +489,11 @@ impl Optimizer {
         }
     }
 
-    fn reorder_joins(pattern: GraphPattern, input_types: &VariableTypes) -> GraphPattern {
+    fn reorder_joins(
+        pattern: GraphPattern,
+        input_types: &VariableTypes,
+        statistics: &dyn Statistics,
+    ) -> GraphPattern {
         match pattern {
             GraphPattern::QuadPattern { .. }
             | GraphPattern::Path { .. }
@@ -512,7 +526,9 @@ impl Optimizer {
                     .enumerate()
                     .filter(|(_, v)| **v)
                     .map(|(i, _)| i)
-                    .min_by_key(|i| estimate_graph_pattern_size(&to_reorder[*i], input_types))
+                    .min_by_key(|i| {
+                        estimate_graph_pattern_size(&to_reorder[*i], input_types, statistics)
+                    })
                 {
                     not_yet_reordered_ids[next_entry_id] = false; // It's now done
                     let mut output = to_reorder[next_entry_id].clone();
@@ -540,6 +556,7 @@ impl Optimizer {
                                     &output_types,
                                     &to_reorder[*i],
                                     input_types,
+                                    statistics,
                                 )
                             } else {
                                 estimate_join_cost(
@@ -553,6 +570,7 @@ impl Optimizer {
                                         ),
                                     },
                                     input_types,
+                                    statistics,
                                 )
                             }
                         })
@@ -604,8 +622,8 @@ impl Optimizer {
                             &infer_graph_pattern_types(&right, input_types.clone()),
                             input_types,
                         );
-                        if estimate_graph_pattern_size(&left, input_types)
-                            <= estimate_graph_pattern_size(&right, input_types)
+                        if estimate_graph_pattern_size(&left, input_types, statistics)
+                            <= estimate_graph_pattern_size(&right, input_types, statistics)
                         {
                             GraphPattern::join(
                                 left,
@@ -626,8 +644,8 @@ impl Optimizer {
             GraphPattern::Lateral { left, right } => {
                 let left_types = infer_graph_pattern_types(&left, input_types.clone());
                 GraphPattern::lateral(
-                    Self::reorder_joins(*left, input_types),
-                    Self::reorder_joins(*right, &left_types),
+                    Self::reorder_joins(*left, input_types, statistics),
+                    Self::reorder_joins(*right, &left_types, statistics),
                 )
             }
             GraphPattern::LeftJoin {
@@ -636,9 +654,9 @@ impl Optimizer {
                 expression,
                 ..
             } => {
-                let left = Self::reorder_joins(*left, input_types);
+                let left = Self::reorder_joins(*left, input_types, statistics);
                 let left_types = infer_graph_pattern_types(&left, input_types.clone());
-                let right = Self::reorder_joins(*right, input_types);
+                let right = Self::reorder_joins(*right, input_types, statistics);
                 let right_types = infer_graph_pattern_types(&right, input_types.clone());
                 #[cfg(feature = "sep-0006")]
                 {
@@ -666,9 +684,9 @@ impl Optimizer {
                 )
             }
             GraphPattern::Minus { left, right, .. } => {
-                let left = Self::reorder_joins(*left, input_types);
+                let left = Self::reorder_joins(*left, input_types, statistics);
                 let left_types = infer_graph_pattern_types(&left, input_types.clone());
-                let right = Self::reorder_joins(*right, input_types);
+                let right = Self::reorder_joins(*right, input_types, statistics);
                 let right_types = infer_graph_pattern_types(&right, input_types.clone());
                 GraphPattern::minus(
                     left,
@@ -683,46 +701,57 @@ impl Optimizer {
                 expression,
                 variable,
             } => GraphPattern::extend(
-                Self::reorder_joins(*inner, input_types),
+                Self::reorder_joins(*inner, input_types, statistics),
                 variable,
                 expression,
             ),
-            GraphPattern::Filter { inner, expression } => {
-                GraphPattern::filter(Self::reorder_joins(*inner, input_types), expression)
-            }
+            GraphPattern::Filter { inner, expression } => GraphPattern::filter(
+                Self::reorder_joins(*inner, input_types, statistics),
+                expression,
+            ),
             GraphPattern::Union { inner } => GraphPattern::union_all(
                 inner
                     .into_iter()
-                    .map(|c| Self::reorder_joins(c, input_types)),
+                    .map(|c| Self::reorder_joins(c, input_types, statistics)),
             ),
             GraphPattern::Slice {
                 inner,
                 start,
                 length,
-            } => GraphPattern::slice(Self::reorder_joins(*inner, input_types), start, length),
+            } => GraphPattern::slice(
+                Self::reorder_joins(*inner, input_types, statistics),
+                start,
+                length,
+            ),
             GraphPattern::Distinct { inner } => {
-                GraphPattern::distinct(Self::reorder_joins(*inner, input_types))
+                GraphPattern::distinct(Self::reorder_joins(*inner, input_types, statistics))
             }
             GraphPattern::Reduced { inner } => {
-                GraphPattern::reduced(Self::reorder_joins(*inner, input_types))
-            }
-            GraphPattern::Project { inner, variables } => {
-                GraphPattern::project(Self::reorder_joins(*inner, input_types), variables)
-            }
-            GraphPattern::OrderBy { inner, expression } => {
-                GraphPattern::order_by(Self::reorder_joins(*inner, input_types), expression)
+                GraphPattern::reduced(Self::reorder_joins(*inner, input_types, statistics))
             }
+            GraphPattern::Project { inner, variables } => GraphPattern::project(
+                Self::reorder_joins(*inner, input_types, statistics),
+                variables,
+            ),
+            GraphPattern::OrderBy { inner, expression } => GraphPattern::order_by(
+                Self::reorder_joins(*inner, input_types, statistics),
+                expression,
+            ),
             GraphPattern::Service {
                 inner,
                 name,
                 silent,
-            } => GraphPattern::service(Self::reorder_joins(*inner, input_types), name, silent),
+            } => GraphPattern::service(
+                Self::reorder_joins(*inner, input_types, statistics),
+                name,
+                silent,
+            ),
             GraphPattern::Group {
                 inner,
                 variables,
                 aggregates,
             } => GraphPattern::group(
-                Self::reorder_joins(*inner, input_types),
+                Self::reorder_joins(*inner, input_types, statistics),
                 variables,
                 aggregates,
             ),
@@ -891,7 +920,11 @@ fn join_key_variables(
         .collect()
 }
 
-fn estimate_graph_pattern_size(pattern: &GraphPattern, input_types: &VariableTypes) -> usize {
+fn estimate_graph_pattern_size(
+    pattern: &GraphPattern,
+    input_types: &VariableTypes,
+    statistics: &dyn Statistics,
+) -> usize {
     match pattern {
         GraphPattern::Values { bindings, .. } => bindings.len(),
         GraphPattern::QuadPattern {
@@ -899,11 +932,20 @@ fn estimate_graph_pattern_size(pattern: &GraphPattern, input_types: &VariableTyp
             predicate,
             object,
             ..
-        } => estimate_triple_pattern_size(
-            is_term_pattern_bound(subject, input_types),
-            is_named_node_pattern_bound(predicate, input_types),
-            is_term_pattern_bound(object, input_types),
-        ),
+        } => {
+            let subject = term_pattern_statistics_term(subject, input_types);
+            let predicate = named_node_pattern_statistics_term(predicate, input_types);
+            let object = term_pattern_statistics_term(object, input_types);
+            statistics
+                .triple_pattern_cardinality(subject, predicate, object)
+                .unwrap_or_else(|| {
+                    estimate_triple_pattern_size(
+                        subject.is_bound(),
+                        predicate.is_bound(),
+                        object.is_bound(),
+                    )
+                })
+        }
         GraphPattern::Path {
             subject,
             path,
@@ -913,12 +955,13 @@ fn estimate_graph_pattern_size(pattern: &GraphPattern, input_types: &VariableTyp
             is_term_pattern_bound(subject, input_types),
             path,
             is_term_pattern_bound(object, input_types),
+            statistics,
         ),
         GraphPattern::Join {
             left,
             right,
             algorithm,
-        } => estimate_join_cost(left, right, algorithm, input_types),
+        } => estimate_join_cost(left, right, algorithm, input_types, statistics),
         GraphPattern::LeftJoin {
             left,
             right,
@@ -926,13 +969,14 @@ fn estimate_graph_pattern_size(pattern: &GraphPattern, input_types: &VariableTyp
             ..
         } => match algorithm {
             LeftJoinAlgorithm::HashBuildRightProbeLeft { keys } => {
-                let left_size = estimate_graph_pattern_size(left, input_types);
+                let left_size = estimate_graph_pattern_size(left, input_types, statistics);
                 max(
                     left_size,
                     left_size
                         .saturating_mul(estimate_graph_pattern_size(
                             right,
                             &infer_graph_pattern_types(right, input_types.clone()),
+                            statistics,
                         ))
                         .saturating_div(1_000_usize.saturating_pow(keys.len().try_into().unwrap())),
                 )
@@ -944,12 +988,15 @@ fn estimate_graph_pattern_size(pattern: &GraphPattern, input_types: &VariableTyp
             &infer_graph_pattern_types(left, input_types.clone()),
             right,
             input_types,
+            statistics,
         ),
         GraphPattern::Union { inner } => inner
             .iter()
-            .map(|inner| estimate_graph_pattern_size(inner, input_types))
+            .map(|inner| estimate_graph_pattern_size(inner, input_types, statistics))
             .fold(0, usize::saturating_add),
-        GraphPattern::Minus { left, .. } => estimate_graph_pattern_size(left, input_types),
+        GraphPattern::Minus { left, .. } => {
+            estimate_graph_pattern_size(left, input_types, statistics)
+        }
         GraphPattern::Filter { inner, .. }
         | GraphPattern::Extend { inner, .. }
         | GraphPattern::OrderBy { inner, .. }
@@ -957,13 +1004,15 @@ fn estimate_graph_pattern_size(pattern: &GraphPattern, input_types: &VariableTyp
         | GraphPattern::Distinct { inner, .. }
         | GraphPattern::Reduced { inner, .. }
         | GraphPattern::Group { inner, .. }
-        | GraphPattern::Service { inner, .. } => estimate_graph_pattern_size(inner, input_types),
+        | GraphPattern::Service { inner, .. } => {
+            estimate_graph_pattern_size(inner, input_types, statistics)
+        }
         GraphPattern::Slice {
             inner,
             start,
             length,
         } => {
-            let inner = estimate_graph_pattern_size(inner, input_types);
+            let inner = estimate_graph_pattern_size(inner, input_types, statistics);
             if let Some(length) = length {
                 min(inner, *length - *start)
             } else {
@@ -978,11 +1027,12 @@ fn estimate_join_cost(
     right: &GraphPattern,
     algorithm: &JoinAlgorithm,
     input_types: &VariableTypes,
+    statistics: &dyn Statistics,
 ) -> usize {
     match algorithm {
         JoinAlgorithm::HashBuildLeftProbeRight { keys } => {
-            estimate_graph_pattern_size(left, input_types)
-                .saturating_mul(estimate_graph_pattern_size(right, input_types))
+            estimate_graph_pattern_size(left, input_types, statistics)
+                .saturating_mul(estimate_graph_pattern_size(right, input_types, statistics))
                 .saturating_div(1_000_usize.saturating_pow(keys.len().try_into().unwrap()))
         }
     }
@@ -992,9 +1042,10 @@ fn estimate_lateral_cost(
     left_types: &VariableTypes,
     right: &GraphPattern,
     input_types: &VariableTypes,
+    statistics: &dyn Statistics,
 ) -> usize {
-    estimate_graph_pattern_size(left, input_types)
-        .saturating_mul(estimate_graph_pattern_size(right, left_types))
+    estimate_graph_pattern_size(left, input_types, statistics)
+        .saturating_mul(estimate_graph_pattern_size(right, left_types, statistics))
 }
 
 fn estimate_triple_pattern_size(
@@ -1014,28 +1065,41 @@ fn estimate_triple_pattern_size(
     }
 }
 
-fn estimate_path_size(start_bound: bool, path: &PropertyPathExpression, end_bound: bool) -> usize {
+fn estimate_path_size(
+    start_bound: bool,
+    path: &PropertyPathExpression,
+    end_bound: bool,
+    statistics: &dyn Statistics,
+) -> usize {
     match path {
-        PropertyPathExpression::NamedNode(_) => {
-            estimate_triple_pattern_size(start_bound, true, end_bound)
+        PropertyPathExpression::NamedNode(p) => statistics
+            .triple_pattern_cardinality(
+                bound_statistics_term(start_bound),
+                PatternTerm::Constant(p.as_ref().into()),
+                bound_statistics_term(end_bound),
+            )
+            .unwrap_or_else(|| estimate_triple_pattern_size(start_bound, true, end_bound)),
+        PropertyPathExpression::Reverse(p) => {
+            estimate_path_size(end_bound, p, start_bound, statistics)
         }
-        PropertyPathExpression::Reverse(p) => estimate_path_size(end_bound, p, start_bound),
         PropertyPathExpression::Sequence(a, b) => {
             // We do a for loop join in the best direction
             min(
-                estimate_path_size(start_bound, a, false)
-                    .saturating_mul(estimate_path_size(true, b, end_bound)),
-                estimate_path_size(start_bound, a, true)
-                    .saturating_mul(estimate_path_size(false, b, end_bound)),
+                estimate_path_size(start_bound, a, false, statistics)
+                    .saturating_mul(estimate_path_size(true, b, end_bound, statistics)),
+                estimate_path_size(start_bound, a, true, statistics)
+                    .saturating_mul(estimate_path_size(false, b, end_bound, statistics)),
             )
         }
-        PropertyPathExpression::Alternative(a, b) => estimate_path_size(start_bound, a, end_bound)
-            .saturating_add(estimate_path_size(start_bound, b, end_bound)),
+        PropertyPathExpression::Alternative(a, b) => {
+            estimate_path_size(start_bound, a, end_bound, statistics)
+                .saturating_add(estimate_path_size(start_bound, b, end_bound, statistics))
+        }
         PropertyPathExpression::ZeroOrMore(p) => {
             if start_bound && end_bound {
                 1
             } else if start_bound || end_bound {
-                estimate_path_size(start_bound, p, end_bound).saturating_mul(1000)
+                estimate_path_size(start_bound, p, end_bound, statistics).saturating_mul(1000)
             } else {
                 1_000_000_000
             }
@@ -1044,14 +1108,14 @@ fn estimate_path_size(start_bound: bool, path: &PropertyPathExpression, end_boun
             if start_bound && end_bound {
                 1
             } else {
-                estimate_path_size(start_bound, p, end_bound).saturating_mul(1000)
+                estimate_path_size(start_bound, p, end_bound, statistics).saturating_mul(1000)
             }
         }
         PropertyPathExpression::ZeroOrOne(p) => {
             if start_bound && end_bound {
                 1
             } else if start_bound || end_bound {
-                estimate_path_size(start_bound, p, end_bound)
+                estimate_path_size(start_bound, p, end_bound, statistics)
             } else {
                 1_000_000_000
             }
@@ -1062,6 +1126,41 @@ fn estimate_path_size(start_bound: bool, path: &PropertyPathExpression, end_boun
     }
 }
 
+fn term_pattern_statistics_term<'a>(
+    pattern: &'a GroundTermPattern,
+    input_types: &VariableTypes,
+) -> PatternTerm<'a> {
+    match pattern {
+        GroundTermPattern::NamedNode(node) => PatternTerm::Constant(node.as_ref().into()),
+        GroundTermPattern::Literal(literal) => PatternTerm::Constant(literal.as_ref().into()),
+        GroundTermPattern::Variable(_) => {
+            bound_statistics_term(is_term_pattern_bound(pattern, input_types))
+        }
+        #[cfg(feature = "rdf-star")]
+        GroundTermPattern::Triple(_) => {
+            bound_statistics_term(is_term_pattern_bound(pattern, input_types))
+        }
+    }
+}
+
+fn named_node_pattern_statistics_term<'a>(
+    pattern: &'a NamedNodePattern,
+    input_types: &VariableTypes,
+) -> PatternTerm<'a> {
+    match pattern {
+        NamedNodePattern::NamedNode(node) => PatternTerm::Constant(node.as_ref().into()),
+        NamedNodePattern::Variable(v) => bound_statistics_term(!input_types.get(v).undef),
+    }
+}
+
+fn bound_statistics_term(is_bound: bool) -> PatternTerm<'static> {
+    if is_bound {
+        PatternTerm::Bound
+    } else {
+        PatternTerm::Free
+    }
+}
+
 fn is_term_pattern_bound(pattern: &GroundTermPattern, input_types: &VariableTypes) -> bool {
     match pattern {
         GroundTermPattern::NamedNode(_) | GroundTermPattern::Literal(_) => true,
@@ -0,0 +1,51 @@
+use oxrdf::TermRef;
+
+/// Cardinality statistics about the dataset a query is evaluated against.
+///
+/// The [`Optimizer`](crate::Optimizer) consults them when ordering joins
+/// and falls back to generic heuristics when no estimation is returned.
+pub trait Statistics {
+    /// Estimated number of triples matching the given triple pattern.
+    ///
+    /// Returns `None` if the implementation has no better estimation than the default heuristics.
+    fn triple_pattern_cardinality(
+        &self,
+        subject: PatternTerm<'_>,
+        predicate: PatternTerm<'_>,
+        object: PatternTerm<'_>,
+    ) -> Option<usize>;
+}
+
+/// A triple pattern position as seen by [`Statistics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternTerm<'a> {
+    /// A constant term written in the query.
+    Constant(TermRef<'a>),
+    /// A variable already bound when the pattern is evaluated.
+    Bound,
+    /// A variable not bound yet when the pattern is evaluated.
+    Free,
+}
+
+impl PatternTerm<'_> {
+    #[inline]
+    pub fn is_bound(&self) -> bool {
+        !matches!(self, Self::Free)
+    }
+}
+
+/// [`Statistics`] without any knowledge of the data, the optimizer only uses its heuristics.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoStatistics;
+
+impl Statistics for NoStatistics {
+    #[inline]
+    fn triple_pattern_cardinality(
+        &self,
+        _subject: PatternTerm<'_>,
+        _predicate: PatternTerm<'_>,
+        _object: PatternTerm<'_>,
+    ) -> Option<usize> {
+        None
+    }
+}